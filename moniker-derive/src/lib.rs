@@ -6,76 +6,388 @@ extern crate syn;
 extern crate synstructure;
 extern crate proc_macro2;
 
-use synstructure::{BindStyle, Structure};
+use proc_macro2::TokenStream;
+use synstructure::{BindStyle, BindingInfo, Structure, VariantInfo};
 
-decl_derive!([BoundTerm] => bound_term_derive);
+/// Options that can be supplied using `#[moniker(...)]` attributes on
+/// variants and fields
+///
+/// Options that are placed on a variant apply to all of its fields.
+#[derive(Debug, Copy, Clone, Default)]
+struct Options {
+    /// `#[moniker(ignore)]`: the field does not participate in name binding
+    /// or alpha equality, and does not need to implement the derived trait
+    /// (the strict equality traits compare it with `PartialEq` instead)
+    ignore: bool,
+    /// `#[moniker(embed)]`: the field of a pattern is a term
+    embed: bool,
+    /// `#[moniker(eq = "skip")]`: the field participates in name binding, but
+    /// is skipped when comparing for alpha equality
+    skip_eq: bool,
+    /// `#[moniker(shift)]`: the terms embedded in the field of a pattern skip
+    /// the innermost frame of binders
+    shift: bool,
+}
 
-fn bound_term_derive(mut s: Structure) -> proc_macro2::TokenStream {
-    s.bind_with(|_| BindStyle::Ref);
-    let term_eq_body = {
-        let body = s.variants().iter().fold(quote!(), |acc, v| {
-            // Create two sets of bindings, one for the lhs, and another for the rhs
-            let mut lhs = v.clone();
-            let mut rhs = v.clone();
-            lhs.binding_name(|_, i| {
-                syn::Ident::new(
-                    &format!("__binding_lhs_{}", i),
-                    proc_macro2::Span::call_site(),
-                )
-            });
-            rhs.binding_name(|_, i| {
-                syn::Ident::new(
-                    &format!("__binding_rhs_{}", i),
-                    proc_macro2::Span::call_site(),
-                )
-            });
+impl Options {
+    fn parse(attrs: &[syn::Attribute]) -> Options {
+        let mut options = Options::default();
 
-            let lhs_pat = lhs.pat();
-            let rhs_pat = rhs.pat();
+        for attr in attrs {
+            let meta = match attr.interpret_meta() {
+                Some(syn::Meta::List(ref meta)) if meta.ident == "moniker" => meta.clone(),
+                Some(_) | None => continue,
+            };
 
-            // build up the alpha-equality expression for this variant
-            let arm_body = <_>::zip(lhs.bindings().iter(), rhs.bindings()).fold(
-                quote!(true),
-                |acc, (lhs, rhs)| {
-                    quote! { #acc && moniker::BoundTerm::<String>::term_eq(#lhs, #rhs) }
-                },
-            );
+            for nested in &meta.nested {
+                match *nested {
+                    syn::NestedMeta::Meta(syn::Meta::Word(ref ident)) if ident == "ignore" => {
+                        options.ignore = true;
+                    },
+                    syn::NestedMeta::Meta(syn::Meta::Word(ref ident)) if ident == "embed" => {
+                        options.embed = true;
+                    },
+                    syn::NestedMeta::Meta(syn::Meta::Word(ref ident)) if ident == "shift" => {
+                        options.shift = true;
+                    },
+                    // Handled by `where_predicates`
+                    syn::NestedMeta::Meta(syn::Meta::NameValue(ref name_value))
+                        if name_value.ident == "bound" => {},
+                    syn::NestedMeta::Meta(syn::Meta::NameValue(ref name_value))
+                        if name_value.ident == "eq" =>
+                    {
+                        match name_value.lit {
+                            syn::Lit::Str(ref lit) if lit.value() == "skip" => {
+                                options.skip_eq = true;
+                            },
+                            _ => panic!("expected `#[moniker(eq = \"skip\")]`"),
+                        }
+                    },
+                    _ => panic!("unknown option in `#[moniker(...)]` attribute"),
+                }
+            }
+        }
+
+        options
+    }
+
+    /// Combine the options of a variant with the options of one of its fields
+    fn merge(self, other: Options) -> Options {
+        Options {
+            ignore: self.ignore || other.ignore,
+            embed: self.embed || other.embed,
+            skip_eq: self.skip_eq || other.skip_eq,
+            shift: self.shift || other.shift,
+        }
+    }
+}
+
+/// Build the where clause predicates for a derived impl
+///
+/// If the type has a `#[moniker(bound = "...")]` attribute then the supplied
+/// predicates are used. Otherwise we bound each of the type parameters that are
+/// referenced in the fields of the type by the trait returned by `bound_for`.
+/// We avoid bounding the types of the fields themselves, because these are
+/// often recursive, which would result in the impls never applying.
+fn where_predicates(
+    s: &Structure,
+    bound_for: impl Fn(Options) -> Option<TokenStream>,
+) -> Vec<TokenStream> {
+    if let Some(predicates) = bound_attr_predicates(s) {
+        return predicates;
+    }
+
+    let mut predicates = Vec::new();
+    let mut seen = Vec::new();
+    for v in s.variants() {
+        let variant_options = Options::parse(v.ast().attrs);
+        for bi in v.bindings() {
+            let bound = match bound_for(variant_options.merge(Options::parse(&bi.ast().attrs))) {
+                Some(bound) => bound,
+                None => continue,
+            };
+            for param in bi.referenced_ty_params() {
+                let predicate = quote!{ #param: #bound };
+                if !seen.contains(&predicate.to_string()) {
+                    seen.push(predicate.to_string());
+                    predicates.push(predicate);
+                }
+            }
+        }
+    }
+    predicates
+}
+
+/// The where clause predicates supplied by a `#[moniker(bound = "...")]`
+/// attribute on the type, if there is one
+fn bound_attr_predicates(s: &Structure) -> Option<Vec<TokenStream>> {
+    for attr in &s.ast().attrs {
+        let meta = match attr.interpret_meta() {
+            Some(syn::Meta::List(ref meta)) if meta.ident == "moniker" => meta.clone(),
+            Some(_) | None => continue,
+        };
+
+        for nested in &meta.nested {
+            if let syn::NestedMeta::Meta(syn::Meta::NameValue(ref name_value)) = *nested {
+                if name_value.ident == "bound" {
+                    let bound = match name_value.lit {
+                        syn::Lit::Str(ref lit) => lit.value(),
+                        _ => panic!("expected `#[moniker(bound = \"...\")]`"),
+                    };
+                    let where_clause = syn::parse_str::<syn::WhereClause>(&format!("where {}", bound))
+                        .expect("invalid where clause predicates in `#[moniker(bound = \"...\")]`");
+
+                    return Some(where_clause.predicates.iter().map(|pred| quote!(#pred)).collect());
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Build the where clause predicates for a derived impl by bounding the types
+/// of each of the fields that are kept by the `keep` predicate by `bound`
+///
+/// This is used when the trait has extra type parameters that can only be
+/// chosen by the impls of the field types. Recursive types must break the
+/// cycle with a hand-written impl somewhere, otherwise the derived impl will
+/// never apply. The predicates can be overridden with a
+/// `#[moniker(bound = "...")]` attribute.
+fn field_type_predicates(
+    s: &Structure,
+    keep: impl Fn(Options) -> bool,
+    bound: TokenStream,
+) -> Vec<TokenStream> {
+    if let Some(predicates) = bound_attr_predicates(s) {
+        return predicates;
+    }
+
+    let mut predicates = Vec::new();
+    let mut seen = Vec::new();
+    for v in s.variants() {
+        let variant_options = Options::parse(v.ast().attrs);
+        for bi in v.bindings() {
+            if !keep(variant_options.merge(Options::parse(&bi.ast().attrs))) {
+                continue;
+            }
+            let ty = &bi.ast().ty;
+            let predicate = quote!{ #ty: #bound };
+            if !seen.contains(&predicate.to_string()) {
+                seen.push(predicate.to_string());
+                predicates.push(predicate);
+            }
+        }
+    }
+    predicates
+}
 
-            quote! { #acc (&#lhs_pat, &#rhs_pat) => #arm_body, }
+/// Build the arms of a `match` on a single value, calling `f` on each of the
+/// fields that are kept by the `keep` predicate
+fn each_field(
+    s: &Structure,
+    keep: impl Fn(Options) -> bool,
+    mut f: impl FnMut(&BindingInfo, Options) -> TokenStream,
+) -> TokenStream {
+    s.variants().iter().fold(quote!(), |acc, v| {
+        let variant_options = Options::parse(v.ast().attrs);
+        let field_options = |bi: &BindingInfo| variant_options.merge(Options::parse(&bi.ast().attrs));
+
+        // NOTE: We don't use `VariantInfo::filter` here, because it results
+        // in the wrong fields being bound in tuple variants
+        let arm = v.each(|bi| match field_options(bi) {
+            options if keep(options) => f(bi, options),
+            _ => quote!(),
+        });
+
+        quote! { #acc #arm }
+    })
+}
+
+/// Build the arms of a `match` on a pair of values, combining the comparisons
+/// of each of the fields that are kept by the `keep` predicate
+fn each_field_eq(
+    s: &Structure,
+    keep: impl Fn(Options) -> bool,
+    mut f: impl FnMut(&BindingInfo, &BindingInfo, Options) -> TokenStream,
+) -> TokenStream {
+    each_field_pair(s, keep, quote!(true), quote!(false), |acc, _, lhs, rhs, options| {
+        let field_eq = f(lhs, rhs, options);
+        quote! { #acc && #field_eq }
+    })
+}
+
+/// Build the arms of a `match` on a pair of values, folding `combine` over
+/// each of the fields that are kept by the `keep` predicate, starting from
+/// `init`
+///
+/// The `mismatch` expression is used when the values have different variants.
+/// `combine` is also passed the name of each field, qualified by the name of
+/// its variant if the type is an enum, for use in diagnostics.
+fn each_field_pair(
+    s: &Structure,
+    keep: impl Fn(Options) -> bool,
+    init: TokenStream,
+    mismatch: TokenStream,
+    mut combine: impl FnMut(TokenStream, &str, &BindingInfo, &BindingInfo, Options) -> TokenStream,
+) -> TokenStream {
+    let is_enum = match s.ast().data {
+        syn::Data::Enum(_) => true,
+        syn::Data::Struct(_) | syn::Data::Union(_) => false,
+    };
+
+    let body = s.variants().iter().fold(quote!(), |acc, v| {
+        let variant_options = Options::parse(v.ast().attrs);
+        let field_options = |bi: &BindingInfo| variant_options.merge(Options::parse(&bi.ast().attrs));
+
+        // Create two sets of bindings, one for the lhs, and another for the rhs
+        let mut lhs: VariantInfo = v.clone();
+        let mut rhs: VariantInfo = v.clone();
+        lhs.binding_name(|_, i| {
+            syn::Ident::new(
+                &format!("__binding_lhs_{}", i),
+                proc_macro2::Span::call_site(),
+            )
+        });
+        rhs.binding_name(|_, i| {
+            syn::Ident::new(
+                &format!("__binding_rhs_{}", i),
+                proc_macro2::Span::call_site(),
+            )
         });
 
-        // Avoid the 'unreachable match' warning for types with zero or one variants
-        match s.variants().len() {
-            0 | 1 => body,
-            _ => quote! { #body (_, _) => false },
+        let lhs_pat = lhs.pat();
+        let rhs_pat = rhs.pat();
+
+        // build up the combined expression for this variant
+        let arm_body = <_>::zip(lhs.bindings().iter(), rhs.bindings())
+            .enumerate()
+            .fold(init.clone(), |acc, (i, (lhs, rhs))| match field_options(lhs) {
+                options if keep(options) => {
+                    let field_name = match lhs.ast().ident {
+                        Some(ref ident) => ident.to_string(),
+                        None => i.to_string(),
+                    };
+                    let name = if is_enum {
+                        format!("{}.{}", v.ast().ident, field_name)
+                    } else {
+                        field_name
+                    };
+                    combine(acc, &name, lhs, rhs, options)
+                },
+                _ => acc,
+            });
+
+        quote! { #acc (&#lhs_pat, &#rhs_pat) => #arm_body, }
+    });
+
+    // Avoid the 'unreachable match' warning for types with zero or one variants
+    match s.variants().len() {
+        0 | 1 => body,
+        _ => quote! { #body (_, _) => #mismatch },
+    }
+}
+
+/// Build the arms of a `match` on a pair of values, finding the first of the
+/// fields that are kept by the `keep` predicate where the values differ, using
+/// the expressions returned by `f` to compare each pair of fields
+fn each_field_diff(
+    s: &Structure,
+    keep: impl Fn(Options) -> bool,
+    mut f: impl FnMut(&BindingInfo, &BindingInfo, Options) -> TokenStream,
+) -> TokenStream {
+    // Values with different variants are rendered as just their constructors
+    let constructor_body = s.each_variant(|v| {
+        let name = match v.ast().fields {
+            syn::Fields::Named(_) => format!("{} {{ .. }}", v.ast().ident),
+            syn::Fields::Unnamed(_) => format!("{}(..)", v.ast().ident),
+            syn::Fields::Unit => v.ast().ident.to_string(),
+        };
+        quote!(#name)
+    });
+    let mismatch = quote! {
+        Some(moniker::TermDiff::new(
+            match *self { #constructor_body },
+            match *other { #constructor_body },
+        ))
+    };
+
+    each_field_pair(s, keep, quote!(None), mismatch, |acc, name, lhs, rhs, options| {
+        let field_diff = f(lhs, rhs, options);
+        quote! { #acc.or_else(|| #field_diff.map(|__diff| __diff.within(#name))) }
+    })
+}
+
+decl_derive!([BoundTerm, attributes(moniker)] => bound_term_derive);
+
+fn bound_term_derive(mut s: Structure) -> TokenStream {
+    let binds = |options: Options| {
+        if options.embed {
+            panic!("`#[moniker(embed)]` can only be used when deriving `BoundPattern`");
         }
+        if options.shift {
+            panic!("`#[moniker(shift)]` can only be used when deriving `BoundPattern`");
+        }
+        !options.ignore
     };
+    let compares = |options: Options| binds(options) && !options.skip_eq;
+
+    s.bind_with(|_| BindStyle::Ref);
+    let term_eq_body = each_field_eq(&s, compares, |lhs, rhs, _| {
+        quote! { moniker::BoundTerm::<String>::term_eq(#lhs, #rhs) }
+    });
+    let term_diff_body = each_field_diff(&s, compares, |lhs, rhs, _| {
+        quote! { moniker::BoundTerm::<String>::term_diff(#lhs, #rhs) }
+    });
 
     s.bind_with(|_| BindStyle::RefMut);
-    let close_term_body = s.each(|bi| {
+    let close_term_body = each_field(&s, binds, |bi, _| {
         quote!{ moniker::BoundTerm::<String>::close_term(#bi, __state, __binders); }
     });
-    let open_term_body = s.each(|bi| {
+    let open_term_body = each_field(&s, binds, |bi, _| {
         quote!{ moniker::BoundTerm::<String>::open_term(#bi, __state, __binders); }
     });
 
     s.bind_with(|_| BindStyle::Ref);
-    let visit_vars_body = s.each(|bi| {
+    let visit_vars_body = each_field(&s, binds, |bi, _| {
         quote!{ moniker::BoundTerm::<String>::visit_vars(#bi, __on_var); }
     });
     s.bind_with(|_| BindStyle::RefMut);
-    let visit_mut_vars_body = s.each(|bi| {
+    let visit_mut_vars_body = each_field(&s, binds, |bi, _| {
         quote!{ moniker::BoundTerm::<String>::visit_mut_vars(#bi, __on_var); }
     });
+    s.bind_with(|_| BindStyle::Ref);
+    let walk_term_body = each_field(&s, binds, |bi, _| {
+        quote!{ moniker::BoundTerm::<String>::walk_term(#bi, __walker); }
+    });
+    s.bind_with(|_| BindStyle::RefMut);
+    let walk_mut_term_body = each_field(&s, binds, |bi, _| {
+        quote!{ moniker::BoundTerm::<String>::walk_mut_term(#bi, __walker); }
+    });
+
+    let predicates = where_predicates(&s, |options| {
+        if binds(options) {
+            Some(quote!(moniker::BoundTerm<String>))
+        } else {
+            None
+        }
+    });
 
+    // The bounds are supplied by `where_predicates`, so we remove the bindings
+    // to stop `gen_impl` from adding its own bounds to the field types
+    s.filter(|_| false);
     s.gen_impl(quote! {
         extern crate moniker;
 
-        gen impl moniker::BoundTerm<String> for @Self {
+        gen impl moniker::BoundTerm<String> for @Self where #(#predicates,)* {
             fn term_eq(&self, other: &Self) -> bool {
                 match (self, other) { #term_eq_body }
             }
 
+            fn term_diff(&self, other: &Self) -> Option<moniker::TermDiff> {
+                match (self, other) { #term_diff_body }
+            }
+
             fn close_term(
                 &mut self,
                 __state: moniker::ScopeState,
@@ -99,78 +411,147 @@ fn bound_term_derive(mut s: Structure) -> proc_macro2::TokenStream {
             fn visit_mut_vars(&mut self, __on_var: &mut impl FnMut(&mut moniker::Var<String>)) {
                 match *self { #visit_mut_vars_body }
             }
+
+            fn walk_term(&self, __walker: &mut impl moniker::Walker<String>) {
+                match *self { #walk_term_body }
+            }
+
+            fn walk_mut_term(&mut self, __walker: &mut impl moniker::WalkerMut<String>) {
+                match *self { #walk_mut_term_body }
+            }
         }
     })
 }
 
-decl_derive!([BoundPattern] => bound_pattern_derive);
-
-fn bound_pattern_derive(mut s: Structure) -> proc_macro2::TokenStream {
-    s.bind_with(|_| BindStyle::Ref);
-    let pattern_eq_body = {
-        let body = s.variants().iter().fold(quote!(), |acc, v| {
-            // Create two sets of bindings, one for the lhs, and another for the rhs
-            let mut lhs = v.clone();
-            let mut rhs = v.clone();
-            lhs.binding_name(|_, i| {
-                syn::Ident::new(
-                    &format!("__binding_lhs_{}", i),
-                    proc_macro2::Span::call_site(),
-                )
-            });
-            rhs.binding_name(|_, i| {
-                syn::Ident::new(
-                    &format!("__binding_rhs_{}", i),
-                    proc_macro2::Span::call_site(),
-                )
-            });
-
-            let lhs_pat = lhs.pat();
-            let rhs_pat = rhs.pat();
+decl_derive!([BoundPattern, attributes(moniker)] => bound_pattern_derive);
 
-            // build up the alpha-equality expression for this variant
-            let arm_body = <_>::zip(lhs.bindings().iter(), rhs.bindings()).fold(
-                quote!(true),
-                |acc, (lhs, rhs)| {
-                    quote! { #acc && moniker::BoundPattern::<String>::pattern_eq(#lhs, #rhs) }
-                },
-            );
+fn bound_pattern_derive(mut s: Structure) -> TokenStream {
+    let binds = |options: Options| !options.ignore;
+    let compares = |options: Options| binds(options) && !options.skip_eq;
+    let has_binders = |options: Options| binds(options) && !options.embed;
 
-            quote! { #acc (&#lhs_pat, &#rhs_pat) => #arm_body, }
-        });
+    s.bind_with(|_| BindStyle::Ref);
+    let pattern_eq_body = each_field_eq(&s, compares, |lhs, rhs, options| {
+        if options.embed {
+            quote! { moniker::BoundTerm::<String>::term_eq(#lhs, #rhs) }
+        } else {
+            quote! { moniker::BoundPattern::<String>::pattern_eq(#lhs, #rhs) }
+        }
+    });
+    let pattern_diff_body = each_field_diff(&s, compares, |lhs, rhs, options| {
+        if options.embed {
+            quote! { moniker::BoundTerm::<String>::term_diff(#lhs, #rhs) }
+        } else {
+            quote! { moniker::BoundPattern::<String>::pattern_diff(#lhs, #rhs) }
+        }
+    });
 
-        // Avoid the 'unreachable match' warning for types with zero or one variants
-        match s.variants().len() {
-            0 | 1 => body,
-            _ => quote! { #body (_, _) => false },
+    // The state to close and open each field with
+    let field_state = |options: Options| {
+        if options.shift {
+            quote!(__state.shift())
+        } else {
+            quote!(__state)
         }
     };
 
     s.bind_with(|_| BindStyle::RefMut);
-    let close_pattern_body = s.each(|bi| {
-        quote!{ moniker::BoundPattern::<String>::close_pattern(#bi, __state, __binders); }
+    let close_pattern_body = each_field(&s, binds, |bi, options| {
+        let state = field_state(options);
+        if options.embed {
+            quote!{
+                if let Some(__state) = #state.outer_term() {
+                    moniker::BoundTerm::<String>::close_term(#bi, __state, __binders);
+                }
+            }
+        } else {
+            quote!{ moniker::BoundPattern::<String>::close_pattern(#bi, #state, __binders); }
+        }
     });
-    let open_pattern_body = s.each(|bi| {
-        quote!{ moniker::BoundPattern::<String>::open_pattern(#bi, __state, __binders); }
+    let open_pattern_body = each_field(&s, binds, |bi, options| {
+        let state = field_state(options);
+        if options.embed {
+            quote!{
+                if let Some(__state) = #state.outer_term() {
+                    moniker::BoundTerm::<String>::open_term(#bi, __state, __binders);
+                }
+            }
+        } else {
+            quote!{ moniker::BoundPattern::<String>::open_pattern(#bi, #state, __binders); }
+        }
     });
 
     s.bind_with(|_| BindStyle::Ref);
-    let visit_binders_body = s.each(|bi| {
+    let visit_binders_body = each_field(&s, has_binders, |bi, _| {
         quote!{ moniker::BoundPattern::<String>::visit_binders(#bi, __on_binder); }
     });
     s.bind_with(|_| BindStyle::RefMut);
-    let visit_mut_binders_body = s.each(|bi| {
+    let visit_mut_binders_body = each_field(&s, has_binders, |bi, _| {
         quote!{ moniker::BoundPattern::<String>::visit_mut_binders(#bi, __on_binder); }
     });
+    // The statements for walking each field, using the given walk methods
+    let walk_field = |walk_term, walk_pattern, walker| {
+        move |bi: &BindingInfo, options: Options| {
+            let walk = if options.embed {
+                quote!{
+                    if __terms.outer() {
+                        moniker::BoundTerm::<String>::#walk_term(#bi, __walker);
+                    }
+                }
+            } else {
+                quote!{ moniker::BoundPattern::<String>::#walk_pattern(#bi, __terms, __walker); }
+            };
+
+            if options.shift {
+                quote!{
+                    moniker::#walker::<String>::enter_shift(__walker);
+                    #walk
+                    moniker::#walker::<String>::exit_shift(__walker);
+                }
+            } else {
+                walk
+            }
+        }
+    };
+
+    s.bind_with(|_| BindStyle::Ref);
+    let walk_pattern_body = each_field(
+        &s,
+        binds,
+        walk_field(quote!(walk_term), quote!(walk_pattern), quote!(Walker)),
+    );
+    s.bind_with(|_| BindStyle::RefMut);
+    let walk_mut_pattern_body = each_field(
+        &s,
+        binds,
+        walk_field(quote!(walk_mut_term), quote!(walk_mut_pattern), quote!(WalkerMut)),
+    );
+
+    let predicates = where_predicates(&s, |options| {
+        if !binds(options) {
+            None
+        } else if options.embed {
+            Some(quote!(moniker::BoundTerm<String>))
+        } else {
+            Some(quote!(moniker::BoundPattern<String>))
+        }
+    });
 
+    // The bounds are supplied by `where_predicates`, so we remove the bindings
+    // to stop `gen_impl` from adding its own bounds to the field types
+    s.filter(|_| false);
     s.gen_impl(quote! {
         extern crate moniker;
 
-        gen impl moniker::BoundPattern<String> for @Self {
+        gen impl moniker::BoundPattern<String> for @Self where #(#predicates,)* {
             fn pattern_eq(&self, other: &Self) -> bool {
                 match (self, other) { #pattern_eq_body }
             }
 
+            fn pattern_diff(&self, other: &Self) -> Option<moniker::TermDiff> {
+                match (self, other) { #pattern_diff_body }
+            }
+
             fn close_pattern(
                 &mut self,
                 __state: moniker::ScopeState,
@@ -197,6 +578,331 @@ fn bound_pattern_derive(mut s: Structure) -> proc_macro2::TokenStream {
             ) {
                 match *self { #visit_mut_binders_body }
             }
+
+            fn walk_pattern(
+                &self,
+                __terms: moniker::PatternTerms,
+                __walker: &mut impl moniker::Walker<String>,
+            ) {
+                match *self { #walk_pattern_body }
+            }
+
+            fn walk_mut_pattern(
+                &mut self,
+                __terms: moniker::PatternTerms,
+                __walker: &mut impl moniker::WalkerMut<String>,
+            ) {
+                match *self { #walk_mut_pattern_body }
+            }
+        }
+    })
+}
+
+decl_derive!([Unify, attributes(moniker)] => unify_derive);
+
+fn unify_derive(mut s: Structure) -> TokenStream {
+    let binds = |options: Options| !options.ignore;
+    let unifies = |options: Options| binds(options) && !options.skip_eq;
+
+    s.bind_with(|_| BindStyle::Ref);
+    let unify_body = each_field_pair(
+        &s,
+        unifies,
+        quote!(Ok(())),
+        quote!(Err(moniker::UnifyError::Mismatch)),
+        |acc, _, lhs, rhs, _| {
+            quote! {
+                #acc.and_then(|()| moniker::Unify::<String, __T>::unify(#lhs, #rhs, __unifier))
+            }
+        },
+    );
+
+    s.bind_with(|_| BindStyle::RefMut);
+    let zonk_body = each_field(&s, binds, |bi, _| {
+        quote!{ moniker::Unify::<String, __T>::zonk(#bi, __unifier); }
+    });
+
+    s.bind_with(|_| BindStyle::Ref);
+    let visit_meta_vars_body = each_field(&s, binds, |bi, _| {
+        quote!{ moniker::Unify::<String, __T>::visit_meta_vars(#bi, __on_meta_var); }
+    });
+    s.bind_with(|_| BindStyle::RefMut);
+    let visit_mut_subterms_body = each_field(&s, binds, |bi, _| {
+        quote!{ moniker::Unify::<String, __T>::visit_mut_subterms(#bi, __on_subterm); }
+    });
+
+    // The meta-carrying type of the AST is only known to the impls of the
+    // field types, so we have to bound those rather than the type parameters
+    let predicates = field_type_predicates(&s, binds, quote!(moniker::Unify<String, __T>));
+
+    // The bounds are supplied by `field_type_predicates`, so we remove the
+    // bindings to stop `gen_impl` from adding its own bounds to the field types
+    s.filter(|_| false);
+    s.gen_impl(quote! {
+        extern crate moniker;
+
+        gen impl<__T> moniker::Unify<String, __T> for @Self where #(#predicates,)* {
+            fn unify(
+                &self,
+                other: &Self,
+                __unifier: &mut moniker::Unifier<String, __T>,
+            ) -> Result<(), moniker::UnifyError<String>> {
+                match (self, other) { #unify_body }
+            }
+
+            fn zonk(&mut self, __unifier: &moniker::Unifier<String, __T>) {
+                match *self { #zonk_body }
+            }
+
+            fn visit_meta_vars(
+                &self,
+                __on_meta_var: &mut impl FnMut(&moniker::MetaVar<String>),
+            ) {
+                match *self { #visit_meta_vars_body }
+            }
+
+            fn visit_mut_subterms(&mut self, __on_subterm: &mut impl FnMut(&mut __T)) {
+                match *self { #visit_mut_subterms_body }
+            }
+        }
+    })
+}
+
+decl_derive!([Node, attributes(moniker)] => node_derive);
+
+fn node_derive(mut s: Structure) -> TokenStream {
+    let binds = |options: Options| !options.ignore;
+    let compares = |options: Options| !options.ignore && !options.skip_eq;
+
+    let node_name_body = s.each_variant(|v| {
+        let name = v.ast().ident.to_string();
+        quote!(#name)
+    });
+
+    s.bind_with(|_| BindStyle::Ref);
+    let children_body = each_field(&s, binds, |bi, _| {
+        quote!{ __children.push(#bi); }
+    });
+    let eq_children_body = each_field(&s, compares, |bi, _| {
+        quote!{ __children.push(#bi); }
+    });
+    s.bind_with(|_| BindStyle::RefMut);
+    let children_mut_body = each_field(&s, binds, |bi, _| {
+        quote!{ __children.push(#bi); }
+    });
+
+    let predicates = where_predicates(&s, |options| {
+        if binds(options) {
+            Some(quote!(moniker::Node))
+        } else {
+            None
+        }
+    });
+
+    // The bounds are supplied by `where_predicates`, so we remove the bindings
+    // to stop `gen_impl` from adding its own bounds to the field types
+    s.filter(|_| false);
+    s.gen_impl(quote! {
+        extern crate moniker;
+        use std::any::Any as __Any;
+
+        gen impl moniker::Node for @Self where #(#predicates,)* {
+            fn node_name(&self) -> &'static str {
+                match *self { #node_name_body }
+            }
+
+            fn children(&self) -> Vec<&dyn moniker::Node> {
+                #[allow(unused_mut)]
+                let mut __children = Vec::<&dyn moniker::Node>::new();
+                match *self { #children_body }
+                __children
+            }
+
+            fn children_mut(&mut self) -> Vec<&mut dyn moniker::Node> {
+                #[allow(unused_mut)]
+                let mut __children = Vec::<&mut dyn moniker::Node>::new();
+                match *self { #children_mut_body }
+                __children
+            }
+
+            fn eq_children(&self) -> Vec<&dyn moniker::Node> {
+                #[allow(unused_mut)]
+                let mut __children = Vec::<&dyn moniker::Node>::new();
+                match *self { #eq_children_body }
+                __children
+            }
+
+            fn as_any(&self) -> &dyn __Any {
+                self
+            }
+
+            fn as_any_mut(&mut self) -> &mut dyn __Any {
+                self
+            }
+        }
+    })
+}
+
+decl_derive!([TermEqStrict, attributes(moniker)] => term_eq_strict_derive);
+
+fn term_eq_strict_derive(mut s: Structure) -> TokenStream {
+    // Ignored fields are compared too, but with their `PartialEq` impls
+    s.bind_with(|_| BindStyle::Ref);
+    let term_eq_strict_body = each_field_eq(&s, |_| true, |lhs, rhs, options| {
+        if options.ignore {
+            quote! { ::std::cmp::PartialEq::eq(#lhs, #rhs) }
+        } else {
+            quote! { moniker::TermEqStrict::term_eq_strict(#lhs, #rhs) }
+        }
+    });
+
+    let predicates = where_predicates(&s, |options| {
+        if options.ignore {
+            Some(quote!(::std::cmp::PartialEq))
+        } else {
+            Some(quote!(moniker::TermEqStrict))
+        }
+    });
+
+    // The bounds are supplied by `where_predicates`, so we remove the bindings
+    // to stop `gen_impl` from adding its own bounds to the field types
+    s.filter(|_| false);
+    s.gen_impl(quote! {
+        extern crate moniker;
+
+        gen impl moniker::TermEqStrict for @Self where #(#predicates,)* {
+            fn term_eq_strict(&self, other: &Self) -> bool {
+                match (self, other) { #term_eq_strict_body }
+            }
+        }
+    })
+}
+
+decl_derive!([PatternEqStrict, attributes(moniker)] => pattern_eq_strict_derive);
+
+fn pattern_eq_strict_derive(mut s: Structure) -> TokenStream {
+    // Ignored fields are compared too, but with their `PartialEq` impls
+    s.bind_with(|_| BindStyle::Ref);
+    let pattern_eq_strict_body = each_field_eq(&s, |_| true, |lhs, rhs, options| {
+        if options.ignore {
+            quote! { ::std::cmp::PartialEq::eq(#lhs, #rhs) }
+        } else if options.embed {
+            quote! { moniker::TermEqStrict::term_eq_strict(#lhs, #rhs) }
+        } else {
+            quote! { moniker::PatternEqStrict::pattern_eq_strict(#lhs, #rhs) }
+        }
+    });
+
+    let predicates = where_predicates(&s, |options| {
+        if options.ignore {
+            Some(quote!(::std::cmp::PartialEq))
+        } else if options.embed {
+            Some(quote!(moniker::TermEqStrict))
+        } else {
+            Some(quote!(moniker::PatternEqStrict))
+        }
+    });
+
+    // The bounds are supplied by `where_predicates`, so we remove the bindings
+    // to stop `gen_impl` from adding its own bounds to the field types
+    s.filter(|_| false);
+    s.gen_impl(quote! {
+        extern crate moniker;
+
+        gen impl moniker::PatternEqStrict for @Self where #(#predicates,)* {
+            fn pattern_eq_strict(&self, other: &Self) -> bool {
+                match (self, other) { #pattern_eq_strict_body }
+            }
+        }
+    })
+}
+
+decl_derive!([ArbitraryTerm, attributes(moniker)] => arbitrary_term_derive);
+
+fn arbitrary_term_derive(mut s: Structure) -> TokenStream {
+    // Each variant is generated if all of its fields can be generated in the
+    // context. Ignored fields are set to their default values.
+    let mut can_bind_body = quote!(false);
+    let push_variants_body = s.variants().iter().fold(quote!(), |acc, v| {
+        let variant_options = Options::parse(v.ast().attrs);
+        let mut fields = Vec::new();
+        let mut field_strategies = Vec::new();
+        let mut field_can_binds = Vec::new();
+
+        let construct = v.construct(|field, i| {
+            if variant_options.merge(Options::parse(&field.attrs)).ignore {
+                return quote! { ::std::default::Default::default() };
+            }
+
+            let ty = &field.ty;
+            let name = syn::Ident::new(&format!("__field_{}", i), proc_macro2::Span::call_site());
+            let strategy = quote! {
+                <#ty as moniker::arbitrary::ArbitraryTerm<String>>::arbitrary_term(__ctx)
+            };
+            let can_bind = quote! {
+                <#ty as moniker::arbitrary::ArbitraryTerm<String>>::can_bind(__ctx)
+            };
+            fields.push(name.clone());
+            field_strategies.push(strategy);
+            field_can_binds.push(can_bind);
+            quote!(#name)
+        });
+
+        // The variant binds something if all of its fields can be generated,
+        // and at least one of them binds something
+        let generated = field_strategies.iter().map(|strategy| quote!(#strategy.is_some()));
+        can_bind_body = quote! {
+            #can_bind_body || (true #(&& #generated)* && (false #(|| #field_can_binds)*))
+        };
+
+        let fields = &fields;
+        let strategy = if fields.is_empty() {
+            quote! { moniker::arbitrary::Just(()) }
+        } else {
+            quote! { (#(#fields,)*) }
+        };
+        let push = quote! {
+            __variants.push(moniker::arbitrary::Strategy::boxed(
+                moniker::arbitrary::Strategy::prop_map(#strategy, |(#(#fields,)*)| #construct),
+            ));
+        };
+
+        if fields.is_empty() {
+            quote! { #acc #push }
+        } else {
+            quote! {
+                #acc
+                if let (#(Some(#fields),)*) = (#(#field_strategies,)*) { #push }
+            }
+        }
+    });
+
+    let predicates = where_predicates(&s, |options| {
+        if options.ignore {
+            Some(quote!(::std::default::Default))
+        } else {
+            Some(quote!(moniker::arbitrary::ArbitraryTerm<String>))
+        }
+    });
+
+    // The bounds are supplied by `where_predicates`, so we remove the bindings
+    // to stop `gen_impl` from adding its own bounds to the field types
+    s.filter(|_| false);
+    s.gen_impl(quote! {
+        extern crate moniker;
+
+        gen impl moniker::arbitrary::ArbitraryTerm<String> for @Self where #(#predicates,)* {
+            fn arbitrary_term(
+                __ctx: &moniker::arbitrary::Context<String>,
+            ) -> Option<moniker::arbitrary::BoxedStrategy<Self>> {
+                let mut __variants = Vec::new();
+                #push_variants_body
+                moniker::arbitrary::one_of(__variants)
+            }
+
+            fn can_bind(__ctx: &moniker::arbitrary::Context<String>) -> bool {
+                #can_bind_body
+            }
         }
     })
 }
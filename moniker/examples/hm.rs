@@ -0,0 +1,294 @@
+//! An example of using the `moniker` library to implement Hindley-Milner type
+//! inference for the lambda calculus with let-polymorphism
+
+extern crate im;
+#[cfg_attr(test, macro_use)]
+extern crate moniker;
+
+use im::HashMap;
+use moniker::{Binder, BoundTerm, Embed, FreeVar, Scope, Var};
+use std::collections::HashSet;
+use std::rc::Rc;
+
+/// Types
+///
+/// ```text
+/// t ::= a             type variables
+///     | Int           integer types
+///     | t₁ -> t₂      function types
+/// ```
+#[derive(Debug, Clone, BoundTerm)]
+pub enum Type {
+    /// Type variables
+    Var(Var<String>),
+    /// Integers
+    Int,
+    /// Function types
+    Arrow(RcType, RcType),
+}
+
+/// Reference counted types
+#[derive(Debug, Clone, BoundTerm)]
+pub struct RcType {
+    pub inner: Rc<Type>,
+}
+
+impl From<Type> for RcType {
+    fn from(src: Type) -> RcType {
+        RcType {
+            inner: Rc::new(src),
+        }
+    }
+}
+
+impl RcType {
+    /// Apply a substitution to the free type variables of this type
+    fn substs(&self, subst: &Subst) -> RcType {
+        match *self.inner {
+            Type::Var(Var::Free(ref free_var)) => match subst.get(free_var) {
+                Some(ty) => ty.substs(subst),
+                None => self.clone(),
+            },
+            Type::Var(Var::Bound(_)) | Type::Int => self.clone(),
+            Type::Arrow(ref param, ref body) => {
+                RcType::from(Type::Arrow(param.substs(subst), body.substs(subst)))
+            },
+        }
+    }
+}
+
+/// Type schemes
+///
+/// ```text
+/// σ ::= ∀a₁ ... aₙ. t
+/// ```
+pub type Scheme = Scope<Vec<Binder<String>>, RcType>;
+
+/// Expressions
+///
+/// ```text
+/// e ::= x                     variables
+///     | n                     integer literals
+///     | \x => e               anonymous functions
+///     | e₁ e₂                 function application
+///     | let x = e₁ in e₂      let bindings
+/// ```
+#[derive(Debug, Clone, BoundTerm)]
+pub enum Expr {
+    /// Variables
+    Var(Var<String>),
+    /// Integer literals
+    Int(i32),
+    /// Lambda expressions
+    Lam(Scope<Binder<String>, RcExpr>),
+    /// Function application
+    App(RcExpr, RcExpr),
+    /// Let bindings
+    Let(Scope<(Binder<String>, Embed<RcExpr>), RcExpr>),
+}
+
+/// Reference counted expressions
+#[derive(Debug, Clone, BoundTerm)]
+pub struct RcExpr {
+    pub inner: Rc<Expr>,
+}
+
+impl From<Expr> for RcExpr {
+    fn from(src: Expr) -> RcExpr {
+        RcExpr {
+            inner: Rc::new(src),
+        }
+    }
+}
+
+/// A context containing the type schemes of the variables in scope
+type Context = HashMap<FreeVar<String>, Scheme>;
+
+/// A substitution from type variables to the types they have been solved to
+type Subst = HashMap<FreeVar<String>, RcType>;
+
+/// Create a fresh type variable
+fn fresh_ty() -> RcType {
+    RcType::from(Type::Var(Var::Free(FreeVar::fresh_named("a"))))
+}
+
+/// Unify two types, extending the substitution with the solved type variables
+pub fn unify(subst: &mut Subst, ty1: &RcType, ty2: &RcType) -> Result<(), String> {
+    let (ty1, ty2) = (ty1.substs(subst), ty2.substs(subst));
+
+    match (&*ty1.inner, &*ty2.inner) {
+        (&Type::Var(Var::Free(ref free_var1)), &Type::Var(Var::Free(ref free_var2)))
+            if free_var1 == free_var2 =>
+        {
+            Ok(())
+        },
+        (&Type::Var(Var::Free(ref free_var)), _) => bind(subst, free_var, &ty2),
+        (_, &Type::Var(Var::Free(ref free_var))) => bind(subst, free_var, &ty1),
+        (&Type::Int, &Type::Int) => Ok(()),
+        (&Type::Arrow(ref param1, ref body1), &Type::Arrow(ref param2, ref body2)) => {
+            unify(subst, param1, param2)?;
+            unify(subst, body1, body2)
+        },
+        (_, _) => Err(format!(
+            "type mismatch - found `{:?}` but expected `{:?}`",
+            ty1, ty2
+        )),
+    }
+}
+
+/// Solve a type variable, checking that it does not occur in its solution
+fn bind(subst: &mut Subst, free_var: &FreeVar<String>, ty: &RcType) -> Result<(), String> {
+    if ty.free_vars().contains(free_var) {
+        Err(format!("`{}` occurs in `{:?}`", free_var, ty))
+    } else {
+        subst.insert(free_var.clone(), ty.clone());
+        Ok(())
+    }
+}
+
+/// Generalize the type variables of a type that are not free in the context
+pub fn generalize(context: &Context, subst: &Subst, ty: &RcType) -> Scheme {
+    let context_free_vars = context
+        .values()
+        .flat_map(|scheme| scheme.unsafe_body.substs(subst).free_vars())
+        .collect::<HashSet<_>>();
+
+    moniker::generalize(&context_free_vars, ty.substs(subst))
+}
+
+/// Infer the type of an expression
+pub fn infer(context: &Context, subst: &mut Subst, expr: &RcExpr) -> Result<RcType, String> {
+    match *expr.inner {
+        Expr::Var(Var::Free(ref free_var)) => match context.get(free_var) {
+            Some(scheme) => Ok(moniker::instantiate_fresh((*scheme).clone()).1),
+            None => Err(format!("`{}` not found in `{:?}`", free_var, context)),
+        },
+        Expr::Var(Var::Bound(ref bound_var)) => {
+            panic!("encountered a bound variable: {}", bound_var)
+        },
+        Expr::Int(_) => Ok(RcType::from(Type::Int)),
+        Expr::Lam(ref scope) => {
+            let (Binder(free_var), body) = scope.clone().unbind();
+            let param_ty = fresh_ty();
+            let scheme = Scope::new(Vec::new(), param_ty.clone());
+            let body_ty = infer(&context.update(free_var, scheme), subst, &body)?;
+
+            Ok(RcType::from(Type::Arrow(param_ty, body_ty)))
+        },
+        Expr::App(ref fun, ref arg) => {
+            let fun_ty = infer(context, subst, fun)?;
+            let arg_ty = infer(context, subst, arg)?;
+            let ret_ty = fresh_ty();
+            unify(
+                subst,
+                &fun_ty,
+                &RcType::from(Type::Arrow(arg_ty, ret_ty.clone())),
+            )?;
+
+            Ok(ret_ty)
+        },
+        Expr::Let(ref scope) => {
+            let ((Binder(free_var), Embed(def)), body) = scope.clone().unbind();
+            let def_ty = infer(context, subst, &def)?;
+            let scheme = generalize(context, subst, &def_ty);
+
+            infer(&context.update(free_var, scheme), subst, &body)
+        },
+    }
+}
+
+/// Infer the type scheme of a top-level expression
+pub fn infer_scheme(expr: &RcExpr) -> Result<Scheme, String> {
+    let context = Context::new();
+    let mut subst = Subst::new();
+    let ty = infer(&context, &mut subst, expr)?;
+
+    Ok(generalize(&context, &subst, &ty))
+}
+
+#[test]
+fn test_infer_id() {
+    let var = |free_var: &FreeVar<String>| RcExpr::from(Expr::Var(Var::Free(free_var.clone())));
+    let ty_var = |free_var: &FreeVar<String>| RcType::from(Type::Var(Var::Free(free_var.clone())));
+    let arrow = |param, body| RcType::from(Type::Arrow(param, body));
+
+    let x = FreeVar::fresh_named("x");
+    let a = FreeVar::fresh_named("a");
+
+    // expr = \x => x
+    let expr = RcExpr::from(Expr::Lam(Scope::new(Binder(x.clone()), var(&x))));
+
+    assert_term_eq!(
+        infer_scheme(&expr).unwrap(),
+        Scope::new(vec![Binder(a.clone())], arrow(ty_var(&a), ty_var(&a))),
+    );
+}
+
+#[test]
+fn test_infer_let_polymorphism() {
+    let var = |free_var: &FreeVar<String>| RcExpr::from(Expr::Var(Var::Free(free_var.clone())));
+
+    let x = FreeVar::fresh_named("x");
+    let id = FreeVar::fresh_named("id");
+
+    // expr = let id = \x => x in id id 1
+    let expr = RcExpr::from(Expr::Let(Scope::new(
+        (
+            Binder(id.clone()),
+            Embed(RcExpr::from(Expr::Lam(Scope::new(
+                Binder(x.clone()),
+                var(&x),
+            )))),
+        ),
+        RcExpr::from(Expr::App(
+            RcExpr::from(Expr::App(var(&id), var(&id))),
+            RcExpr::from(Expr::Int(1)),
+        )),
+    )));
+
+    assert_term_eq!(
+        infer_scheme(&expr).unwrap(),
+        Scope::new(Vec::new(), RcType::from(Type::Int)),
+    );
+}
+
+#[test]
+fn test_generalize_binder_order() {
+    let ty_var = |free_var: &FreeVar<String>| RcType::from(Type::Var(Var::Free(free_var.clone())));
+    let arrow = |param, body| RcType::from(Type::Arrow(param, body));
+
+    let a = FreeVar::fresh_named("a");
+    let b = FreeVar::fresh_named("b");
+    let c = FreeVar::fresh_named("c");
+
+    // ty = b -> c -> b -> a
+    let ty = arrow(ty_var(&b), arrow(ty_var(&c), arrow(ty_var(&b), ty_var(&a))));
+
+    // `c` is free in the context, so it is not generalized
+    let context_free_vars = vec![c.clone()].into_iter().collect();
+    let scheme = moniker::generalize(&context_free_vars, ty.clone());
+
+    assert_eq!(
+        scheme.unsafe_pattern,
+        vec![Binder(b.clone()), Binder(a.clone())]
+    );
+
+    // instantiating the scheme results in fresh type variables, in the same
+    // order as the binders
+    let (free_vars, instantiated_ty) = moniker::instantiate_fresh(scheme);
+
+    assert_eq!(free_vars.len(), 2);
+    assert!(free_vars.iter().all(|fv| *fv != a && *fv != b));
+    assert_term_eq!(
+        instantiated_ty,
+        arrow(
+            ty_var(&free_vars[0]),
+            arrow(
+                ty_var(&c),
+                arrow(ty_var(&free_vars[0]), ty_var(&free_vars[1]))
+            ),
+        ),
+    );
+}
+
+fn main() {}
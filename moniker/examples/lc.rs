@@ -14,7 +14,7 @@ use std::rc::Rc;
 ///     | \x => e    anonymous functions
 ///     | e₁ e₂      function application
 /// ````
-#[derive(Debug, Clone, BoundTerm)]
+#[derive(Debug, Clone, BoundTerm, Node, TermEqStrict)]
 pub enum Expr {
     /// Variables
     Var(Var<String>),
@@ -25,7 +25,7 @@ pub enum Expr {
 }
 
 /// Reference counted expressions
-#[derive(Debug, Clone, BoundTerm)]
+#[derive(Debug, Clone, BoundTerm, Node, TermEqStrict)]
 pub struct RcExpr {
     pub inner: Rc<Expr>,
 }
@@ -89,4 +89,521 @@ fn test_eval() {
     assert_term_eq!(eval(&expr), RcExpr::from(Expr::Var(Var::Free(y.clone()))));
 }
 
+#[test]
+fn test_lift_free_vars() {
+    use moniker::{BoundTerm, Embed, FreeVar, OuterFreeVarError};
+
+    let var = |x: &FreeVar<String>| RcExpr::from(Expr::Var(Var::Free(x.clone())));
+    let app = |fun, arg| RcExpr::from(Expr::App(fun, arg));
+
+    let f = FreeVar::fresh_named("f");
+    let x = FreeVar::fresh_named("x");
+    let y = FreeVar::fresh_named("y");
+
+    // scope = \x => f x y
+    let scope = Scope::new(Binder(x.clone()), app(app(var(&f), var(&x)), var(&y)));
+    let (captured, lifted) = scope.lift_free_vars().unwrap();
+
+    assert_eq!(captured, vec![f.clone(), y.clone()]);
+    assert!(lifted.free_vars().is_empty());
+    assert!(lifted.is_locally_closed());
+
+    // lifted = \(f, y) x => f x y
+    let (f2, x2, y2) = (
+        FreeVar::fresh_named("f"),
+        FreeVar::fresh_named("x"),
+        FreeVar::fresh_named("y"),
+    );
+    assert_term_eq!(
+        lifted,
+        Scope::new(
+            (
+                vec![Binder(f2.clone()), Binder(y2.clone())],
+                Binder(x2.clone())
+            ),
+            app(app(var(&f2), var(&x2)), var(&y2)),
+        ),
+    );
+
+    // scope = \(x = f) => x y, where `f` is outside of the scope
+    let scope = Scope::new((Binder(x.clone()), Embed(var(&f))), app(var(&x), var(&y)));
+    assert_eq!(
+        scope.lift_free_vars().map(|(captured, _)| captured),
+        Err(OuterFreeVarError {
+            free_var: f.clone()
+        }),
+    );
+}
+
+#[test]
+fn test_structural_view() {
+    use moniker::{FreeVar, Node};
+
+    let var = |x: &FreeVar<String>| RcExpr::from(Expr::Var(Var::Free(x.clone())));
+    let lam =
+        |x: &FreeVar<String>, body| RcExpr::from(Expr::Lam(Scope::new(Binder(x.clone()), body)));
+    let app = |fun, arg| RcExpr::from(Expr::App(fun, arg));
+
+    let f = FreeVar::fresh_named("f");
+    let g = FreeVar::fresh_named("g");
+    let x = FreeVar::fresh_named("x");
+    let y = FreeVar::fresh_named("y");
+
+    // expr = (\x => x) (f y)
+    let mut expr = app(lam(&x, var(&x)), app(var(&f), var(&y)));
+
+    assert_eq!(moniker::size::<RcExpr>(&expr), 6);
+    assert_eq!(moniker::depth::<RcExpr>(&expr), 3);
+    assert_eq!(
+        moniker::subterms::<Expr>(&expr)
+            .map(|expr| expr.node_name())
+            .collect::<Vec<_>>(),
+        vec!["App", "Lam", "Var", "App", "Var", "Var"],
+    );
+    assert_eq!(
+        moniker::find_subterms(&expr, |var: &Var<String>| match *var {
+            Var::Free(_) => true,
+            Var::Bound(_) => false,
+        }),
+        vec![&Var::Free(f.clone()), &Var::Free(y.clone())],
+    );
+
+    // rename `f` to `g` in place
+    moniker::visit_nodes_mut(&mut expr, &mut |node| {
+        if let Some(var) = node.downcast_mut::<Var<String>>() {
+            if *var == Var::Free(f.clone()) {
+                *var = Var::Free(g.clone());
+            }
+        }
+    });
+    assert_term_eq!(expr, app(lam(&x, var(&x)), app(var(&g), var(&y))));
+}
+
+#[test]
+fn test_term_diff() {
+    use moniker::{BoundTerm, FreeVar, TermDiff};
+
+    let var = |x: &FreeVar<String>| RcExpr::from(Expr::Var(Var::Free(x.clone())));
+    let lam =
+        |x: &FreeVar<String>, body| RcExpr::from(Expr::Lam(Scope::new(Binder(x.clone()), body)));
+    let app = |fun, arg| RcExpr::from(Expr::App(fun, arg));
+
+    let f = FreeVar::fresh_named("f");
+    let x = FreeVar::fresh_named("x");
+    let y = FreeVar::fresh_named("y");
+
+    // alpha equivalent terms have no difference
+    assert_eq!(lam(&x, var(&x)).term_diff(&lam(&y, var(&y))), None);
+
+    // `\x => f x` and `\y => f x` differ at the bound and free `x`
+    let diff = lam(&x, app(var(&f), var(&x)))
+        .term_diff(&lam(&y, app(var(&f), var(&x))))
+        .unwrap();
+    assert_eq!(
+        diff.path_string(),
+        "inner.Lam.0.body.inner.App.1.inner.Var.0"
+    );
+    assert_eq!(diff.lhs, "x@0.0");
+    assert_eq!(diff.rhs, x.to_string());
+
+    // terms with different constructors are rendered as just the constructors
+    assert_eq!(
+        app(var(&f), var(&x)).term_diff(&lam(&x, var(&x))),
+        Some(TermDiff::new("App(..)", "Lam(..)").within("inner")),
+    );
+}
+
+#[test]
+fn test_term_eq_witness() {
+    use moniker::FreeVar;
+
+    let var = |x: &FreeVar<String>| RcExpr::from(Expr::Var(Var::Free(x.clone())));
+    let lam =
+        |x: &FreeVar<String>, body| RcExpr::from(Expr::Lam(Scope::new(Binder(x.clone()), body)));
+    let app = |fun, arg| RcExpr::from(Expr::App(fun, arg));
+
+    let f = FreeVar::fresh_named("f");
+    let x = FreeVar::fresh_named("x");
+    let y = FreeVar::fresh_named("y");
+    let a = FreeVar::fresh_named("a");
+    let b = FreeVar::fresh_named("b");
+
+    // \x => \y => f x y
+    let old_expr = lam(&x, lam(&y, app(app(var(&f), var(&x)), var(&y))));
+    // \a => \b => f a b
+    let new_expr = lam(&a, lam(&b, app(app(var(&f), var(&a)), var(&b))));
+
+    assert_eq!(
+        moniker::term_eq_witness(&old_expr, &new_expr),
+        Some(vec![
+            (Binder(x.clone()), Binder(a.clone())),
+            (Binder(y.clone()), Binder(b.clone())),
+        ]),
+    );
+    assert_eq!(moniker::term_eq_witness(&old_expr, &lam(&a, var(&a))), None);
+
+    /// An expression with an example that is not compared
+    #[derive(Debug, Clone, BoundTerm, Node)]
+    struct Example {
+        #[moniker(eq = "skip")]
+        example: RcExpr,
+        expr: RcExpr,
+    }
+
+    // the binders of skipped fields are not paired up
+    assert_eq!(
+        moniker::term_eq_witness(
+            &Example {
+                example: lam(&x, var(&x)),
+                expr: lam(&y, var(&y)),
+            },
+            &Example {
+                example: var(&f),
+                expr: lam(&b, var(&b)),
+            },
+        ),
+        Some(vec![(Binder(y.clone()), Binder(b.clone()))]),
+    );
+
+    // f x y and f a b, after being opened separately
+    let old_body = app(app(var(&f), var(&x)), var(&y));
+    let new_body = app(app(var(&f), var(&a)), var(&b));
+    let renaming = moniker::term_eq_renaming(&old_body, &new_body).unwrap();
+    assert_eq!(renaming.len(), 3);
+    assert_eq!(renaming[&f], f);
+    assert_eq!(renaming[&x], a);
+    assert_eq!(renaming[&y], b);
+
+    // the renaming must be a bijection
+    let old_body = app(var(&x), var(&y));
+    let new_body = app(var(&a), var(&a));
+    assert_eq!(moniker::term_eq_renaming(&old_body, &new_body), None);
+    assert_eq!(moniker::term_eq_renaming(&new_body, &old_body), None);
+}
+
+#[test]
+fn test_term_eq_strict() {
+    use moniker::{FreeVar, TermEqStrict};
+
+    let var = |x: &FreeVar<String>| RcExpr::from(Expr::Var(Var::Free(x.clone())));
+    let lam =
+        |x: &FreeVar<String>, body| RcExpr::from(Expr::Lam(Scope::new(Binder(x.clone()), body)));
+
+    let x1 = FreeVar::fresh_named("x");
+    let x2 = FreeVar::fresh_named("x");
+    let y = FreeVar::fresh_named("y");
+
+    // binders with the same names are strictly equal, even if they are
+    // different free variables
+    assert!(lam(&x1, var(&x1)).term_eq_strict(&lam(&x2, var(&x2))));
+    // alpha equivalent terms with different names are not
+    assert_term_eq!(lam(&x1, var(&x1)), lam(&y, var(&y)));
+    assert!(!lam(&x1, var(&x1)).term_eq_strict(&lam(&y, var(&y))));
+    // free variables are compared by their identities
+    assert!(var(&x1).term_eq_strict(&var(&x1)));
+    assert!(!var(&x1).term_eq_strict(&var(&x2)));
+}
+
+#[test]
+fn test_canonicalize() {
+    use moniker::FreeVar;
+
+    let var = |x: &FreeVar<String>| RcExpr::from(Expr::Var(Var::Free(x.clone())));
+    let lam =
+        |x: &FreeVar<String>, body| RcExpr::from(Expr::Lam(Scope::new(Binder(x.clone()), body)));
+    let app = |fun, arg| RcExpr::from(Expr::App(fun, arg));
+
+    // \x => f x, where `f` is generated before the binder
+    let expr1 = {
+        let f = FreeVar::fresh_named("f");
+        let x = FreeVar::fresh_named("x");
+        lam(&x, app(var(&f), var(&x)))
+    };
+    // \x => f x, where `f` is generated after the binder
+    let expr2 = {
+        let x = FreeVar::fresh_named("x");
+        let f = FreeVar::fresh_named("f");
+        lam(&x, app(var(&f), var(&x)))
+    };
+
+    assert_ne!(format!("{:?}", expr1), format!("{:?}", expr2));
+
+    let expr1 = moniker::canonicalize(expr1);
+    let expr2 = moniker::canonicalize(expr2);
+    assert_eq!(format!("{:?}", expr1), format!("{:?}", expr2));
+
+    // the binder comes first, followed by the free variable
+    match *expr1.inner {
+        Expr::Lam(ref scope) => match *scope.unsafe_body.inner {
+            Expr::App(ref fun, _) => match *fun.inner {
+                Expr::Var(ref var) => assert_eq!(var.to_string(), "f$c1"),
+                _ => panic!("expected a variable"),
+            },
+            _ => panic!("expected an application"),
+        },
+        _ => panic!("expected a lambda"),
+    }
+
+    // the binders still have their own names
+    let x = FreeVar::fresh_named("x");
+    let y = FreeVar::fresh_named("y");
+    let expr1 = moniker::canonicalize(lam(&x, var(&x)));
+    let expr2 = moniker::canonicalize(lam(&y, var(&y)));
+    assert_ne!(format!("{:?}", expr1), format!("{:?}", expr2));
+
+    // unless they are renamed too
+    let name = |index| format!("x{}", index);
+    let expr1 = moniker::canonicalize_with_names(lam(&x, var(&x)), name);
+    let expr2 = moniker::canonicalize_with_names(lam(&y, var(&y)), name);
+    assert_eq!(format!("{:?}", expr1), format!("{:?}", expr2));
+
+    // the bound variables are renamed to match their binders
+    match *expr1.inner {
+        Expr::Lam(ref scope) => match *scope.unsafe_body.inner {
+            Expr::Var(ref var) => assert_eq!(var.to_string(), "x0@0.0"),
+            _ => panic!("expected a variable"),
+        },
+        _ => panic!("expected a lambda"),
+    }
+}
+
+#[test]
+fn test_term_fingerprint() {
+    use moniker::FreeVar;
+
+    let var = |x: &FreeVar<String>| RcExpr::from(Expr::Var(Var::Free(x.clone())));
+    let lam =
+        |x: &FreeVar<String>, body| RcExpr::from(Expr::Lam(Scope::new(Binder(x.clone()), body)));
+    let app = |fun, arg| RcExpr::from(Expr::App(fun, arg));
+    // free variables are identified by their names, as if they were globals
+    let fingerprint = |expr: &RcExpr| {
+        moniker::term_fingerprint(expr, |free_var: &FreeVar<String>| {
+            free_var.pretty_name.clone().unwrap()
+        })
+    };
+
+    let f1 = FreeVar::fresh_named("f");
+    let f2 = FreeVar::fresh_named("f");
+    let g = FreeVar::fresh_named("g");
+    let x = FreeVar::fresh_named("x");
+    let y = FreeVar::fresh_named("y");
+
+    // \x => f x
+    let expr = lam(&x, app(var(&f1), var(&x)));
+
+    // alpha equivalent terms with the same keys have the same fingerprint
+    assert_eq!(
+        fingerprint(&expr),
+        fingerprint(&lam(&y, app(var(&f2), var(&y))))
+    );
+    assert_ne!(
+        fingerprint(&expr),
+        fingerprint(&lam(&y, app(var(&g), var(&y))))
+    );
+    assert_ne!(
+        fingerprint(&expr),
+        fingerprint(&lam(&y, app(var(&y), var(&f1))))
+    );
+
+    // the encoding is stable, so changes to it must bump the version
+    assert_eq!(moniker::FINGERPRINT_VERSION, 1);
+    assert_eq!(
+        fingerprint(&expr),
+        0x1771_7c48_eab7_280c_4d8c_b8b6_36e8_68bc
+    );
+}
+
+#[test]
+fn test_laws() {
+    use moniker::laws;
+    use moniker::{BoundTerm, FrameKind, FreeVar, ScopeState, Walker, WalkerMut};
+
+    let var = |x: &FreeVar<String>| RcExpr::from(Expr::Var(Var::Free(x.clone())));
+    let lam =
+        |x: &FreeVar<String>, body| RcExpr::from(Expr::Lam(Scope::new(Binder(x.clone()), body)));
+    let app = |fun, arg| RcExpr::from(Expr::App(fun, arg));
+
+    let f = FreeVar::fresh_named("f");
+    let x = FreeVar::fresh_named("x");
+    let y = FreeVar::fresh_named("y");
+
+    // \x => \y => f x y
+    let scope = Scope::new(
+        Binder(x.clone()),
+        lam(&y, app(app(var(&f), var(&x)), var(&y))),
+    );
+    let expr = RcExpr::from(Expr::Lam(scope.clone()));
+
+    assert_eq!(laws::check_open_close_roundtrip(&expr), Ok(()));
+    assert_eq!(laws::check_term_eq_reflexive(&expr), Ok(()));
+    assert_eq!(laws::check_pattern_eq_reflexive(&Binder(x.clone())), Ok(()));
+    assert_eq!(laws::check_free_vars_consistent_with_visit(&expr), Ok(()));
+    assert_eq!(laws::check_alpha_invariance_of_unbind(&scope), Ok(()));
+
+    /// A hand-written binding construct that forgets to increment the scope
+    /// state when closing its body
+    #[derive(Debug, Clone)]
+    struct BadLam(Binder<String>, RcExpr);
+
+    impl BoundTerm<String> for BadLam {
+        fn term_eq(&self, other: &BadLam) -> bool {
+            self.1.term_eq(&other.1)
+        }
+
+        fn close_term(&mut self, state: ScopeState, binders: &[Binder<String>]) {
+            self.1.close_term(state, binders);
+        }
+
+        fn open_term(&mut self, state: ScopeState, binders: &[Binder<String>]) {
+            self.1.open_term(state.incr(), binders);
+        }
+
+        fn visit_vars(&self, on_var: &mut impl FnMut(&Var<String>)) {
+            self.1.visit_vars(on_var);
+        }
+
+        fn visit_mut_vars(&mut self, on_var: &mut impl FnMut(&mut Var<String>)) {
+            self.1.visit_mut_vars(on_var);
+        }
+
+        fn walk_term(&self, walker: &mut impl Walker<String>) {
+            walker.enter_frame(FrameKind::Scope, &self.0);
+            self.1.walk_term(walker);
+            walker.exit_frame(FrameKind::Scope);
+        }
+
+        fn walk_mut_term(&mut self, walker: &mut impl WalkerMut<String>) {
+            walker.enter_frame(FrameKind::Scope, &mut self.0);
+            self.1.walk_mut_term(walker);
+            walker.exit_frame(FrameKind::Scope);
+        }
+    }
+
+    let bad_lam = BadLam(Binder(y.clone()), var(&x));
+
+    let violation = laws::check_open_close_roundtrip(&bad_lam).unwrap_err();
+    assert_eq!(violation.law, "open_close_roundtrip");
+    assert!(violation
+        .to_string()
+        .contains("bound 0 of the 1 occurrence(s)"));
+}
+
+#[test]
+#[should_panic(expected = "at: `inner.App.0.inner.Var.0`")]
+fn test_assert_term_eq_diff() {
+    use moniker::FreeVar;
+
+    let var = |x: &FreeVar<String>| RcExpr::from(Expr::Var(Var::Free(x.clone())));
+    let app = |fun, arg| RcExpr::from(Expr::App(fun, arg));
+
+    let f = FreeVar::fresh_named("f");
+    let g = FreeVar::fresh_named("g");
+    let x = FreeVar::fresh_named("x");
+
+    assert_term_eq!(app(var(&f), var(&x)), app(var(&g), var(&x)));
+}
+
+#[test]
+fn test_derive_attributes() {
+    use moniker::{BoundPattern, BoundTerm, FreeVar, Node, PatternEqStrict, TermEqStrict};
+    use std::ops::Range;
+
+    /// A parameter with a source span and a type annotation
+    #[derive(Debug, Clone, BoundPattern, Node, PatternEqStrict)]
+    struct Param {
+        #[moniker(ignore)]
+        span: Range<usize>,
+        binder: Binder<String>,
+        #[moniker(embed)]
+        ann: RcExpr,
+    }
+
+    /// An expression with a variable that it is related to, for documentation
+    #[derive(Debug, Clone, BoundTerm, Node, TermEqStrict)]
+    struct Documented {
+        expr: RcExpr,
+        #[moniker(eq = "skip")]
+        see_also: Var<String>,
+    }
+
+    /// An expression with a label, in a tuple struct
+    #[derive(Debug, Clone, BoundTerm)]
+    struct Labelled(#[moniker(ignore)] String, RcExpr);
+
+    let x = FreeVar::fresh_named("x");
+    let y = FreeVar::fresh_named("y");
+    let a = FreeVar::fresh_named("a");
+
+    let param = |span, binder: &FreeVar<String>| Param {
+        span,
+        binder: Binder(binder.clone()),
+        ann: RcExpr::from(Expr::Var(Var::Free(a.clone()))),
+    };
+
+    // spans are ignored, and annotations are not binders
+    assert_pattern_eq!(param(0..1, &x), param(3..4, &x));
+    assert_eq!(param(0..1, &x).binders(), vec![Binder(x.clone())]);
+    assert_eq!(
+        moniker::pattern_eq_witness(&param(0..1, &x), &param(3..4, &y)),
+        Some(vec![(Binder(x.clone()), Binder(y.clone()))]),
+    );
+    // spans are not part of the structural view
+    assert_eq!(param(0..1, &x).children().len(), 2);
+    // but they are compared strictly, along with the names of binders
+    assert!(param(0..1, &x).pattern_eq_strict(&param(0..1, &x)));
+    assert!(!param(0..1, &x).pattern_eq_strict(&param(3..4, &x)));
+    assert!(!param(0..1, &x).pattern_eq_strict(&param(0..1, &y)));
+
+    // `x` and `y`, each closed over their own binder
+    let doc = |binder: &FreeVar<String>| {
+        let mut doc = Documented {
+            expr: RcExpr::from(Expr::Var(Var::Free(binder.clone()))),
+            see_also: Var::Free(binder.clone()),
+        };
+        doc.close_term(moniker::ScopeState::new(), &[Binder(binder.clone())]);
+        doc
+    };
+
+    assert_term_eq!(doc(&x), doc(&y));
+    assert_eq!(doc(&x).free_vars().len(), 0);
+    // the related variable is closed, but not compared
+    assert_term_eq!(
+        doc(&x),
+        Documented {
+            see_also: Var::Free(y.clone()),
+            ..doc(&x)
+        }
+    );
+    // nor is it part of the fingerprint
+    let fingerprint = |doc: &Documented| {
+        moniker::term_fingerprint(doc, |free_var: &FreeVar<String>| free_var.to_string())
+    };
+    assert_eq!(doc(&x).children().len(), 2);
+    assert_eq!(doc(&x).eq_children().len(), 1);
+    assert_eq!(
+        fingerprint(&doc(&x)),
+        fingerprint(&Documented {
+            see_also: Var::Free(y.clone()),
+            ..doc(&x)
+        })
+    );
+    // but it is compared strictly
+    assert!(doc(&x).term_eq_strict(&doc(&x)));
+    assert!(!doc(&x).term_eq_strict(&Documented {
+        see_also: Var::Free(y.clone()),
+        ..doc(&x)
+    }));
+
+    // the label is ignored, but the expression is still compared
+    let labelled = |label: &str, var: &FreeVar<String>| {
+        Labelled(
+            label.to_owned(),
+            RcExpr::from(Expr::Var(Var::Free(var.clone()))),
+        )
+    };
+    assert_term_eq!(labelled("a", &x), labelled("b", &x));
+    assert!(!labelled("a", &x).term_eq(&labelled("a", &y)));
+    assert_eq!(labelled("a", &x).free_vars().len(), 1);
+}
+
 fn main() {}
@@ -0,0 +1,97 @@
+//! An example of using the `moniker` library to implement the untyped lambda
+//! calculus, using an AST that is generic over its annotations and over the
+//! type of its subterms
+
+#[macro_use]
+extern crate moniker;
+
+use moniker::{Binder, Scope, Var};
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// Expressions, annotated with `Ann`, with subterms of type `E`
+///
+/// ```text
+/// e ::= x             variables
+///     | \x => e       anonymous functions
+///     | e₁ e₂         function application
+///     | -- c e        comments from the source code
+/// ````
+#[derive(Debug, Clone, BoundTerm)]
+#[moniker(bound = "Ann: Clone, E: Clone + moniker::BoundTerm<String>")]
+pub enum Expr<'src, Ann, E>
+where
+    Ann: Clone,
+{
+    /// Variables
+    Var(#[moniker(ignore)] Ann, Var<String>),
+    /// Lambda expressions
+    Lam(#[moniker(ignore)] Ann, Scope<Binder<String>, E>),
+    /// Function application
+    App(#[moniker(ignore)] Ann, E, E),
+    /// Comments
+    Comment(#[moniker(ignore)] Ann, #[moniker(ignore)] &'src str, E),
+}
+
+/// Reference counted expressions
+#[derive(Debug, Clone, BoundTerm)]
+#[moniker(bound = "Ann: Clone")]
+pub struct RcExpr<'src, Ann: Clone> {
+    pub inner: Rc<Expr<'src, Ann, RcExpr<'src, Ann>>>,
+}
+
+impl<'src, Ann: Clone> From<Expr<'src, Ann, RcExpr<'src, Ann>>> for RcExpr<'src, Ann> {
+    fn from(src: Expr<'src, Ann, RcExpr<'src, Ann>>) -> RcExpr<'src, Ann> {
+        RcExpr {
+            inner: Rc::new(src),
+        }
+    }
+}
+
+/// Atomically reference counted expressions, for sharing between threads
+#[derive(Debug, Clone, BoundTerm)]
+#[moniker(bound = "Ann: Clone")]
+pub struct ArcExpr<'src, Ann: Clone> {
+    pub inner: Arc<Expr<'src, Ann, ArcExpr<'src, Ann>>>,
+}
+
+impl<'src, Ann: Clone> From<Expr<'src, Ann, ArcExpr<'src, Ann>>> for ArcExpr<'src, Ann> {
+    fn from(src: Expr<'src, Ann, ArcExpr<'src, Ann>>) -> ArcExpr<'src, Ann> {
+        ArcExpr {
+            inner: Arc::new(src),
+        }
+    }
+}
+
+/// Build the identity function, `\x => -- comment x`, for any type of subterm
+pub fn id<'src, Ann, E>(ann: Ann, comment: &'src str) -> E
+where
+    Ann: Clone,
+    E: Clone + From<Expr<'src, Ann, E>> + moniker::BoundTerm<String>,
+{
+    use moniker::FreeVar;
+
+    let x = FreeVar::fresh_named("x");
+    let var = E::from(Expr::Var(ann.clone(), Var::Free(x.clone())));
+    let body = E::from(Expr::Comment(ann.clone(), comment, var));
+
+    E::from(Expr::Lam(ann, Scope::new(Binder(x), body)))
+}
+
+#[test]
+fn test_term_eq_rc() {
+    let expr1: RcExpr<(usize, usize)> = id((0, 5), "the identity function");
+    let expr2: RcExpr<(usize, usize)> = id((6, 11), "same as before");
+
+    assert_term_eq!(expr1, expr2);
+}
+
+#[test]
+fn test_term_eq_arc() {
+    let expr1: ArcExpr<()> = id((), "the identity function");
+    let expr2: ArcExpr<()> = id((), "same as before");
+
+    assert_term_eq!(expr1, expr2);
+}
+
+fn main() {}
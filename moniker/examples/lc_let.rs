@@ -1,10 +1,13 @@
 //! An example of using the `moniker` library to implement the untyped lambda
-//! calculus with nested let bindings
+//! calculus with nested and simultaneous let bindings
 
 #[macro_use]
 extern crate moniker;
+#[cfg(feature = "proptest")]
+#[macro_use]
+extern crate proptest;
 
-use moniker::{Binder, Embed, Nest, Scope, Var};
+use moniker::{Binder, Embed, Multi, Nest, Scope, Var};
 use std::rc::Rc;
 
 /// Expressions
@@ -14,8 +17,10 @@ use std::rc::Rc;
 ///     | \x => e                       anonymous functions
 ///     | e₁ e₂                         function application
 ///     | let x₁=e₁, ..., xₙ=eₙ in e    nested let bindings
+///     | let x₁=e₁ and ... xₙ=eₙ in e  simultaneous let bindings
 /// ````
 #[derive(Debug, Clone, BoundTerm)]
+#[cfg_attr(feature = "proptest", derive(ArbitraryTerm))]
 pub enum Expr {
     /// Variables
     Var(Var<String>),
@@ -25,10 +30,13 @@ pub enum Expr {
     App(RcExpr, RcExpr),
     /// Nested let bindings
     Let(Scope<Nest<(Binder<String>, Embed<RcExpr>)>, RcExpr>),
+    /// Simultaneous let bindings
+    LetAnd(Scope<Multi<(Binder<String>, Embed<RcExpr>)>, RcExpr>),
 }
 
 /// Reference counted expressions
 #[derive(Debug, Clone, BoundTerm)]
+#[cfg_attr(feature = "proptest", derive(ArbitraryTerm))]
 pub struct RcExpr {
     pub inner: Rc<Expr>,
 }
@@ -69,6 +77,19 @@ impl RcExpr {
                 },
                 unsafe_body: scope.unsafe_body.substs(mappings),
             })),
+            Expr::LetAnd(ref scope) => RcExpr::from(Expr::LetAnd(Scope {
+                unsafe_pattern: Multi {
+                    patterns: scope
+                        .unsafe_pattern
+                        .patterns
+                        .iter()
+                        .map(|&(ref n, Embed(ref value))| {
+                            (n.clone(), Embed(value.substs(mappings)))
+                        })
+                        .collect(),
+                },
+                unsafe_body: scope.unsafe_body.substs(mappings),
+            })),
         }
     }
 }
@@ -93,6 +114,16 @@ pub fn eval(expr: &RcExpr) -> RcExpr {
                 mappings.push((binder, value));
             }
 
+            eval(&body.substs(&mappings))
+        },
+        Expr::LetAnd(ref scope) => {
+            let (bindings, body) = scope.clone().unbind();
+            let mappings = bindings
+                .unmulti()
+                .into_iter()
+                .map(|(binder, Embed(value))| (binder, eval(&value)))
+                .collect::<Vec<_>>();
+
             eval(&body.substs(&mappings))
         },
     }
@@ -159,4 +190,216 @@ fn test_eval_let() {
     assert_term_eq!(eval(&expr), RcExpr::from(Expr::Var(Var::Free(y.clone()))),);
 }
 
+#[test]
+fn test_freshen_binders() {
+    use moniker::{FreeVar, Walker};
+
+    /// Collects every variable in a term, including those in patterns
+    struct Vars(Vec<Var<String>>);
+
+    impl Walker<String> for Vars {
+        fn on_var(&mut self, var: &Var<String>) {
+            self.0.push(var.clone());
+        }
+    }
+
+    let var = |x: &FreeVar<String>| RcExpr::from(Expr::Var(Var::Free(x.clone())));
+    let lam =
+        |x: &FreeVar<String>, body| RcExpr::from(Expr::Lam(Scope::new(Binder(x.clone()), body)));
+
+    let x = FreeVar::fresh_named("x");
+    let f = FreeVar::fresh_named("f");
+    let g = FreeVar::fresh_named("g");
+
+    // expr = let f = \x => x; g = f in \x => g x
+    let expr = RcExpr::from(Expr::Let(Scope::new(
+        Nest::new(vec![
+            (Binder(f.clone()), Embed(lam(&x, var(&x)))),
+            (Binder(g.clone()), Embed(var(&f))),
+        ]),
+        lam(&x, RcExpr::from(Expr::App(var(&g), var(&x)))),
+    )));
+
+    let (freshened, pretty_names) = moniker::freshen_binders(expr.clone());
+
+    let mut names = pretty_names.values().cloned().collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(
+        names,
+        vec![
+            Some("f".to_owned()),
+            Some("g".to_owned()),
+            Some("x".to_owned()),
+            Some("x".to_owned())
+        ],
+    );
+
+    // every variable refers to one of the new binders
+    let mut vars = Vars(Vec::new());
+    moniker::BoundTerm::walk_term(&freshened, &mut vars);
+    assert_eq!(vars.0.len(), 4);
+    for var in vars.0 {
+        match var {
+            Var::Free(free_var) => assert!(pretty_names.contains_key(&Binder(free_var))),
+            Var::Bound(bound_var) => panic!("unexpected bound variable: {}", bound_var),
+        }
+    }
+
+    assert_term_eq!(moniker::close_binders(freshened), expr);
+}
+
+#[test]
+fn test_eval_let_and() {
+    use moniker::FreeVar;
+
+    let x = FreeVar::fresh_named("x");
+    let y = FreeVar::fresh_named("y");
+    let a = FreeVar::fresh_named("a");
+
+    let var = |free_var: &FreeVar<String>| RcExpr::from(Expr::Var(Var::Free(free_var.clone())));
+
+    // expr =
+    //      let x = a
+    //      and y = x
+    //      in y
+    let expr = RcExpr::from(Expr::LetAnd(Scope::new(
+        Multi::new(vec![
+            (Binder(x.clone()), Embed(var(&a))),
+            (Binder(y.clone()), Embed(var(&x))),
+        ]),
+        var(&y),
+    )));
+
+    // The `x` in the binding of `y` refers to the free variable, not to the
+    // binding of `x`
+    assert_term_eq!(eval(&expr), var(&x));
+}
+
+#[test]
+fn test_unbind2_let_and() {
+    use moniker::{BoundPattern, FreeVar};
+
+    let var = |free_var: &FreeVar<String>| RcExpr::from(Expr::Var(Var::Free(free_var.clone())));
+    let let_and = |x: &FreeVar<String>, y: &FreeVar<String>| {
+        // let x = y and y = x in x
+        Scope::new(
+            Multi::new(vec![
+                (Binder(x.clone()), Embed(var(y))),
+                (Binder(y.clone()), Embed(var(x))),
+            ]),
+            var(x),
+        )
+    };
+
+    let x1 = FreeVar::fresh_named("x");
+    let y1 = FreeVar::fresh_named("y");
+    let x2 = FreeVar::fresh_named("x");
+    let y2 = FreeVar::fresh_named("y");
+
+    let (bindings1, body1, bindings2, body2) = let_and(&x1, &y1).unbind2(let_and(&x2, &y2));
+
+    assert_eq!(bindings1.binders(), bindings2.binders());
+    assert_term_eq!(body1, body2);
+}
+
+#[test]
+fn test_lint() {
+    use moniker::lint::{self, Lint};
+    use moniker::FreeVar;
+
+    let x1 = FreeVar::fresh_named("x");
+    let x2 = FreeVar::fresh_named("x");
+    let y = FreeVar::fresh_named("y");
+    let z = FreeVar::fresh_named("z");
+
+    // expr = \y -> let x = y, x = x in z
+    let expr = RcExpr::from(Expr::Lam(Scope::new(
+        Binder(y.clone()),
+        RcExpr::from(Expr::Let(Scope::new(
+            Nest::new(vec![
+                (
+                    Binder(x1.clone()),
+                    Embed(RcExpr::from(Expr::Var(Var::Free(y.clone())))),
+                ),
+                (
+                    Binder(x2.clone()),
+                    Embed(RcExpr::from(Expr::Var(Var::Free(x1.clone())))),
+                ),
+            ]),
+            RcExpr::from(Expr::Var(Var::Free(z.clone()))),
+        ))),
+    )));
+
+    let mut lints = Vec::new();
+    lint::lint_term(&expr, &mut |lint| lints.push(lint));
+
+    assert_eq!(
+        lints,
+        vec![
+            Lint::ShadowedBinder {
+                binder: Binder(x2.clone()),
+                shadowed: Binder(x1.clone()),
+            },
+            Lint::UnusedBinder(Binder(x2.clone())),
+        ],
+    );
+}
+
+#[test]
+fn test_lint_shadowed_rebinding() {
+    use moniker::lint::{self, Lint};
+    use moniker::FreeVar;
+
+    let x = FreeVar::fresh_named("x");
+    let z = FreeVar::fresh_named("z");
+
+    let var = |x: &FreeVar<String>| RcExpr::from(Expr::Var(Var::Free(x.clone())));
+    let lam =
+        |x: &FreeVar<String>, body| RcExpr::from(Expr::Lam(Scope::new(Binder(x.clone()), body)));
+    let lints = |expr: &RcExpr| {
+        let mut lints = Vec::new();
+        lint::lint_term(expr, &mut |lint| lints.push(lint));
+        lints
+    };
+
+    // Both lambdas are bound using the same free variable, as might happen
+    // when rebinding a scope after unbinding it
+    let shadowed = Lint::ShadowedBinder {
+        binder: Binder(x.clone()),
+        shadowed: Binder(x.clone()),
+    };
+
+    // expr = \x -> \x -> x
+    let expr = lam(&x, lam(&x, var(&x)));
+    assert_eq!(
+        lints(&expr),
+        vec![shadowed.clone(), Lint::UnusedBinder(Binder(x.clone()))],
+    );
+
+    // expr = \x -> x (\x -> x)
+    let expr = lam(&x, RcExpr::from(Expr::App(var(&x), lam(&x, var(&x)))));
+    assert_eq!(lints(&expr), vec![shadowed.clone()]);
+
+    // expr = \x -> x (\x -> z)
+    let expr = lam(&x, RcExpr::from(Expr::App(var(&x), lam(&x, var(&z)))));
+    assert_eq!(
+        lints(&expr),
+        vec![shadowed.clone(), Lint::UnusedBinder(Binder(x.clone()))],
+    );
+}
+
+#[cfg(feature = "proptest")]
+proptest! {
+    #[test]
+    fn test_arbitrary_closed_exprs(
+        expr in moniker::arbitrary::any_term::<_, RcExpr>(
+            &moniker::arbitrary::Context::new(4).with_names(|i| format!("x{}", i)),
+        ),
+    ) {
+        // The variables in the nested and simultaneous let bindings are drawn from their binders
+        prop_assert!(moniker::BoundTerm::is_locally_closed(&expr));
+        prop_assert!(moniker::BoundTerm::free_vars(&expr).is_empty());
+    }
+}
+
 fn main() {}
@@ -3,8 +3,11 @@
 
 #[macro_use]
 extern crate moniker;
+#[cfg(feature = "proptest")]
+#[macro_use]
+extern crate proptest;
 
-use moniker::{Binder, BoundTerm, Embed, Rec, Scope, Var};
+use moniker::{Binder, BoundTerm, Embed, Rec, Scope, Unordered, Var};
 use std::rc::Rc;
 
 /// Expressions
@@ -15,7 +18,8 @@ use std::rc::Rc;
 ///     | e₁ e₂                         function application
 ///     | let x₁=e₁, ..., xₙ=eₙ in e    mutually recursive let bindings
 /// ````
-#[derive(Debug, Clone, BoundTerm)]
+#[derive(Debug, Clone, BoundTerm, Node)]
+#[cfg_attr(feature = "proptest", derive(ArbitraryTerm))]
 pub enum Expr {
     /// Variables
     Var(Var<String>),
@@ -24,11 +28,12 @@ pub enum Expr {
     /// Function application
     App(RcExpr, RcExpr),
     /// Mutually recursive let bindings
-    LetRec(Scope<Rec<Vec<(Binder<String>, Embed<RcExpr>)>>, RcExpr>),
+    LetRec(Scope<Rec<Unordered<(Binder<String>, Embed<RcExpr>)>>, RcExpr>),
 }
 
 /// Reference counted expressions
-#[derive(Debug, Clone, BoundTerm)]
+#[derive(Debug, Clone, BoundTerm, Node)]
+#[cfg_attr(feature = "proptest", derive(ArbitraryTerm))]
 pub struct RcExpr {
     pub inner: Rc<Expr>,
 }
@@ -57,14 +62,17 @@ impl RcExpr {
             )),
             Expr::LetRec(ref scope) => RcExpr::from(Expr::LetRec(Scope {
                 unsafe_pattern: Rec {
-                    unsafe_pattern: scope
-                        .unsafe_pattern
-                        .unsafe_pattern
-                        .iter()
-                        .map(|&(ref n, Embed(ref value))| {
-                            (n.clone(), Embed(value.subst(name, replacement)))
-                        })
-                        .collect(),
+                    unsafe_pattern: Unordered {
+                        unsafe_patterns: scope
+                            .unsafe_pattern
+                            .unsafe_pattern
+                            .unsafe_patterns
+                            .iter()
+                            .map(|&(ref n, Embed(ref value))| {
+                                (n.clone(), Embed(value.subst(name, replacement)))
+                            })
+                            .collect(),
+                    },
                 },
                 unsafe_body: scope.unsafe_body.subst(name, replacement),
             })),
@@ -85,18 +93,25 @@ pub fn eval(expr: &RcExpr) -> RcExpr {
         },
         Expr::LetRec(ref scope) => {
             let (bindings, mut body) = scope.clone().unbind();
-            let bindings = bindings.unrec();
+            let bindings = bindings.unrec().into_patterns();
 
-            // substitute the variable definitions all (once) throughout the body
-            for &(ref binder, Embed(ref binding)) in &bindings {
-                body = body.subst(binder, binding);
+            // substitute the variable definitions throughout the body, once
+            // for each definition, so that definitions referring to other
+            // definitions are resolved regardless of the order of the bindings
+            for _ in 0..bindings.len() {
+                for &(ref binder, Embed(ref binding)) in &bindings {
+                    body = body.subst(binder, binding);
+                }
             }
 
             // garbage collect, if possible
             // FIXME: `free_vars` is slow! We probably want this to be faster - see issue #10
             let fvs = body.free_vars();
             if bindings.iter().any(|&(Binder(ref fv), _)| fvs.contains(fv)) {
-                RcExpr::from(Expr::LetRec(Scope::new(Rec::new(bindings), body)))
+                RcExpr::from(Expr::LetRec(Scope::new(
+                    Rec::new(Unordered::new(bindings)),
+                    body,
+                )))
             } else {
                 eval(&body)
             }
@@ -138,7 +153,7 @@ fn test_eval_let_rec() {
     //      in
     //          test
     let expr = RcExpr::from(Expr::LetRec(Scope::new(
-        Rec::new(vec![
+        Rec::new(Unordered::new(vec![
             (
                 Binder(test.clone()),
                 Embed(RcExpr::from(Expr::App(
@@ -153,11 +168,398 @@ fn test_eval_let_rec() {
                     RcExpr::from(Expr::Var(Var::Free(x2.clone()))),
                 )))),
             ),
-        ]),
+        ])),
         RcExpr::from(Expr::Var(Var::Free(test.clone()))),
     )));
 
     assert_term_eq!(eval(&expr), RcExpr::from(Expr::Var(Var::Free(x1.clone()))));
 }
 
+#[test]
+fn test_let_rec_in_scope() {
+    use moniker::FreeVar;
+
+    let x = FreeVar::fresh_named("x");
+    let y = FreeVar::fresh_named("y");
+    let f = FreeVar::fresh_named("f");
+
+    // expr = \x -> let f = \y -> f <arg> in f
+    let expr = |arg: &FreeVar<String>| {
+        RcExpr::from(Expr::Lam(Scope::new(
+            Binder(x.clone()),
+            RcExpr::from(Expr::LetRec(Scope::new(
+                Rec::new(Unordered::new(vec![(
+                    Binder(f.clone()),
+                    Embed(RcExpr::from(Expr::Lam(Scope::new(
+                        Binder(y.clone()),
+                        RcExpr::from(Expr::App(
+                            RcExpr::from(Expr::Var(Var::Free(f.clone()))),
+                            RcExpr::from(Expr::Var(Var::Free(arg.clone()))),
+                        )),
+                    )))),
+                )])),
+                RcExpr::from(Expr::Var(Var::Free(f.clone()))),
+            ))),
+        )))
+    };
+
+    // The recursive binder must not be confused with the outer binder
+    assert!(!expr(&x).term_eq(&expr(&f)));
+
+    // Unbinding the let should leave the outer variable in place
+    let (x_binder, let_expr) = match *expr(&x).inner {
+        Expr::Lam(ref scope) => scope.clone().unbind(),
+        _ => panic!("expected a lambda"),
+    };
+    let (bindings, _) = match *let_expr.inner {
+        Expr::LetRec(ref scope) => scope.clone().unbind(),
+        _ => panic!("expected a let binding"),
+    };
+    let bindings = bindings.unrec().into_patterns();
+    let (_, f_body) = match *(bindings[0].1).0.inner {
+        Expr::Lam(ref scope) => scope.clone().unbind(),
+        _ => panic!("expected a lambda"),
+    };
+
+    assert_term_eq!(
+        f_body,
+        RcExpr::from(Expr::App(
+            RcExpr::from(Expr::Var(Var::Free((bindings[0].0).0.clone()))),
+            RcExpr::from(Expr::Var(Var::Free(x_binder.0.clone()))),
+        )),
+    );
+}
+
+#[test]
+fn test_term_eq_let_rec_reordered() {
+    use moniker::FreeVar;
+
+    let var = |x: &FreeVar<String>| RcExpr::from(Expr::Var(Var::Free(x.clone())));
+    let let_rec = |f: &FreeVar<String>, g: &FreeVar<String>, swap: bool| {
+        let mut bindings = vec![
+            (Binder(f.clone()), Embed(var(g))),
+            (Binder(g.clone()), Embed(var(f))),
+        ];
+        if swap {
+            bindings.reverse();
+        }
+        RcExpr::from(Expr::LetRec(Scope::new(
+            Rec::new(Unordered::new(bindings)),
+            var(f),
+        )))
+    };
+
+    let f1 = FreeVar::fresh_named("f");
+    let g1 = FreeVar::fresh_named("g");
+    let f2 = FreeVar::fresh_named("f");
+    let g2 = FreeVar::fresh_named("g");
+
+    // let f = g; g = f in f
+    // let g = f; f = g in f
+    assert_term_eq!(let_rec(&f1, &g1, false), let_rec(&f2, &g2, true));
+
+    // let f = g; g = f in f
+    // let f = g; g = g in f
+    let expr = RcExpr::from(Expr::LetRec(Scope::new(
+        Rec::new(Unordered::new(vec![
+            (Binder(f2.clone()), Embed(var(&g2))),
+            (Binder(g2.clone()), Embed(var(&g2))),
+        ])),
+        var(&f2),
+    )));
+    assert!(!RcExpr::term_eq(&let_rec(&f1, &g1, false), &expr));
+
+    // let f = g; g = f in f
+    // let f = g; g = f in g
+    //
+    // swapping the names of `f` and `g` turns one into the other
+    let expr = RcExpr::from(Expr::LetRec(Scope::new(
+        Rec::new(Unordered::new(vec![
+            (Binder(f2.clone()), Embed(var(&g2))),
+            (Binder(g2.clone()), Embed(var(&f2))),
+        ])),
+        var(&g2),
+    )));
+    assert_term_eq!(let_rec(&f1, &g1, false), expr);
+}
+
+#[test]
+fn test_term_eq_let_rec_free_vars_reordered() {
+    use moniker::FreeVar;
+
+    let var = |x: &FreeVar<String>| RcExpr::from(Expr::Var(Var::Free(x.clone())));
+    let lam =
+        |x: &FreeVar<String>, body| RcExpr::from(Expr::Lam(Scope::new(Binder(x.clone()), body)));
+    // \x => \y => let rec f = x; g = y in body
+    let expr = |x: &FreeVar<String>, y: &FreeVar<String>, body_is_f: bool| {
+        let f = FreeVar::fresh_named("f");
+        let g = FreeVar::fresh_named("g");
+        let let_rec = RcExpr::from(Expr::LetRec(Scope::new(
+            Rec::new(Unordered::new(vec![
+                (Binder(f.clone()), Embed(var(x))),
+                (Binder(g.clone()), Embed(var(y))),
+            ])),
+            var(if body_is_f { &f } else { &g }),
+        )));
+        lam(x, lam(y, let_rec))
+    };
+
+    // the order that the bound variables were created in should not matter
+    let x1 = FreeVar::fresh_named("x");
+    let y1 = FreeVar::fresh_named("y");
+    let y2 = FreeVar::fresh_named("y");
+    let x2 = FreeVar::fresh_named("x");
+    assert_term_eq!(expr(&x1, &y1, true), expr(&x2, &y2, true));
+    assert_term_eq!(expr(&x1, &y1, false), expr(&x2, &y2, false));
+    assert!(!RcExpr::term_eq(
+        &expr(&x1, &y1, true),
+        &expr(&x2, &y2, false),
+    ));
+}
+
+#[test]
+fn test_term_eq_let_rec_renamed() {
+    use moniker::FreeVar;
+
+    let var = |x: &FreeVar<String>| RcExpr::from(Expr::Var(Var::Free(x.clone())));
+    let lam =
+        |x: &FreeVar<String>, body| RcExpr::from(Expr::Lam(Scope::new(Binder(x.clone()), body)));
+    // let f = \x -> g; g = f in body
+    let let_rec = |f: &FreeVar<String>, g: &FreeVar<String>, swap: bool, body: &FreeVar<String>| {
+        let x = FreeVar::fresh_named("x");
+        let mut bindings = vec![
+            (Binder(f.clone()), Embed(lam(&x, var(g)))),
+            (Binder(g.clone()), Embed(var(f))),
+        ];
+        if swap {
+            bindings.reverse();
+        }
+        RcExpr::from(Expr::LetRec(Scope::new(
+            Rec::new(Unordered::new(bindings)),
+            var(body),
+        )))
+    };
+
+    let a = FreeVar::fresh_named("a");
+    let b = FreeVar::fresh_named("b");
+    let y = FreeVar::fresh_named("y");
+    let z = FreeVar::fresh_named("z");
+
+    // let a = \x -> b; b = a in a
+    // let z = \x -> y; y = z in z
+    assert_term_eq!(let_rec(&a, &b, false, &a), let_rec(&z, &y, true, &z));
+    assert!(!RcExpr::term_eq(
+        &let_rec(&a, &b, false, &a),
+        &let_rec(&z, &y, true, &y),
+    ));
+
+    // the same, with unnamed binders
+    let f = FreeVar::fresh_unnamed();
+    let g = FreeVar::fresh_unnamed();
+    assert_term_eq!(let_rec(&a, &b, false, &b), let_rec(&f, &g, true, &g));
+    assert_term_eq!(let_rec(&f, &g, false, &f), let_rec(&f, &g, true, &f));
+    assert!(!RcExpr::term_eq(
+        &let_rec(&f, &g, false, &f),
+        &let_rec(&f, &g, true, &g),
+    ));
+}
+
+#[test]
+fn test_rec_into_sccs() {
+    use moniker::FreeVar;
+
+    let var = |x: &FreeVar<String>| RcExpr::from(Expr::Var(Var::Free(x.clone())));
+    let app = |f: &FreeVar<String>, x: &FreeVar<String>| RcExpr::from(Expr::App(var(f), var(x)));
+
+    let main = FreeVar::fresh_named("main");
+    let f = FreeVar::fresh_named("f");
+    let g = FreeVar::fresh_named("g");
+    let y = FreeVar::fresh_named("y");
+    let z = FreeVar::fresh_named("z");
+
+    // let main = f y
+    //     f = g
+    //     g = f
+    //     y = z
+    let rec = Rec::new(vec![
+        (Binder(main.clone()), Embed(app(&f, &y))),
+        (Binder(f.clone()), Embed(var(&g))),
+        (Binder(g.clone()), Embed(var(&f))),
+        (Binder(y.clone()), Embed(var(&z))),
+    ]);
+
+    let groups = rec
+        .into_sccs()
+        .unnest()
+        .into_iter()
+        .map(|group| {
+            group
+                .unrec()
+                .into_iter()
+                .map(|(Binder(free_var), _)| free_var)
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(groups, vec![vec![f, g], vec![y], vec![main]]);
+}
+
+#[test]
+fn test_lint_let_rec() {
+    use moniker::lint;
+    use moniker::FreeVar;
+
+    let y = FreeVar::fresh_named("y");
+    let f = FreeVar::fresh_named("f");
+
+    // expr = \y -> let f = f y in f
+    let expr = RcExpr::from(Expr::Lam(Scope::new(
+        Binder(y.clone()),
+        RcExpr::from(Expr::LetRec(Scope::new(
+            Rec::new(Unordered::new(vec![(
+                Binder(f.clone()),
+                Embed(RcExpr::from(Expr::App(
+                    RcExpr::from(Expr::Var(Var::Free(f.clone()))),
+                    RcExpr::from(Expr::Var(Var::Free(y.clone()))),
+                ))),
+            )])),
+            RcExpr::from(Expr::Var(Var::Free(f.clone()))),
+        ))),
+    )));
+
+    let mut lints = Vec::new();
+    lint::lint_term(&expr, &mut |lint| lints.push(lint));
+
+    assert!(lints.is_empty(), "unexpected lints: {:?}", lints);
+}
+
+#[test]
+fn test_inner_outer_neutral() {
+    use moniker::{FreeVar, Inner, Neutral, Outer};
+
+    type Pattern = (
+        Binder<String>,
+        Inner<RcExpr>,
+        Outer<RcExpr>,
+        Neutral<RcExpr>,
+    );
+
+    let var = |free_var: &FreeVar<String>| RcExpr::from(Expr::Var(Var::Free(free_var.clone())));
+    let scope = |x: &FreeVar<String>, global: &FreeVar<String>| {
+        // \x => <x, inner x, outer x, neutral global> => x
+        Scope::new(
+            Binder(x.clone()),
+            Scope::new(
+                (
+                    Binder(x.clone()),
+                    Inner(var(x)),
+                    Outer(var(x)),
+                    Neutral(var(global)),
+                ),
+                var(x),
+            ),
+        )
+    };
+
+    let x = FreeVar::fresh_named("x");
+    let y = FreeVar::fresh_named("y");
+    let global = FreeVar::fresh_named("global");
+
+    let term: Scope<Binder<String>, Scope<Pattern, RcExpr>> = scope(&x, &global);
+    assert!(term.is_locally_closed());
+    assert_term_eq!(term, scope(&y, &global));
+    assert!(!term.term_eq(&scope(&x, &FreeVar::fresh_named("global"))));
+
+    let (outer_binder, inner_scope) = term.unbind();
+    let ((inner_binder, Inner(inner), Outer(outer), Neutral(neutral)), body) = inner_scope.unbind();
+
+    assert_term_eq!(inner, var(&inner_binder.0));
+    assert_term_eq!(outer, var(&outer_binder.0));
+    assert_term_eq!(neutral, var(&global));
+    assert_term_eq!(body, var(&inner_binder.0));
+}
+
+#[test]
+fn test_shift() {
+    use moniker::{FreeVar, Shift};
+
+    type Pattern = Rec<Vec<(Binder<String>, Shift<Embed<RcExpr>>)>>;
+
+    let var = |free_var: &FreeVar<String>| RcExpr::from(Expr::Var(Var::Free(free_var.clone())));
+    let f = FreeVar::fresh_named("f");
+
+    // \f => let f = f in f
+    //
+    // where the binding of `f` refers to the `f` from the enclosing function
+    let term: Scope<Binder<String>, Scope<Pattern, RcExpr>> = Scope::new(
+        Binder(f.clone()),
+        Scope::new(
+            Rec::new(vec![(Binder(f.clone()), Shift(Embed(var(&f))))]),
+            var(&f),
+        ),
+    );
+    assert!(term.is_locally_closed());
+
+    let (Binder(outer_f), let_scope) = term.unbind();
+    let (rec, body) = let_scope.unbind();
+    let bindings = rec.unrec();
+
+    assert_eq!(bindings.len(), 1);
+    let (Binder(ref inner_f), Shift(Embed(ref value))) = bindings[0];
+    assert_ne!(outer_f, *inner_f);
+    assert_term_eq!(*value, var(&outer_f));
+    assert_term_eq!(body, var(inner_f));
+}
+
+#[test]
+fn test_derive_shift() {
+    use moniker::{BoundPattern, FreeVar};
+
+    #[derive(Debug, Clone, BoundPattern)]
+    struct Binding {
+        binder: Binder<String>,
+        #[moniker(embed, shift)]
+        value: RcExpr,
+    }
+
+    let var = |free_var: &FreeVar<String>| RcExpr::from(Expr::Var(Var::Free(free_var.clone())));
+    let f = FreeVar::fresh_named("f");
+
+    // \f => let f = f in f
+    let term: Scope<Binder<String>, Scope<Rec<Binding>, RcExpr>> = Scope::new(
+        Binder(f.clone()),
+        Scope::new(
+            Rec::new(Binding {
+                binder: Binder(f.clone()),
+                value: var(&f),
+            }),
+            var(&f),
+        ),
+    );
+    assert!(term.is_locally_closed());
+
+    let (Binder(outer_f), let_scope) = term.unbind();
+    let (rec, _) = let_scope.unbind();
+    let binding = rec.unrec();
+
+    assert_ne!(outer_f, binding.binder.0);
+    assert_term_eq!(binding.value, var(&outer_f));
+}
+
+#[cfg(feature = "proptest")]
+proptest! {
+    #[test]
+    fn test_arbitrary_closed_exprs(
+        expr in moniker::arbitrary::any_term::<_, RcExpr>(
+            &moniker::arbitrary::Context::new(4).with_names(|i| format!("x{}", i)),
+        ),
+    ) {
+        // The variables in the recursive let bindings are drawn from their binders
+        prop_assert!(moniker::BoundTerm::is_locally_closed(&expr));
+        prop_assert!(moniker::BoundTerm::free_vars(&expr).is_empty());
+        prop_assert_eq!(moniker::laws::check_term_eq_reflexive(&expr), Ok(()));
+        prop_assert_eq!(moniker::laws::check_free_vars_consistent_with_visit(&expr), Ok(()));
+    }
+}
+
 fn main() {}
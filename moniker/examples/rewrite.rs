@@ -0,0 +1,317 @@
+//! An example of using the `moniker` library to optimize the untyped lambda
+//! calculus with rewrite rules
+
+#[macro_use]
+extern crate moniker;
+
+use moniker::{
+    Binder, BoundTerm, FreeVar, MetaTerm, MetaVar, Rule, Scope, Unifier, Unify, UnifyError, Var,
+};
+use std::rc::Rc;
+
+/// Expressions
+///
+/// ```text
+/// e ::= x          variables
+///     | ?m         pattern variables
+///     | n          integer literals
+///     | \x => e    anonymous functions
+///     | e₁ e₂      function application
+/// ```
+#[derive(Debug, Clone, BoundTerm, Node, Unify)]
+pub enum Expr {
+    /// Variables
+    Var(Var<String>),
+    /// Pattern variables
+    Meta(MetaVar<String>),
+    /// Integer literals
+    Int(i32),
+    /// Lambda expressions
+    Lam(Scope<Binder<String>, RcExpr>),
+    /// Function application
+    App(RcExpr, RcExpr),
+}
+
+/// Reference counted expressions
+#[derive(Debug, Clone, BoundTerm, Node)]
+pub struct RcExpr {
+    pub inner: Rc<Expr>,
+}
+
+impl From<Expr> for RcExpr {
+    fn from(src: Expr) -> RcExpr {
+        RcExpr {
+            inner: Rc::new(src),
+        }
+    }
+}
+
+/// Construct a free variable
+fn var(free_var: &FreeVar<String>) -> RcExpr {
+    RcExpr::from(Expr::Var(Var::Free(free_var.clone())))
+}
+
+/// Construct a pattern variable
+fn meta(meta_var: &MetaVar<String>) -> RcExpr {
+    RcExpr::from(Expr::Meta(meta_var.clone()))
+}
+
+/// Construct a lambda expression, binding the free variable in the body
+fn lam(free_var: &FreeVar<String>, body: RcExpr) -> RcExpr {
+    RcExpr::from(Expr::Lam(Scope::new(Binder(free_var.clone()), body)))
+}
+
+/// Construct a function application
+fn app(fun: RcExpr, arg: RcExpr) -> RcExpr {
+    RcExpr::from(Expr::App(fun, arg))
+}
+
+impl MetaTerm<String> for RcExpr {
+    fn as_meta_var(&self) -> Option<&MetaVar<String>> {
+        match *self.inner {
+            Expr::Meta(ref meta_var) => Some(meta_var),
+            _ => None,
+        }
+    }
+
+    fn as_free_var(&self) -> Option<&FreeVar<String>> {
+        match *self.inner {
+            Expr::Var(Var::Free(ref free_var)) => Some(free_var),
+            _ => None,
+        }
+    }
+
+    fn visit_mut_children(&mut self, on_child: &mut impl FnMut(&mut RcExpr)) {
+        Rc::make_mut(&mut self.inner).visit_mut_subterms(on_child);
+    }
+}
+
+impl Unify<String, RcExpr> for RcExpr {
+    fn unify(
+        &self,
+        other: &RcExpr,
+        unifier: &mut Unifier<String, RcExpr>,
+    ) -> Result<(), UnifyError<String>> {
+        match unifier.unify_meta_vars(self, other) {
+            Some(result) => result,
+            None => self.inner.unify(&other.inner, unifier),
+        }
+    }
+
+    fn zonk(&mut self, unifier: &Unifier<String, RcExpr>) {
+        if !unifier.zonk_meta_var(self) {
+            self.inner.zonk(unifier);
+        }
+    }
+
+    fn visit_meta_vars(&self, on_meta_var: &mut impl FnMut(&MetaVar<String>)) {
+        self.inner.visit_meta_vars(on_meta_var);
+    }
+
+    fn visit_mut_subterms(&mut self, on_subterm: &mut impl FnMut(&mut RcExpr)) {
+        on_subterm(self);
+    }
+}
+
+/// `(\x => e) a ~> e[x := a]`
+///
+/// The body of the function refers to its binder, so it can't be bound to a
+/// pattern variable by matching against `(\x => ?e) ?a`. Instead we match the
+/// whole function, and unbind it when computing the replacement.
+pub fn beta_rule() -> Rule<String, RcExpr> {
+    let f = MetaVar::fresh_named("f");
+    let a = MetaVar::fresh_named("a");
+    let lhs = app(meta(&f), meta(&a));
+
+    Rule::with_fn(lhs, move |bindings| match *bindings[&f].inner {
+        Expr::Lam(ref scope) => {
+            let (Binder(free_var), body) = scope.clone().unbind();
+            Some(moniker::subst(&body, &free_var, &bindings[&a]))
+        },
+        _ => None,
+    })
+}
+
+/// `\x => f x ~> f`, where `x` is not free in `f`
+pub fn eta_rule() -> Rule<String, RcExpr> {
+    let x = FreeVar::fresh_named("x");
+    let f = MetaVar::fresh_named("f");
+    let lhs = lam(&x, app(meta(&f), var(&x)));
+
+    Rule::new(lhs, meta(&f))
+}
+
+#[test]
+fn test_match_modulo_alpha() {
+    let g = FreeVar::fresh_named("g");
+    let x = FreeVar::fresh_named("x");
+    let y = FreeVar::fresh_named("y");
+    let f = MetaVar::fresh_named("f");
+
+    // pattern = \x => ?f x
+    let pattern = lam(&x, app(meta(&f), var(&x)));
+
+    // \y => g y
+    let bindings = moniker::match_term(&pattern, &lam(&y, app(var(&g), var(&y)))).unwrap();
+    assert_eq!(bindings.len(), 1);
+    assert_term_eq!(bindings[&f], var(&g));
+
+    // \y => y y, where `?f` would refer to the bound `y`
+    assert!(moniker::match_term(&pattern, &lam(&y, app(var(&y), var(&y)))).is_none());
+}
+
+#[test]
+fn test_match_non_linear() {
+    let x = FreeVar::fresh_named("x");
+    let y = FreeVar::fresh_named("y");
+    let a = MetaVar::fresh_named("a");
+
+    // pattern = ?a ?a
+    let pattern = app(meta(&a), meta(&a));
+
+    // (\x => x) (\y => y)
+    let expr = app(lam(&x, var(&x)), lam(&y, var(&y)));
+    let bindings = moniker::match_term(&pattern, &expr).unwrap();
+    assert_term_eq!(bindings[&a], lam(&x, var(&x)));
+
+    // 1 2
+    let expr = app(RcExpr::from(Expr::Int(1)), RcExpr::from(Expr::Int(2)));
+    assert!(moniker::match_term(&pattern, &expr).is_none());
+}
+
+#[test]
+fn test_find_matches() {
+    let g = FreeVar::fresh_named("g");
+    let x = FreeVar::fresh_named("x");
+    let a = MetaVar::fresh_named("a");
+
+    // pattern = ?a 1
+    let pattern = app(meta(&a), RcExpr::from(Expr::Int(1)));
+
+    // expr = (g 1) ((\x => x) 1)
+    let expr = app(
+        app(var(&g), RcExpr::from(Expr::Int(1))),
+        app(lam(&x, var(&x)), RcExpr::from(Expr::Int(1))),
+    );
+
+    let matches = moniker::find_matches(&expr, &pattern);
+    assert_eq!(matches.len(), 2);
+    assert_term_eq!(matches[0].1[&a], var(&g));
+    assert_term_eq!(matches[1].1[&a], lam(&x, var(&x)));
+
+    // expr = \x => x 1
+    let expr = lam(&x, app(var(&x), RcExpr::from(Expr::Int(1))));
+
+    // `x` is bound outside of the match
+    assert!(moniker::find_matches(&expr, &pattern).is_empty());
+}
+
+#[test]
+fn test_rewrite_eta() {
+    use moniker::Strategy;
+
+    let g = FreeVar::fresh_named("g");
+    let x = FreeVar::fresh_named("x");
+    let y = FreeVar::fresh_named("y");
+
+    // expr = \y => (\x => g x) y
+    let expr = lam(&y, app(lam(&x, app(var(&g), var(&x))), var(&y)));
+
+    for &strategy in &[Strategy::BottomUp, Strategy::TopDown] {
+        assert_term_eq!(
+            moniker::rewrite(&[eta_rule()], strategy, expr.clone()),
+            var(&g),
+        );
+    }
+}
+
+#[test]
+fn test_apply_beta() {
+    let x = FreeVar::fresh_named("x");
+    let y = FreeVar::fresh_named("y");
+    let a = FreeVar::fresh_named("a");
+
+    // expr = (\x => x (\y => x y)) a
+    let expr = app(
+        lam(&x, app(var(&x), lam(&y, app(var(&x), var(&y))))),
+        var(&a),
+    );
+
+    // (\x => e) a ~> e[x := a]
+    assert_term_eq!(
+        beta_rule().apply(&expr).unwrap(),
+        app(var(&a), lam(&y, app(var(&a), var(&y)))),
+    );
+
+    // the rule only applies when the function is a lambda
+    assert!(beta_rule().apply(&app(var(&x), var(&a))).is_none());
+}
+
+#[test]
+fn test_rewrite_beta_capture_avoiding() {
+    use moniker::Strategy;
+
+    let x = FreeVar::fresh_named("x");
+    let y = FreeVar::fresh_named("y");
+    let z = FreeVar::fresh_named("z");
+
+    // expr = (\x => \y => x) y
+    let expr = app(lam(&x, lam(&y, var(&x))), var(&y));
+
+    for &strategy in &[Strategy::BottomUp, Strategy::TopDown] {
+        // the free `y` is not captured by the inner binder
+        assert_term_eq!(
+            moniker::rewrite(&[beta_rule()], strategy, expr.clone()),
+            lam(&z, var(&y)),
+        );
+    }
+}
+
+#[test]
+fn test_rewrite_template_under_binders() {
+    use moniker::Strategy;
+
+    let x = FreeVar::fresh_named("x");
+    let y = FreeVar::fresh_named("y");
+    let a = MetaVar::fresh_named("a");
+
+    // rule = 0 ?a ~> \y => ?a
+    let rule = Rule::new(app(RcExpr::from(Expr::Int(0)), meta(&a)), lam(&y, meta(&a)));
+
+    // expr = \y => 0 y
+    let expr = lam(&y, app(RcExpr::from(Expr::Int(0)), var(&y)));
+
+    // the instantiated `y` still refers to the outer binder
+    assert_term_eq!(
+        moniker::rewrite(&[rule], Strategy::TopDown, expr),
+        lam(&x, lam(&y, var(&x))),
+    );
+}
+
+#[test]
+fn test_rewrite_strategies_normalize() {
+    use moniker::Strategy;
+
+    let f = FreeVar::fresh_named("f");
+    let x = FreeVar::fresh_named("x");
+    let y = FreeVar::fresh_named("y");
+
+    // expr = \f => (\x => f x) ((\y => y) 1)
+    let expr = lam(
+        &f,
+        app(
+            lam(&x, app(var(&f), var(&x))),
+            app(lam(&y, var(&y)), RcExpr::from(Expr::Int(1))),
+        ),
+    );
+    let rules = [beta_rule(), eta_rule()];
+
+    for &strategy in &[Strategy::BottomUp, Strategy::TopDown] {
+        assert_term_eq!(
+            moniker::rewrite(&rules, strategy, expr.clone()),
+            lam(&f, app(var(&f), RcExpr::from(Expr::Int(1)))),
+        );
+    }
+}
+
+fn main() {}
@@ -7,9 +7,12 @@
 extern crate im;
 #[macro_use]
 extern crate moniker;
+#[cfg(feature = "proptest")]
+#[macro_use]
+extern crate proptest;
 
 use im::HashMap;
-use moniker::{Binder, BoundTerm, Embed, FreeVar, Scope, Var};
+use moniker::{Binder, BoundTerm, Embed, FreeVar, LocallyClosed, Scope, Var};
 use std::rc::Rc;
 
 /// Types
@@ -21,6 +24,7 @@ use std::rc::Rc;
 ///     | t -> t                function types
 /// ```
 #[derive(Debug, Clone, BoundTerm)]
+#[cfg_attr(feature = "proptest", derive(ArbitraryTerm))]
 pub enum Type {
     /// Integers
     Int,
@@ -34,6 +38,7 @@ pub enum Type {
 
 /// Reference counted types
 #[derive(Debug, Clone, BoundTerm)]
+#[cfg_attr(feature = "proptest", derive(ArbitraryTerm))]
 pub struct RcType {
     pub inner: Rc<Type>,
 }
@@ -48,6 +53,7 @@ impl From<Type> for RcType {
 
 /// Literal values
 #[derive(Debug, Clone, BoundTerm)]
+#[cfg_attr(feature = "proptest", derive(ArbitraryTerm))]
 pub enum Literal {
     /// Integer literals
     Int(i32),
@@ -67,6 +73,7 @@ pub enum Literal {
 ///     | e₁ e₂         function application
 /// ```
 #[derive(Debug, Clone, BoundTerm)]
+#[cfg_attr(feature = "proptest", derive(ArbitraryTerm))]
 pub enum Expr {
     /// Annotated expressions
     Ann(RcExpr, RcType),
@@ -82,6 +89,7 @@ pub enum Expr {
 
 /// Reference counted expressions
 #[derive(Debug, Clone, BoundTerm)]
+#[cfg_attr(feature = "proptest", derive(ArbitraryTerm))]
 pub struct RcExpr {
     pub inner: Rc<Expr>,
 }
@@ -134,10 +142,15 @@ pub fn eval(expr: &RcExpr) -> RcExpr {
 type Context = HashMap<FreeVar<String>, RcType>;
 
 /// Check that a (potentially ambiguous) expression conforms to a given type
-pub fn check(context: &Context, expr: &RcExpr, expected_ty: &RcType) -> Result<(), String> {
+pub fn check(
+    context: &Context,
+    expr: &LocallyClosed<RcExpr>,
+    expected_ty: &RcType,
+) -> Result<(), String> {
     match (&*expr.inner, &*expected_ty.inner) {
         (&Expr::Lam(ref scope), &Type::Arrow(ref param_ty, ref ret_ty)) => {
-            if let ((Binder(free_var), Embed(None)), body) = scope.clone().unbind() {
+            if let ((Binder(free_var), Embed(None)), body) = expr.unsafe_project(|_| scope).unbind()
+            {
                 check(&context.update(free_var, param_ty.clone()), &body, ret_ty)?;
                 return Ok(());
             }
@@ -158,10 +171,10 @@ pub fn check(context: &Context, expr: &RcExpr, expected_ty: &RcType) -> Result<(
 }
 
 /// Synthesize the types of unambiguous expressions
-pub fn infer(context: &Context, expr: &RcExpr) -> Result<RcType, String> {
+pub fn infer(context: &Context, expr: &LocallyClosed<RcExpr>) -> Result<RcType, String> {
     match *expr.inner {
-        Expr::Ann(ref expr, ref ty) => {
-            check(context, expr, ty)?;
+        Expr::Ann(ref term, ref ty) => {
+            check(context, &expr.unsafe_project(|_| term), ty)?;
             Ok(ty.clone())
         },
         Expr::Literal(Literal::Int(_)) => Ok(RcType::from(Type::Int)),
@@ -172,9 +185,9 @@ pub fn infer(context: &Context, expr: &RcExpr) -> Result<RcType, String> {
             None => Err(format!("`{}` not found in `{:?}`", free_var, context)),
         },
         Expr::Var(Var::Bound(ref bound_var)) => {
-            panic!("encountered a bound variable: {}", bound_var)
+            Err(format!("dangling bound variable `{}`", bound_var))
         },
-        Expr::Lam(ref scope) => match scope.clone().unbind() {
+        Expr::Lam(ref scope) => match expr.unsafe_project(|_| scope).unbind() {
             ((Binder(free_var), Embed(Some(ann))), body) => {
                 let body_ty = infer(&context.update(free_var, ann.clone()), &body)?;
                 Ok(RcType::from(Type::Arrow(ann, body_ty)))
@@ -184,19 +197,21 @@ pub fn infer(context: &Context, expr: &RcExpr) -> Result<RcType, String> {
                 binder
             )),
         },
-        Expr::App(ref fun, ref arg) => match *infer(context, fun)?.inner {
-            Type::Arrow(ref param_ty, ref ret_ty) => {
-                let arg_ty = infer(context, arg)?;
-                if RcType::term_eq(param_ty, &arg_ty) {
-                    Ok(ret_ty.clone())
-                } else {
-                    Err(format!(
-                        "argument type mismatch - found `{:?}` but expected `{:?}`",
-                        arg_ty, param_ty,
-                    ))
-                }
-            },
-            _ => Err(format!("`{:?}` is not a function", fun)),
+        Expr::App(ref fun, ref arg) => {
+            match *infer(context, &expr.unsafe_project(|_| fun))?.inner {
+                Type::Arrow(ref param_ty, ref ret_ty) => {
+                    let arg_ty = infer(context, &expr.unsafe_project(|_| arg))?;
+                    if RcType::term_eq(param_ty, &arg_ty) {
+                        Ok(ret_ty.clone())
+                    } else {
+                        Err(format!(
+                            "argument type mismatch - found `{:?}` but expected `{:?}`",
+                            arg_ty, param_ty,
+                        ))
+                    }
+                },
+                _ => Err(format!("`{:?}` is not a function", fun)),
+            }
         },
     }
 }
@@ -214,7 +229,7 @@ fn test_infer() {
     )));
 
     assert_term_eq!(
-        infer(&Context::new(), &expr).unwrap(),
+        infer(&Context::new(), &LocallyClosed::new(expr).unwrap()).unwrap(),
         RcType::from(Type::Arrow(
             RcType::from(Type::Int),
             RcType::from(Type::Int)
@@ -222,7 +237,52 @@ fn test_infer() {
     );
 }
 
-// TODO: Use property testing for this!
-// http://janmidtgaard.dk/papers/Midtgaard-al%3AICFP17-full.pdf
+#[test]
+fn test_is_locally_closed() {
+    use moniker::FreeVar;
+
+    let x = FreeVar::fresh_named("x");
+
+    // expr = (\x : Int -> x)
+    let expr = RcExpr::from(Expr::Lam(Scope::new(
+        (Binder(x.clone()), Embed(Some(RcType::from(Type::Int)))),
+        RcExpr::from(Expr::Var(Var::Free(x.clone()))),
+    )));
+
+    assert!(expr.is_locally_closed());
+
+    // The body of the scope refers to the binder in the pattern
+    match *expr.inner {
+        Expr::Lam(ref scope) => {
+            assert!(!scope.unsafe_body.is_locally_closed());
+            assert!(LocallyClosed::new(scope.unsafe_body.clone()).is_err());
+
+            // The annotation is outside of the scope, so it is locally closed
+            let annotation = &(scope.unsafe_pattern.1).0;
+            assert!(LocallyClosed::new(annotation.clone()).is_ok());
+        },
+        _ => panic!("expected a lambda"),
+    }
+}
+
+// See also: http://janmidtgaard.dk/papers/Midtgaard-al%3AICFP17-full.pdf
+#[cfg(feature = "proptest")]
+proptest! {
+    #[test]
+    fn test_infer_closed_exprs(
+        expr in moniker::arbitrary::any_term::<_, RcExpr>(
+            &moniker::arbitrary::Context::new(4).with_names(|i| format!("x{}", i)),
+        ),
+    ) {
+        // Generated expressions are well-scoped, so closed expressions are
+        // locally closed and never refer to unknown variables
+        let expr = LocallyClosed::new(expr).unwrap();
+
+        match infer(&Context::new(), &expr) {
+            Ok(ty) => prop_assert!(check(&Context::new(), &expr, &ty).is_ok()),
+            Err(message) => prop_assert!(!message.contains("not found"), "{}", message),
+        }
+    }
+}
 
 fn main() {}
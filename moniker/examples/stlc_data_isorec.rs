@@ -15,7 +15,7 @@ extern crate im;
 extern crate moniker;
 
 use im::HashMap;
-use moniker::{Binder, BoundTerm, Embed, FreeVar, Rec, Scope, Var};
+use moniker::{Alt, Binder, BoundTerm, Embed, FreeVar, Rec, Scope, Var};
 use std::rc::Rc;
 
 /// Types
@@ -163,9 +163,11 @@ impl From<Pattern> for RcPattern {
 ///     | {l₁=e₁, ..., lₙ=eₙ}               record expressions
 ///     | e.l                               record projections
 ///     | <l=e>                             tag expressions
-///     | case e of p₁=>e₁, ..., pₙ=>eₙ     case expressions
+///     | case e of ps₁=>e₁, ..., psₙ=>eₙ   case expressions
 ///     | fold t => e                       fold expressions
 ///     | unfold t => e                     unfold expressions
+///
+/// ps ::= p₁ | ... | pₙ                    alternative patterns
 /// ```
 #[derive(Debug, Clone, BoundTerm)]
 pub enum Expr {
@@ -185,8 +187,8 @@ pub enum Expr {
     Proj(RcExpr, String),
     /// Variant introduction
     Tag(String, RcExpr),
-    /// Case expressions
-    Case(RcExpr, Vec<Scope<RcPattern, RcExpr>>),
+    /// Case expressions, where each clause can have alternative patterns
+    Case(RcExpr, Vec<Scope<Alt<RcPattern>, RcExpr>>),
     /// Fold a recursive type
     Fold(RcType, RcExpr),
     /// Unfold a recursive type
@@ -301,9 +303,11 @@ pub fn eval(expr: &RcExpr) -> RcExpr {
         Expr::Case(ref arg, ref clauses) => {
             let arg = eval(arg);
             for clause in clauses {
-                let (pattern, body) = clause.clone().unbind();
-                if let Some(mappings) = match_expr(&pattern, &arg) {
-                    return eval(&body.substs(&mappings));
+                let (patterns, body) = clause.clone().unbind();
+                for pattern in patterns.alternatives() {
+                    if let Some(mappings) = match_expr(pattern, &arg) {
+                        return eval(&body.substs(&mappings));
+                    }
                 }
             }
             RcExpr::from(Expr::Case(arg, clauses.clone())) // stuck
@@ -376,8 +380,8 @@ pub fn check_expr(context: &Context, expr: &RcExpr, expected_ty: &RcType) -> Res
         (&Expr::Case(ref expr, ref clauses), _) => {
             let expr_ty = infer_expr(context, expr)?;
             for clause in clauses {
-                let (pattern, body) = clause.clone().unbind();
-                let bindings = check_pattern(context, &pattern, &expr_ty)?;
+                let (patterns, body) = clause.clone().unbind();
+                let bindings = check_alt_pattern(context, &patterns, &expr_ty)?;
                 check_expr(&(context + &bindings), &body, expected_ty)?;
             }
             return Ok(());
@@ -509,6 +513,39 @@ pub fn check_pattern(
     }
 }
 
+/// Check that each of the alternative patterns conforms to the given type
+///
+/// The alternatives bind the same free variables, so this function also checks
+/// that each alternative assigns the same types to them.
+pub fn check_alt_pattern(
+    context: &Context,
+    patterns: &Alt<RcPattern>,
+    expected_ty: &RcType,
+) -> Result<Context, String> {
+    let mut telescope = None;
+
+    for pattern in patterns.alternatives() {
+        let alt_telescope = check_pattern(context, pattern, expected_ty)?;
+
+        match telescope {
+            None => telescope = Some(alt_telescope),
+            Some(ref telescope) => for (free_var, ty) in alt_telescope.iter() {
+                match telescope.get(free_var) {
+                    Some(ref expected_ty) if RcType::term_eq(ty, expected_ty) => {},
+                    _ => {
+                        return Err(format!(
+                            "`{}` has a different type in each alternative",
+                            free_var
+                        ))
+                    },
+                }
+            },
+        }
+    }
+
+    Ok(telescope.unwrap_or_else(Context::new))
+}
+
 /// Check that a (potentially ambiguous) pattern conforms to a given type
 ///
 /// This function also returns a telescope that can be used to extend the typing
@@ -699,6 +736,209 @@ fn test_infer_expr_record2() {
     );
 }
 
+#[test]
+fn test_duplicate_binder_in_record_pattern() {
+    use moniker::{DuplicateBinderError, FreeVar};
+
+    let x = FreeVar::fresh_named("x");
+
+    // expr = \{ a = x, b = x } -> x
+    let result = Scope::try_new(
+        RcPattern::from(Pattern::Record(vec![
+            (
+                String::from("a"),
+                RcPattern::from(Pattern::Binder(Binder(x.clone()))),
+            ),
+            (
+                String::from("b"),
+                RcPattern::from(Pattern::Binder(Binder(x.clone()))),
+            ),
+        ])),
+        RcExpr::from(Expr::Var(Var::Free(x.clone()))),
+    );
+
+    match result {
+        Err(DuplicateBinderError { binder }) => assert_eq!(binder, Binder(x.clone())),
+        Ok(scope) => panic!("expected a duplicate binder error, found: {:?}", scope),
+    }
+}
+
+#[test]
+fn test_eval_case_alt() {
+    use moniker::FreeVar;
+
+    let x1 = FreeVar::fresh_named("x");
+    let x2 = FreeVar::fresh_named("x");
+
+    // expr = case <b=2> of <a=x> | <b=x> => x
+    let expr = RcExpr::from(Expr::Case(
+        RcExpr::from(Expr::Tag(
+            String::from("b"),
+            RcExpr::from(Expr::Literal(Literal::Int(2))),
+        )),
+        vec![Scope::new(
+            Alt::new(vec![
+                RcPattern::from(Pattern::Tag(
+                    String::from("a"),
+                    RcPattern::from(Pattern::Binder(Binder(x1.clone()))),
+                )),
+                RcPattern::from(Pattern::Tag(
+                    String::from("b"),
+                    RcPattern::from(Pattern::Binder(Binder(x2.clone()))),
+                )),
+            ]),
+            RcExpr::from(Expr::Var(Var::Free(x1.clone()))),
+        )],
+    ));
+
+    assert_term_eq!(eval(&expr), RcExpr::from(Expr::Literal(Literal::Int(2))));
+}
+
+#[test]
+fn test_check_case_alt() {
+    use moniker::FreeVar;
+
+    let x1 = FreeVar::fresh_named("x");
+    let x2 = FreeVar::fresh_named("x");
+    let variant_ty = RcType::from(Type::Variant(vec![
+        (String::from("a"), RcType::from(Type::Int)),
+        (String::from("b"), RcType::from(Type::Int)),
+    ]));
+
+    // expr = case (<a=1> : <a:Int, b:Int>) of <a=x> | <b=x> => x
+    let expr = RcExpr::from(Expr::Case(
+        RcExpr::from(Expr::Ann(
+            RcExpr::from(Expr::Tag(
+                String::from("a"),
+                RcExpr::from(Expr::Literal(Literal::Int(1))),
+            )),
+            variant_ty,
+        )),
+        vec![Scope::new(
+            Alt::new(vec![
+                RcPattern::from(Pattern::Tag(
+                    String::from("a"),
+                    RcPattern::from(Pattern::Binder(Binder(x1.clone()))),
+                )),
+                RcPattern::from(Pattern::Tag(
+                    String::from("b"),
+                    RcPattern::from(Pattern::Binder(Binder(x2.clone()))),
+                )),
+            ]),
+            RcExpr::from(Expr::Var(Var::Free(x1.clone()))),
+        )],
+    ));
+
+    check_expr(&Context::new(), &expr, &RcType::from(Type::Int)).unwrap();
+}
+
+#[test]
+fn test_alt_binders_mismatch() {
+    use moniker::{AltBindersError, FreeVar};
+
+    let x = FreeVar::fresh_named("x");
+    let y = FreeVar::fresh_named("y");
+
+    // patterns = <a=x> | <b=y>
+    let result = Alt::try_new(vec![
+        RcPattern::from(Pattern::Tag(
+            String::from("a"),
+            RcPattern::from(Pattern::Binder(Binder(x.clone()))),
+        )),
+        RcPattern::from(Pattern::Tag(
+            String::from("b"),
+            RcPattern::from(Pattern::Binder(Binder(y.clone()))),
+        )),
+    ]);
+
+    match result {
+        Err(AltBindersError::Missing {
+            binder,
+            alternative,
+        }) => {
+            assert_eq!(binder, Binder(y.clone()));
+            assert_eq!(alternative, 0);
+        },
+        Err(err) => panic!("expected a missing binder error, found: {:?}", err),
+        Ok(patterns) => panic!("expected a binders mismatch error, found: {:?}", patterns),
+    }
+}
+
+#[test]
+fn test_alt_binders_duplicate() {
+    use moniker::{AltBindersError, FreeVar};
+
+    let x1 = FreeVar::fresh_named("x");
+    let x2 = FreeVar::fresh_named("x");
+    let x3 = FreeVar::fresh_named("x");
+
+    // patterns = <a=x> | <b={l=x, r=x}>
+    let result = Alt::try_new(vec![
+        RcPattern::from(Pattern::Tag(
+            String::from("a"),
+            RcPattern::from(Pattern::Binder(Binder(x1.clone()))),
+        )),
+        RcPattern::from(Pattern::Tag(
+            String::from("b"),
+            RcPattern::from(Pattern::Record(vec![
+                (
+                    String::from("l"),
+                    RcPattern::from(Pattern::Binder(Binder(x2.clone()))),
+                ),
+                (
+                    String::from("r"),
+                    RcPattern::from(Pattern::Binder(Binder(x3.clone()))),
+                ),
+            ])),
+        )),
+    ]);
+
+    match result {
+        Err(AltBindersError::Duplicate {
+            binder,
+            alternative,
+        }) => {
+            assert_eq!(binder, Binder(x3.clone()));
+            assert_eq!(alternative, 1);
+        },
+        Err(err) => panic!("expected a duplicate binder error, found: {:?}", err),
+        Ok(patterns) => panic!("expected a duplicate binder error, found: {:?}", patterns),
+    }
+}
+
+#[test]
+fn test_alt_unnamed_binders() {
+    use moniker::{BoundPattern, FreeVar};
+
+    let x1 = FreeVar::fresh_unnamed();
+    let y1 = FreeVar::fresh_unnamed();
+    let x2 = FreeVar::fresh_unnamed();
+    let y2 = FreeVar::fresh_unnamed();
+
+    let pair = |x: &FreeVar<String>, y: &FreeVar<String>| {
+        RcPattern::from(Pattern::Record(vec![
+            (
+                String::from("l"),
+                RcPattern::from(Pattern::Binder(Binder(x.clone()))),
+            ),
+            (
+                String::from("r"),
+                RcPattern::from(Pattern::Binder(Binder(y.clone()))),
+            ),
+        ]))
+    };
+
+    // patterns = {l=_0, r=_1} | {l=_2, r=_3}
+    //
+    // Binders without pretty names are matched up in order
+    let alt = Alt::new(vec![pair(&x1, &y1), pair(&x2, &y2)]);
+    assert_eq!(alt.binders(), vec![Binder(x1.clone()), Binder(y1.clone())]);
+    assert_eq!(
+        alt.alternatives()[1].binders(),
+        vec![Binder(x1.clone()), Binder(y1.clone())],
+    );
+}
+
 // TODO: Use property testing for this!
 // http://janmidtgaard.dk/papers/Midtgaard-al%3AICFP17-full.pdf
 
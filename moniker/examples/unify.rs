@@ -0,0 +1,189 @@
+//! An example of using the `moniker` library to unify types that contain
+//! binders and meta variables, as is needed when elaborating higher-rank types
+
+#[cfg_attr(test, macro_use)]
+extern crate moniker;
+
+use moniker::{
+    Binder, BoundTerm, FreeVar, MetaTerm, MetaVar, Scope, Unifier, Unify, UnifyError, Var,
+};
+use std::rc::Rc;
+
+/// Types
+///
+/// ```text
+/// t ::= a             type variables
+///     | ?m            meta variables
+///     | Int           integer types
+///     | t₁ -> t₂      function types
+///     | ∀a. t         universally quantified types
+/// ```
+#[derive(Debug, Clone, BoundTerm, Unify)]
+pub enum Type {
+    /// Type variables
+    Var(Var<String>),
+    /// Meta variables
+    Meta(MetaVar<String>),
+    /// Integers
+    Int,
+    /// Function types
+    Arrow(RcType, RcType),
+    /// Universally quantified types
+    Forall(Scope<Binder<String>, RcType>),
+}
+
+/// Reference counted types
+#[derive(Debug, Clone, BoundTerm)]
+pub struct RcType {
+    pub inner: Rc<Type>,
+}
+
+impl From<Type> for RcType {
+    fn from(src: Type) -> RcType {
+        RcType {
+            inner: Rc::new(src),
+        }
+    }
+}
+
+impl MetaTerm<String> for RcType {
+    fn as_meta_var(&self) -> Option<&MetaVar<String>> {
+        match *self.inner {
+            Type::Meta(ref meta_var) => Some(meta_var),
+            _ => None,
+        }
+    }
+
+    fn as_free_var(&self) -> Option<&FreeVar<String>> {
+        match *self.inner {
+            Type::Var(Var::Free(ref free_var)) => Some(free_var),
+            _ => None,
+        }
+    }
+
+    fn visit_mut_children(&mut self, on_child: &mut impl FnMut(&mut RcType)) {
+        Rc::make_mut(&mut self.inner).visit_mut_subterms(on_child);
+    }
+}
+
+impl Unify<String, RcType> for RcType {
+    fn unify(
+        &self,
+        other: &RcType,
+        unifier: &mut Unifier<String, RcType>,
+    ) -> Result<(), UnifyError<String>> {
+        match unifier.unify_meta_vars(self, other) {
+            Some(result) => result,
+            None => self.inner.unify(&other.inner, unifier),
+        }
+    }
+
+    fn zonk(&mut self, unifier: &Unifier<String, RcType>) {
+        if !unifier.zonk_meta_var(self) {
+            self.inner.zonk(unifier);
+        }
+    }
+
+    fn visit_meta_vars(&self, on_meta_var: &mut impl FnMut(&MetaVar<String>)) {
+        self.inner.visit_meta_vars(on_meta_var);
+    }
+
+    fn visit_mut_subterms(&mut self, on_subterm: &mut impl FnMut(&mut RcType)) {
+        on_subterm(self);
+    }
+}
+
+#[test]
+fn test_unify_forall() {
+    let var = |free_var: &FreeVar<String>| RcType::from(Type::Var(Var::Free(free_var.clone())));
+    let meta = |meta_var: &MetaVar<String>| RcType::from(Type::Meta(meta_var.clone()));
+    let arrow = |param, body| RcType::from(Type::Arrow(param, body));
+    let forall = |free_var: &FreeVar<String>, body| {
+        RcType::from(Type::Forall(Scope::new(Binder(free_var.clone()), body)))
+    };
+
+    let a = FreeVar::fresh_named("a");
+    let b = FreeVar::fresh_named("b");
+    let m = MetaVar::fresh_named("m");
+
+    // ty1 = forall a. a -> ?m
+    let ty1 = forall(&a, arrow(var(&a), meta(&m)));
+    // ty2 = forall b. b -> Int
+    let ty2 = forall(&b, arrow(var(&b), RcType::from(Type::Int)));
+
+    let mut unifier = Unifier::new();
+    unifier.unify(&ty1, &ty2).unwrap();
+
+    assert_eq!(unifier.subst().len(), 1);
+    assert_term_eq!(unifier.subst()[&m], RcType::from(Type::Int));
+    assert_term_eq!(unifier.zonk(ty1), ty2);
+}
+
+#[test]
+fn test_unify_forall_escape() {
+    let var = |free_var: &FreeVar<String>| RcType::from(Type::Var(Var::Free(free_var.clone())));
+    let meta = |meta_var: &MetaVar<String>| RcType::from(Type::Meta(meta_var.clone()));
+    let arrow = |param, body| RcType::from(Type::Arrow(param, body));
+    let forall = |free_var: &FreeVar<String>, body| {
+        RcType::from(Type::Forall(Scope::new(Binder(free_var.clone()), body)))
+    };
+
+    let a = FreeVar::fresh_named("a");
+    let b = FreeVar::fresh_named("b");
+    let m = MetaVar::fresh_named("m");
+
+    // ty1 = forall a. a -> ?m
+    let ty1 = forall(&a, arrow(var(&a), meta(&m)));
+    // ty2 = forall b. b -> b
+    let ty2 = forall(&b, arrow(var(&b), var(&b)));
+
+    let mut unifier = Unifier::new();
+    match unifier.unify(&ty1, &ty2) {
+        Err(UnifyError::Escape { ref meta_var, .. }) if *meta_var == m => {},
+        result => panic!("expected the solution of `{}` to escape: {:?}", m, result),
+    }
+    assert!(unifier.subst().is_empty());
+    assert!(unifier.rigid_vars().is_empty());
+}
+
+#[test]
+fn test_unify_occurs() {
+    let meta = |meta_var: &MetaVar<String>| RcType::from(Type::Meta(meta_var.clone()));
+    let arrow = |param, body| RcType::from(Type::Arrow(param, body));
+
+    let m = MetaVar::fresh_named("m");
+
+    // ?m = ?m -> Int
+    let ty1 = meta(&m);
+    let ty2 = arrow(meta(&m), RcType::from(Type::Int));
+
+    let mut unifier = Unifier::new();
+    assert_eq!(unifier.unify(&ty1, &ty2), Err(UnifyError::Occurs(m)));
+}
+
+#[test]
+fn test_unify_zonk() {
+    let meta = |meta_var: &MetaVar<String>| RcType::from(Type::Meta(meta_var.clone()));
+    let arrow = |param, body| RcType::from(Type::Arrow(param, body));
+
+    let m1 = MetaVar::fresh_named("m1");
+    let m2 = MetaVar::fresh_named("m2");
+
+    // ?m1 -> ?m2 = ?m2 -> Int
+    let ty1 = arrow(meta(&m1), meta(&m2));
+    let ty2 = arrow(meta(&m2), RcType::from(Type::Int));
+
+    let mut unifier = Unifier::new();
+    unifier.unify(&ty1, &ty2).unwrap();
+
+    // the substitution is not zonked, so `?m1` is solved to `?m2`
+    assert_eq!(unifier.subst().len(), 2);
+    assert_term_eq!(unifier.subst()[&m1], meta(&m2));
+    assert_term_eq!(unifier.subst()[&m2], RcType::from(Type::Int));
+
+    let ty = unifier.zonk(ty1);
+    assert_term_eq!(ty, arrow(RcType::from(Type::Int), RcType::from(Type::Int)));
+    assert!(ty.meta_vars().is_empty());
+}
+
+fn main() {}
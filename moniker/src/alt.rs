@@ -0,0 +1,365 @@
+use std::any::Any;
+use std::error::Error;
+use std::fmt;
+
+use binder::{Binder, DuplicateBinderError};
+use bound::{BoundPattern, ScopeState};
+use diff::TermDiff;
+use meta_var::MetaVar;
+use node::Node;
+use strict::PatternEqStrict;
+use unify::{Unifier, Unify, UnifyError};
+use walk::{PatternTerms, Walker, WalkerMut};
+
+/// Alternative binding patterns that share a single set of binders
+///
+/// This is useful for or-patterns like `A(x) | B(x)`, where the body of a
+/// match arm refers to `x` regardless of which alternative matched. Only the
+/// binders of the first alternative are visited, so each shared binder is
+/// counted once and is assigned a single `BinderIndex`. The binders of the
+/// other alternatives are replaced with the corresponding binders from the
+/// first alternative when the pattern is constructed, so the terms embedded
+/// in every alternative are closed and opened over the same binders.
+///
+/// Terms outside of the pattern, like the body of a `Scope`, should refer to
+/// the binders of the first alternative.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alt<P> {
+    pub unsafe_alternatives: Vec<P>,
+}
+
+impl<P> Alt<P> {
+    /// Combine the given alternatives
+    ///
+    /// # Panics
+    ///
+    /// Panics if the alternatives don't all bind the same names, or if an
+    /// alternative binds the same name more than once. Use `Alt::try_new` to
+    /// handle these cases as errors instead.
+    pub fn new<N>(alternatives: Vec<P>) -> Alt<P>
+    where
+        N: Clone + PartialEq + fmt::Display,
+        P: BoundPattern<N>,
+    {
+        match Alt::try_new(alternatives) {
+            Ok(alt) => alt,
+            Err(error) => panic!("invalid alternatives: {}", error),
+        }
+    }
+
+    /// Combine the given alternatives, returning an error if they don't all
+    /// bind the same names, or if an alternative binds the same name more than
+    /// once
+    ///
+    /// Binders are matched up with the binders of the first alternative if
+    /// they have the same free variables or the same pretty names. Any
+    /// remaining binders without pretty names are matched up in the order that
+    /// they appear in each alternative.
+    pub fn try_new<N>(mut alternatives: Vec<P>) -> Result<Alt<P>, AltBindersError<N>>
+    where
+        N: Clone + PartialEq,
+        P: BoundPattern<N>,
+    {
+        if let Some((first, rest)) = alternatives.split_first_mut() {
+            check_distinct(first, 0)?;
+            let shared = first.binders();
+
+            for (index, alternative) in rest.iter_mut().enumerate() {
+                let alternative_index = index + 1;
+                check_distinct(alternative, alternative_index)?;
+                let binders = alternative.binders();
+
+                let matches = match_binders(&shared, &binders, alternative_index)?;
+                alternative.visit_mut_binders(&mut |binder| {
+                    if let Some(&(_, shared_index)) =
+                        matches.iter().find(|(old_binder, _)| old_binder == binder)
+                    {
+                        *binder = shared[shared_index].clone();
+                    }
+                });
+            }
+        }
+
+        Ok(Alt {
+            unsafe_alternatives: alternatives,
+        })
+    }
+
+    /// The alternatives of the pattern
+    pub fn alternatives(&self) -> &[P] {
+        &self.unsafe_alternatives
+    }
+
+    /// Return the alternatives
+    pub fn unalt(self) -> Vec<P> {
+        self.unsafe_alternatives
+    }
+}
+
+/// Check that an alternative binds distinct free variables with distinct
+/// pretty names
+fn check_distinct<N, P>(pattern: &P, alternative: usize) -> Result<(), AltBindersError<N>>
+where
+    N: Clone + PartialEq,
+    P: BoundPattern<N>,
+{
+    let duplicate = |binder: &Binder<N>| AltBindersError::Duplicate {
+        binder: binder.clone(),
+        alternative,
+    };
+
+    DuplicateBinderError::check(pattern).map_err(|error| duplicate(&error.binder))?;
+
+    let binders = pattern.binders();
+    for (index, binder) in binders.iter().enumerate() {
+        if let Some(ref name) = binder.0.pretty_name {
+            if binders[..index]
+                .iter()
+                .any(|other| other.0.pretty_name.as_ref() == Some(name))
+            {
+                return Err(duplicate(binder));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Match up the binders of an alternative with the shared binders, returning
+/// each binder along with the index of the shared binder that it corresponds to
+fn match_binders<N>(
+    shared: &[Binder<N>],
+    binders: &[Binder<N>],
+    alternative: usize,
+) -> Result<Vec<(Binder<N>, usize)>, AltBindersError<N>>
+where
+    N: Clone + PartialEq,
+{
+    let mut shared_matched = vec![false; shared.len()];
+    let mut matches = vec![None; binders.len()];
+
+    // Match binders that refer to the same free variable or that have the
+    // same pretty name
+    for (index, binder) in binders.iter().enumerate() {
+        let shared_index = shared
+            .iter()
+            .position(|shared| shared == binder)
+            .or_else(|| {
+                let name = binder.0.pretty_name.as_ref()?;
+                shared
+                    .iter()
+                    .position(|shared| shared.0.pretty_name.as_ref() == Some(name))
+            });
+
+        if let Some(shared_index) = shared_index {
+            if shared_matched[shared_index] {
+                return Err(AltBindersError::Duplicate {
+                    binder: binder.clone(),
+                    alternative,
+                });
+            }
+            shared_matched[shared_index] = true;
+            matches[index] = Some(shared_index);
+        }
+    }
+
+    // Match up the remaining unnamed binders in order
+    let mut unnamed_shared = (0..shared.len())
+        .filter(|&shared_index| !shared_matched[shared_index])
+        .filter(|&shared_index| shared[shared_index].0.pretty_name.is_none())
+        .collect::<Vec<_>>()
+        .into_iter();
+    for (index, binder) in binders.iter().enumerate() {
+        if matches[index].is_none() && binder.0.pretty_name.is_none() {
+            if let Some(shared_index) = unnamed_shared.next() {
+                shared_matched[shared_index] = true;
+                matches[index] = Some(shared_index);
+            }
+        }
+    }
+
+    if let Some(index) = matches.iter().position(Option::is_none) {
+        return Err(AltBindersError::Missing {
+            binder: binders[index].clone(),
+            alternative: 0,
+        });
+    }
+    if let Some(shared_index) = shared_matched.iter().position(|&matched| !matched) {
+        return Err(AltBindersError::Missing {
+            binder: shared[shared_index].clone(),
+            alternative,
+        });
+    }
+
+    Ok(<_>::zip(
+        binders.iter().cloned(),
+        matches.into_iter().map(Option::unwrap),
+    )
+    .collect())
+}
+
+/// An error that is returned when the alternatives of an `Alt` pattern don't
+/// all bind the same names
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AltBindersError<N> {
+    /// A binder that was not found in one of the alternatives
+    Missing {
+        /// The binder that was missing
+        binder: Binder<N>,
+        /// The index of the alternative that was missing the binder
+        alternative: usize,
+    },
+    /// A binder that was bound more than once in one of the alternatives
+    Duplicate {
+        /// The binder that was found more than once
+        binder: Binder<N>,
+        /// The index of the alternative that bound the binder more than once
+        alternative: usize,
+    },
+}
+
+impl<N: fmt::Display> fmt::Display for AltBindersError<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AltBindersError::Missing {
+                ref binder,
+                alternative,
+            } => write!(
+                f,
+                "`{}` is not bound in alternative {} of the pattern",
+                binder, alternative,
+            ),
+            AltBindersError::Duplicate {
+                ref binder,
+                alternative,
+            } => write!(
+                f,
+                "`{}` is bound more than once in alternative {} of the pattern",
+                binder, alternative,
+            ),
+        }
+    }
+}
+
+impl<N: fmt::Debug + fmt::Display> Error for AltBindersError<N> {}
+
+impl<N, P> BoundPattern<N> for Alt<P>
+where
+    N: Clone + PartialEq,
+    P: BoundPattern<N>,
+{
+    fn pattern_eq(&self, other: &Alt<P>) -> bool {
+        <[P]>::pattern_eq(&self.unsafe_alternatives, &other.unsafe_alternatives)
+    }
+
+    fn pattern_diff(&self, other: &Alt<P>) -> Option<TermDiff>
+    where
+        N: fmt::Display,
+    {
+        <[P]>::pattern_diff(&self.unsafe_alternatives, &other.unsafe_alternatives)
+    }
+
+    fn close_pattern(&mut self, state: ScopeState, binders: &[Binder<N>]) {
+        <[P]>::close_pattern(&mut self.unsafe_alternatives, state, binders);
+    }
+
+    fn open_pattern(&mut self, state: ScopeState, binders: &[Binder<N>]) {
+        <[P]>::open_pattern(&mut self.unsafe_alternatives, state, binders);
+    }
+
+    fn visit_binders(&self, on_binder: &mut impl FnMut(&Binder<N>)) {
+        if let Some(first) = self.unsafe_alternatives.first() {
+            first.visit_binders(on_binder);
+        }
+    }
+
+    fn visit_mut_binders(&mut self, on_binder: &mut impl FnMut(&mut Binder<N>)) {
+        if let Some((first, rest)) = self.unsafe_alternatives.split_first_mut() {
+            // Keep the binders of the other alternatives in sync with the
+            // shared binders when they are renamed
+            let mut renamed = Vec::new();
+            first.visit_mut_binders(&mut |binder| {
+                let old_binder = binder.clone();
+                on_binder(binder);
+                renamed.push((old_binder, binder.clone()));
+            });
+
+            for alternative in rest {
+                alternative.visit_mut_binders(&mut |binder| {
+                    if let Some((_, new_binder)) =
+                        renamed.iter().find(|(old_binder, _)| old_binder == binder)
+                    {
+                        *binder = new_binder.clone();
+                    }
+                });
+            }
+        }
+    }
+
+    fn walk_pattern(&self, terms: PatternTerms, walker: &mut impl Walker<N>) {
+        <[P]>::walk_pattern(&self.unsafe_alternatives, terms, walker);
+    }
+
+    fn walk_mut_pattern(&mut self, terms: PatternTerms, walker: &mut impl WalkerMut<N>) {
+        <[P]>::walk_mut_pattern(&mut self.unsafe_alternatives, terms, walker);
+    }
+}
+
+impl<N, T, P> Unify<N, T> for Alt<P>
+where
+    P: Unify<N, T>,
+{
+    fn unify(&self, other: &Alt<P>, unifier: &mut Unifier<N, T>) -> Result<(), UnifyError<N>> {
+        <[P]>::unify(
+            &self.unsafe_alternatives,
+            &other.unsafe_alternatives,
+            unifier,
+        )
+    }
+
+    fn zonk(&mut self, unifier: &Unifier<N, T>) {
+        <[P]>::zonk(&mut self.unsafe_alternatives, unifier);
+    }
+
+    fn visit_meta_vars(&self, on_meta_var: &mut impl FnMut(&MetaVar<N>)) {
+        <[P]>::visit_meta_vars(&self.unsafe_alternatives, on_meta_var);
+    }
+
+    fn visit_mut_subterms(&mut self, on_subterm: &mut impl FnMut(&mut T)) {
+        <[P]>::visit_mut_subterms(&mut self.unsafe_alternatives, on_subterm);
+    }
+}
+
+impl<P: Node> Node for Alt<P> {
+    fn node_name(&self) -> &'static str {
+        "Alt"
+    }
+
+    fn children(&self) -> Vec<&dyn Node> {
+        self.unsafe_alternatives
+            .iter()
+            .map(|pattern| pattern as &dyn Node)
+            .collect()
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut dyn Node> {
+        self.unsafe_alternatives
+            .iter_mut()
+            .map(|pattern| pattern as &mut dyn Node)
+            .collect()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl<P: PatternEqStrict> PatternEqStrict for Alt<P> {
+    fn pattern_eq_strict(&self, other: &Alt<P>) -> bool {
+        <[P]>::pattern_eq_strict(&self.unsafe_alternatives, &other.unsafe_alternatives)
+    }
+}
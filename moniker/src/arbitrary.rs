@@ -0,0 +1,546 @@
+//! Generators of well-scoped terms and patterns for property testing with
+//! [proptest]
+//!
+//! Values are generated in a `Context`, which records the variables that are
+//! in scope. Variables are only ever drawn from the context, so generated terms
+//! never contain dangling variables. `Scope`, `Nest`, and `Rec` extend the
+//! context with the binders of their patterns, and then bind the generated
+//! terms with `Scope::new`, `Nest::new`, and `Rec::new`, so the result is in
+//! locally nameless form.
+//!
+//! The `ArbitraryTerm` trait can be derived for abstract syntax trees:
+//!
+//! ```rust,ignore
+//! #[derive(Debug, Clone, BoundTerm, ArbitraryTerm)]
+//! pub enum Expr {
+//!     Var(Var<String>),
+//!     Lam(Scope<Binder<String>, RcExpr>),
+//!     App(RcExpr, RcExpr),
+//! }
+//!
+//! proptest! {
+//!     #[test]
+//!     fn closed_terms_are_locally_closed(
+//!         expr in any_term::<_, RcExpr>(&Context::new(4).with_names(|i| format!("x{}", i)))
+//!     ) {
+//!         prop_assert!(expr.is_locally_closed());
+//!     }
+//! }
+//! ```
+//!
+//! Recursion is bounded by the depth of the context, which is decremented by
+//! the impls for `Box`, `Rc`, `Arc`, `Vec`, and `Nest`. Variants that can't be
+//! generated in a context are skipped by the derived impls, for example
+//! variables when there is nothing in scope, or recursive variants when the
+//! depth has run out.
+//!
+//! [proptest]: https://docs.rs/proptest
+
+use std::fmt;
+use std::rc::Rc;
+use std::slice;
+use std::sync::Arc;
+
+use proptest::arbitrary::any;
+use proptest::strategy::{LazyJust, Union};
+use proptest::{collection, option, sample};
+
+pub use proptest::strategy::{BoxedStrategy, Just, Strategy};
+
+use binder::Binder;
+use bound::{BoundPattern, BoundTerm};
+use embed::Embed;
+use free_var::FreeVar;
+use ignore::Ignore;
+use multi::Multi;
+use nest::Nest;
+use rec::Rec;
+use scope::Scope;
+use unordered::Unordered;
+use var::Var;
+use walk::{PatternTerms, WalkerMut};
+
+/// The maximum length of the vectors and nests that are generated
+const MAX_LEN: usize = 3;
+
+/// The variables that are in scope when generating a term or pattern, along
+/// with the remaining recursion depth
+#[derive(Clone)]
+pub struct Context<N> {
+    depth: u32,
+    free_vars: Vec<FreeVar<N>>,
+    binders: Vec<Binder<N>>,
+    names: Option<Rc<dyn Fn(usize) -> N>>,
+}
+
+impl<N: Clone> Context<N> {
+    /// Create an empty context with the given recursion depth
+    ///
+    /// Terms generated in an empty context are closed.
+    pub fn new(depth: u32) -> Context<N> {
+        Context {
+            depth,
+            free_vars: Vec::new(),
+            binders: Vec::new(),
+            names: None,
+        }
+    }
+
+    /// Allow the given free variables to occur in the generated terms, for
+    /// example the names of global definitions
+    pub fn with_free_vars(mut self, free_vars: impl IntoIterator<Item = FreeVar<N>>) -> Context<N> {
+        self.free_vars.extend(free_vars);
+        self
+    }
+
+    /// Give the generated binders pretty names, based on the number of
+    /// binders that are already in scope
+    ///
+    /// Binders are unnamed by default.
+    pub fn with_names(mut self, names: impl Fn(usize) -> N + 'static) -> Context<N> {
+        self.names = Some(Rc::new(names));
+        self
+    }
+
+    /// The remaining recursion depth
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    /// The free variables that may occur in the generated terms
+    pub fn free_vars(&self) -> &[FreeVar<N>] {
+        &self.free_vars
+    }
+
+    /// The binders that are in scope, from outermost to innermost
+    pub fn binders(&self) -> &[Binder<N>] {
+        &self.binders
+    }
+
+    /// The context for the children of a recursive value, or `None` if the
+    /// recursion depth has run out
+    pub fn deeper(&self) -> Option<Context<N>> {
+        if self.depth == 0 {
+            None
+        } else {
+            Some(Context {
+                depth: self.depth - 1,
+                ..self.clone()
+            })
+        }
+    }
+
+    /// Extend the context with the binders of a pattern
+    pub fn extend(&self, binders: &[Binder<N>]) -> Context<N> {
+        let mut ctx = self.clone();
+        ctx.binders.extend(binders.iter().cloned());
+        ctx
+    }
+
+    /// Create a fresh binder, named using the names supplied to
+    /// `Context::with_names`
+    pub fn fresh_binder(&self) -> Binder<N> {
+        let pretty_name = self.names.as_ref().map(|names| names(self.binders.len()));
+        Binder(FreeVar::fresh(pretty_name))
+    }
+}
+
+impl<N: fmt::Debug> fmt::Debug for Context<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Context")
+            .field("depth", &self.depth)
+            .field("free_vars", &self.free_vars)
+            .field("binders", &self.binders)
+            .finish()
+    }
+}
+
+/// Terms and patterns that can be generated in a `Context`
+///
+/// This can be derived with `#[derive(ArbitraryTerm)]`. Fields that are marked
+/// with `#[moniker(ignore)]` are set to their `Default` values.
+pub trait ArbitraryTerm<N>: fmt::Debug + Sized + 'static {
+    /// A strategy for generating well-scoped values in the given context, or
+    /// `None` if no values can be generated in it
+    ///
+    /// Extending the context with more binders must never stop values from
+    /// being generated.
+    fn arbitrary_term(ctx: &Context<N>) -> Option<BoxedStrategy<Self>>;
+
+    /// Returns `true` if values that bind at least one variable can be
+    /// generated in the given context
+    ///
+    /// This is used by `Scope` to avoid generating patterns that can never
+    /// bind the variables that its body needs.
+    fn can_bind(_ctx: &Context<N>) -> bool {
+        false
+    }
+}
+
+/// A strategy for generating well-scoped values in the given context
+///
+/// # Panics
+///
+/// Panics if no values can be generated in the context, for example if the
+/// context is empty and every value of `T` contains a variable.
+pub fn any_term<N, T: ArbitraryTerm<N>>(ctx: &Context<N>) -> BoxedStrategy<T> {
+    T::arbitrary_term(ctx).expect("no well-scoped values can be generated in this context")
+}
+
+/// Choose uniformly between the given strategies, returning `None` if there
+/// are none to choose from
+pub fn one_of<T: fmt::Debug + 'static>(
+    strategies: Vec<BoxedStrategy<T>>,
+) -> Option<BoxedStrategy<T>> {
+    if strategies.is_empty() {
+        None
+    } else {
+        Some(Union::new(strategies).boxed())
+    }
+}
+
+impl<N> ArbitraryTerm<N> for FreeVar<N>
+where
+    N: Clone + fmt::Debug + 'static,
+{
+    fn arbitrary_term(ctx: &Context<N>) -> Option<BoxedStrategy<FreeVar<N>>> {
+        // The binders in scope are not included, because free variables are
+        // left alone when a term is bound
+        if ctx.free_vars.is_empty() {
+            None
+        } else {
+            Some(sample::select(ctx.free_vars.clone()).boxed())
+        }
+    }
+}
+
+impl<N> ArbitraryTerm<N> for Var<N>
+where
+    N: Clone + fmt::Debug + 'static,
+{
+    fn arbitrary_term(ctx: &Context<N>) -> Option<BoxedStrategy<Var<N>>> {
+        let free_vars = ctx.free_vars.iter().cloned();
+        let bound_vars = ctx.binders.iter().map(|binder| binder.0.clone());
+        let vars = free_vars.chain(bound_vars).collect::<Vec<_>>();
+
+        if vars.is_empty() {
+            None
+        } else {
+            Some(sample::select(vars).prop_map(Var::Free).boxed())
+        }
+    }
+}
+
+impl<N> ArbitraryTerm<N> for Binder<N>
+where
+    N: Clone + fmt::Debug + 'static,
+{
+    fn arbitrary_term(ctx: &Context<N>) -> Option<BoxedStrategy<Binder<N>>> {
+        let ctx = ctx.clone();
+        Some(LazyJust::new(move || ctx.fresh_binder()).boxed())
+    }
+
+    fn can_bind(_: &Context<N>) -> bool {
+        true
+    }
+}
+
+impl<N, P, T> ArbitraryTerm<N> for Scope<P, T>
+where
+    N: Clone + fmt::Debug + 'static,
+    P: Clone + ArbitraryTerm<N> + BoundPattern<N>,
+    T: Clone + ArbitraryTerm<N> + BoundTerm<N>,
+{
+    fn arbitrary_term(ctx: &Context<N>) -> Option<BoxedStrategy<Scope<P, T>>> {
+        let mut patterns = P::arbitrary_term(ctx)?;
+
+        if T::arbitrary_term(ctx).is_none() {
+            // The body can only be generated if the pattern binds something,
+            // for example if the body must be a variable
+            if !P::can_bind(ctx) || T::arbitrary_term(&ctx.extend(&[ctx.fresh_binder()])).is_none()
+            {
+                return None;
+            }
+            patterns = patterns
+                .prop_filter("pattern binds no variables", |pattern| {
+                    !pattern.binders().is_empty()
+                })
+                .boxed();
+        }
+
+        let ctx = ctx.clone();
+        let scopes = patterns.prop_flat_map(move |pattern| {
+            let bodies = T::arbitrary_term(&ctx.extend(&pattern.binders()))
+                .expect("extending the context should not stop bodies from being generated");
+            bodies.prop_map(move |body| Scope::new(pattern.clone(), body))
+        });
+
+        Some(scopes.boxed())
+    }
+}
+
+impl<N, P> ArbitraryTerm<N> for Nest<P>
+where
+    N: Clone + fmt::Debug + 'static,
+    P: Clone + ArbitraryTerm<N> + BoundPattern<N>,
+{
+    fn arbitrary_term(ctx: &Context<N>) -> Option<BoxedStrategy<Nest<P>>> {
+        let ctx = match ctx.deeper() {
+            Some(ctx) => ctx,
+            None => return Some(LazyJust::new(|| Nest::new::<N>(Vec::new())).boxed()),
+        };
+        if P::arbitrary_term(&ctx).is_none() {
+            return Some(LazyJust::new(|| Nest::new::<N>(Vec::new())).boxed());
+        }
+
+        let nests = (0..=MAX_LEN)
+            .prop_flat_map(move |len| nested_patterns(&ctx, len))
+            .prop_map(Nest::new::<N>);
+
+        Some(nests.boxed())
+    }
+
+    fn can_bind(ctx: &Context<N>) -> bool {
+        ctx.deeper().map_or(false, |ctx| P::can_bind(&ctx))
+    }
+}
+
+/// Generate a sequence of patterns, where each pattern is generated in the
+/// scope of the binders of the patterns before it
+fn nested_patterns<N, P>(ctx: &Context<N>, len: usize) -> BoxedStrategy<Vec<P>>
+where
+    N: Clone + fmt::Debug + 'static,
+    P: Clone + ArbitraryTerm<N> + BoundPattern<N>,
+{
+    if len == 0 {
+        return LazyJust::new(Vec::new).boxed();
+    }
+
+    let ctx = ctx.clone();
+    let patterns = P::arbitrary_term(&ctx)
+        .expect("extending the context should not stop patterns from being generated")
+        .prop_flat_map(move |pattern| {
+            let rest = nested_patterns(&ctx.extend(&pattern.binders()), len - 1);
+            rest.prop_map(move |mut patterns: Vec<P>| {
+                patterns.insert(0, pattern.clone());
+                patterns
+            })
+        });
+
+    patterns.boxed()
+}
+
+impl<N, P> ArbitraryTerm<N> for Rec<P>
+where
+    N: Clone + fmt::Debug + 'static,
+    P: Clone + ArbitraryTerm<N> + BoundPattern<N>,
+{
+    fn arbitrary_term(ctx: &Context<N>) -> Option<BoxedStrategy<Rec<P>>> {
+        // The terms embedded in the pattern can refer to the binders of the
+        // pattern itself, but these are only known once the pattern has been
+        // generated. So we generate the pattern in a context that contains a
+        // placeholder binder, and then replace the occurrences of the
+        // placeholder with the binders of the pattern.
+        let placeholder = ctx.fresh_binder();
+        let patterns = P::arbitrary_term(&ctx.extend(slice::from_ref(&placeholder)))?;
+
+        let recs = patterns.prop_filter_map("pattern binds no variables", move |mut pattern| {
+            let binders = pattern.binders();
+            let mut replacer = PlaceholderReplacer {
+                placeholder: &placeholder.0,
+                binders: &binders,
+                count: 0,
+            };
+            pattern.walk_mut_pattern(PatternTerms::All, &mut replacer);
+
+            if replacer.count > 0 && binders.is_empty() {
+                None
+            } else {
+                Some(Rec::new(pattern))
+            }
+        });
+
+        Some(recs.boxed())
+    }
+
+    fn can_bind(ctx: &Context<N>) -> bool {
+        P::can_bind(&ctx.extend(&[ctx.fresh_binder()]))
+    }
+}
+
+/// Replaces the occurrences of a placeholder variable with the given binders,
+/// in turn
+struct PlaceholderReplacer<'a, N: 'a> {
+    placeholder: &'a FreeVar<N>,
+    binders: &'a [Binder<N>],
+    count: usize,
+}
+
+impl<'a, N: Clone> WalkerMut<N> for PlaceholderReplacer<'a, N> {
+    fn on_var(&mut self, var: &mut Var<N>) {
+        if let Var::Free(ref mut free_var) = *var {
+            if free_var.unique_id == self.placeholder.unique_id {
+                if !self.binders.is_empty() {
+                    *free_var = self.binders[self.count % self.binders.len()].0.clone();
+                }
+                self.count += 1;
+            }
+        }
+    }
+}
+
+impl<N, T> ArbitraryTerm<N> for Embed<T>
+where
+    N: Clone + fmt::Debug + 'static,
+    T: ArbitraryTerm<N>,
+{
+    fn arbitrary_term(ctx: &Context<N>) -> Option<BoxedStrategy<Embed<T>>> {
+        Some(T::arbitrary_term(ctx)?.prop_map(Embed).boxed())
+    }
+}
+
+impl<N, T> ArbitraryTerm<N> for Ignore<T>
+where
+    T: Default + fmt::Debug + 'static,
+{
+    fn arbitrary_term(_: &Context<N>) -> Option<BoxedStrategy<Ignore<T>>> {
+        Some(LazyJust::new(Ignore::default).boxed())
+    }
+}
+
+impl<N, P> ArbitraryTerm<N> for Multi<P>
+where
+    N: Clone + fmt::Debug + 'static,
+    P: ArbitraryTerm<N> + BoundPattern<N>,
+{
+    fn arbitrary_term(ctx: &Context<N>) -> Option<BoxedStrategy<Multi<P>>> {
+        Some(
+            Vec::<P>::arbitrary_term(ctx)?
+                .prop_map(Multi::new::<N>)
+                .boxed(),
+        )
+    }
+
+    fn can_bind(ctx: &Context<N>) -> bool {
+        Vec::<P>::can_bind(ctx)
+    }
+}
+
+impl<N, P> ArbitraryTerm<N> for Unordered<P>
+where
+    N: Clone + fmt::Debug + 'static,
+    P: ArbitraryTerm<N>,
+{
+    fn arbitrary_term(ctx: &Context<N>) -> Option<BoxedStrategy<Unordered<P>>> {
+        Some(
+            Vec::<P>::arbitrary_term(ctx)?
+                .prop_map(Unordered::new)
+                .boxed(),
+        )
+    }
+
+    fn can_bind(ctx: &Context<N>) -> bool {
+        Vec::<P>::can_bind(ctx)
+    }
+}
+
+macro_rules! impl_arbitrary_term_any {
+    ($T:ty) => {
+        impl<N> ArbitraryTerm<N> for $T {
+            fn arbitrary_term(_: &Context<N>) -> Option<BoxedStrategy<$T>> {
+                Some(any::<$T>().boxed())
+            }
+        }
+    };
+}
+
+impl_arbitrary_term_any!(());
+impl_arbitrary_term_any!(String);
+impl_arbitrary_term_any!(char);
+impl_arbitrary_term_any!(bool);
+impl_arbitrary_term_any!(u8);
+impl_arbitrary_term_any!(u16);
+impl_arbitrary_term_any!(u32);
+impl_arbitrary_term_any!(u64);
+impl_arbitrary_term_any!(usize);
+impl_arbitrary_term_any!(i8);
+impl_arbitrary_term_any!(i16);
+impl_arbitrary_term_any!(i32);
+impl_arbitrary_term_any!(i64);
+impl_arbitrary_term_any!(isize);
+impl_arbitrary_term_any!(f32);
+impl_arbitrary_term_any!(f64);
+
+impl<N, T> ArbitraryTerm<N> for Option<T>
+where
+    T: ArbitraryTerm<N>,
+{
+    fn arbitrary_term(ctx: &Context<N>) -> Option<BoxedStrategy<Option<T>>> {
+        match T::arbitrary_term(ctx) {
+            Some(values) => Some(option::of(values).boxed()),
+            None => Some(LazyJust::new(|| None).boxed()),
+        }
+    }
+
+    fn can_bind(ctx: &Context<N>) -> bool {
+        T::can_bind(ctx)
+    }
+}
+
+macro_rules! impl_arbitrary_term_pointer {
+    ($P:ident) => {
+        impl<N, T> ArbitraryTerm<N> for $P<T>
+        where
+            N: Clone,
+            T: ArbitraryTerm<N>,
+        {
+            fn arbitrary_term(ctx: &Context<N>) -> Option<BoxedStrategy<$P<T>>> {
+                Some(T::arbitrary_term(&ctx.deeper()?)?.prop_map($P::new).boxed())
+            }
+
+            fn can_bind(ctx: &Context<N>) -> bool {
+                ctx.deeper().map_or(false, |ctx| T::can_bind(&ctx))
+            }
+        }
+    };
+}
+
+impl_arbitrary_term_pointer!(Box);
+impl_arbitrary_term_pointer!(Rc);
+impl_arbitrary_term_pointer!(Arc);
+
+impl<N, T> ArbitraryTerm<N> for Vec<T>
+where
+    N: Clone,
+    T: ArbitraryTerm<N>,
+{
+    fn arbitrary_term(ctx: &Context<N>) -> Option<BoxedStrategy<Vec<T>>> {
+        match ctx.deeper().and_then(|ctx| T::arbitrary_term(&ctx)) {
+            Some(values) => Some(collection::vec(values, 0..=MAX_LEN).boxed()),
+            None => Some(LazyJust::new(Vec::new).boxed()),
+        }
+    }
+
+    fn can_bind(ctx: &Context<N>) -> bool {
+        ctx.deeper().map_or(false, |ctx| T::can_bind(&ctx))
+    }
+}
+
+macro_rules! impl_arbitrary_term_tuple {
+    ($($T:ident),+) => {
+        impl<N, $($T: ArbitraryTerm<N>),+> ArbitraryTerm<N> for ($($T,)+) {
+            fn arbitrary_term(ctx: &Context<N>) -> Option<BoxedStrategy<($($T,)+)>> {
+                Some(($($T::arbitrary_term(ctx)?,)+).boxed())
+            }
+
+            fn can_bind(ctx: &Context<N>) -> bool {
+                $($T::arbitrary_term(ctx).is_some() &&)+ ($($T::can_bind(ctx))||+)
+            }
+        }
+    };
+}
+
+impl_arbitrary_term_tuple!(T1, T2);
+impl_arbitrary_term_tuple!(T1, T2, T3);
+impl_arbitrary_term_tuple!(T1, T2, T3, T4);
+impl_arbitrary_term_tuple!(T1, T2, T3, T4, T5);
@@ -1,5 +1,8 @@
+use std::collections::HashSet;
+use std::error::Error;
 use std::fmt;
 
+use bound::BoundPattern;
 use free_var::FreeVar;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -11,6 +14,49 @@ impl<N: fmt::Display> fmt::Display for Binder<N> {
     }
 }
 
+/// An error that is returned when a pattern binds the same free variable more
+/// than once
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateBinderError<N> {
+    /// The binder that was found more than once in the pattern
+    pub binder: Binder<N>,
+}
+
+impl<N> DuplicateBinderError<N> {
+    /// Check that the pattern does not bind the same free variable more than
+    /// once, returning the first duplicated binder if it does
+    pub fn check<P>(pattern: &P) -> Result<(), DuplicateBinderError<N>>
+    where
+        N: Clone,
+        P: BoundPattern<N> + ?Sized,
+    {
+        let mut seen = HashSet::new();
+        let mut duplicate = None;
+        pattern.visit_binders(&mut |binder| {
+            if duplicate.is_none() && !seen.insert(binder.0.unique_id) {
+                duplicate = Some(binder.clone());
+            }
+        });
+
+        match duplicate {
+            None => Ok(()),
+            Some(binder) => Err(DuplicateBinderError { binder }),
+        }
+    }
+}
+
+impl<N: fmt::Display> fmt::Display for DuplicateBinderError<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "`{}` is bound more than once in the same pattern",
+            self.binder
+        )
+    }
+}
+
+impl<N: fmt::Debug + fmt::Display> Error for DuplicateBinderError<N> {}
+
 impl<N> PartialEq<FreeVar<N>> for Binder<N>
 where
     N: PartialEq,
@@ -19,6 +19,10 @@ macro_rules! impl_bound_term_ignore {
             fn visit_vars(&self, _: &mut impl FnMut(&Var<N>)) {}
 
             fn visit_mut_vars(&mut self, _: &mut impl FnMut(&mut Var<N>)) {}
+
+            fn walk_term(&self, _: &mut impl Walker<N>) {}
+
+            fn walk_mut_term(&mut self, _: &mut impl WalkerMut<N>) {}
         }
     };
 }
@@ -44,6 +48,10 @@ impl<N, T> BoundTerm<N> for Span<T> {
     fn visit_vars(&self, _: &mut impl FnMut(&Var<N>)) {}
 
     fn visit_mut_vars(&mut self, _: &mut impl FnMut(&mut Var<N>)) {}
+
+    fn walk_term(&self, _: &mut impl Walker<N>) {}
+
+    fn walk_mut_term(&mut self, _: &mut impl WalkerMut<N>) {}
 }
 
 macro_rules! impl_bound_pattern_ignore {
@@ -60,6 +68,10 @@ macro_rules! impl_bound_pattern_ignore {
             fn visit_binders(&self, _: &mut impl FnMut(&Binder<N>)) {}
 
             fn visit_mut_binders(&mut self, _: &mut impl FnMut(&mut Binder<N>)) {}
+
+            fn walk_pattern(&self, _: PatternTerms, _: &mut impl Walker<N>) {}
+
+            fn walk_mut_pattern(&mut self, _: PatternTerms, _: &mut impl WalkerMut<N>) {}
         }
     };
 }
@@ -85,4 +97,8 @@ impl<N, T> BoundPattern<N> for Span<T> {
     fn visit_binders(&self, _: &mut impl FnMut(&Binder<N>)) {}
 
     fn visit_mut_binders(&mut self, _: &mut impl FnMut(&mut Binder<N>)) {}
+
+    fn walk_pattern(&self, _: PatternTerms, _: &mut impl Walker<N>) {}
+
+    fn walk_mut_pattern(&mut self, _: PatternTerms, _: &mut impl WalkerMut<N>) {}
 }
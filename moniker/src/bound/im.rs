@@ -11,6 +11,23 @@ where
             && <_>::zip(self.iter(), other.iter()).all(|(lhs, rhs)| T::term_eq(lhs, rhs))
     }
 
+    fn term_diff(&self, other: &Vector<T>) -> Option<TermDiff>
+    where
+        N: fmt::Display,
+    {
+        if self.len() != other.len() {
+            return Some(TermDiff::new(
+                format!("[..; {}]", self.len()),
+                format!("[..; {}]", other.len()),
+            ));
+        }
+
+        <_>::zip(self.iter(), other.iter())
+            .enumerate()
+            .filter_map(|(i, (lhs, rhs))| Some(T::term_diff(lhs, rhs)?.within_index(i)))
+            .next()
+    }
+
     fn close_term(&mut self, state: ScopeState, binders: &[Binder<N>]) {
         for elem in self.iter_mut() {
             elem.close_term(state, binders);
@@ -34,6 +51,18 @@ where
             elem.visit_mut_vars(on_var);
         }
     }
+
+    fn walk_term(&self, walker: &mut impl Walker<N>) {
+        for elem in self.iter() {
+            elem.walk_term(walker);
+        }
+    }
+
+    fn walk_mut_term(&mut self, walker: &mut impl WalkerMut<N>) {
+        for elem in self.iter_mut() {
+            elem.walk_mut_term(walker);
+        }
+    }
 }
 
 impl<N, P> BoundPattern<N> for Vector<P>
@@ -46,6 +75,23 @@ where
             && <_>::zip(self.iter(), other.iter()).all(|(lhs, rhs)| P::pattern_eq(lhs, rhs))
     }
 
+    fn pattern_diff(&self, other: &Vector<P>) -> Option<TermDiff>
+    where
+        N: fmt::Display,
+    {
+        if self.len() != other.len() {
+            return Some(TermDiff::new(
+                format!("[..; {}]", self.len()),
+                format!("[..; {}]", other.len()),
+            ));
+        }
+
+        <_>::zip(self.iter(), other.iter())
+            .enumerate()
+            .filter_map(|(i, (lhs, rhs))| Some(P::pattern_diff(lhs, rhs)?.within_index(i)))
+            .next()
+    }
+
     fn close_pattern(&mut self, state: ScopeState, binders: &[Binder<N>]) {
         for elem in self.iter_mut() {
             elem.close_pattern(state, binders);
@@ -69,4 +115,16 @@ where
             elem.visit_mut_binders(on_binder);
         }
     }
+
+    fn walk_pattern(&self, terms: PatternTerms, walker: &mut impl Walker<N>) {
+        for elem in self.iter() {
+            elem.walk_pattern(terms, walker);
+        }
+    }
+
+    fn walk_mut_pattern(&mut self, terms: PatternTerms, walker: &mut impl WalkerMut<N>) {
+        for elem in self.iter_mut() {
+            elem.walk_mut_pattern(terms, walker);
+        }
+    }
 }
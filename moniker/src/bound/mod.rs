@@ -1,12 +1,17 @@
 use std::collections::HashSet;
+use std::fmt;
 use std::hash::Hash;
 use std::rc::Rc;
 use std::sync::Arc;
 
 use binder::Binder;
 use bound_var::{BinderIndex, BoundVar, ScopeOffset};
+use diff::TermDiff;
 use free_var::FreeVar;
+use invariants;
+use unique_id::UniqueId;
 use var::Var;
+use walk::{PatternTerms, Walker, WalkerMut};
 
 #[cfg(feature = "codespan")]
 mod codespan;
@@ -18,20 +23,88 @@ mod num_bigint;
 #[derive(Debug, Copy, Clone)]
 pub struct ScopeState {
     depth: u32,
+    /// The depth of the terms in the pattern that are outside of the scope of
+    /// the pattern's binders, or `None` if they are unaffected by the binders
+    outer_depth: Option<u32>,
+    /// The depth of the terms in the pattern that are inside the scope of the
+    /// pattern's binders, or `None` if they are unaffected by the binders
+    inner_depth: Option<u32>,
 }
 
 impl ScopeState {
     pub fn new() -> ScopeState {
-        ScopeState { depth: 0 }
+        ScopeState::at_depth(0)
+    }
+
+    fn at_depth(depth: u32) -> ScopeState {
+        ScopeState {
+            depth,
+            outer_depth: Some(depth),
+            inner_depth: Some(depth),
+        }
     }
 
     pub fn depth(&self) -> ScopeOffset {
         ScopeOffset(self.depth)
     }
 
-    pub fn incr(mut self) -> ScopeState {
-        self.depth += 1;
-        self
+    pub fn incr(self) -> ScopeState {
+        ScopeState {
+            depth: self.depth + 1,
+            outer_depth: self.outer_depth.map(|depth| depth + 1),
+            inner_depth: self.inner_depth.map(|depth| depth + 1),
+        }
+    }
+
+    /// The state for closing or opening the pattern of a scope with binders
+    /// from outside of that scope
+    pub(crate) fn enclosing(self) -> ScopeState {
+        ScopeState {
+            // The binders of the pattern's scope are between the terms inside
+            // the scope and the binders from outside of the scope
+            inner_depth: self.inner_depth.map(|depth| depth + 1),
+            ..self
+        }
+    }
+
+    /// The state for closing or opening the pattern of a scope with the
+    /// pattern's own binders
+    pub(crate) fn own() -> ScopeState {
+        ScopeState {
+            outer_depth: None,
+            ..ScopeState::new()
+        }
+    }
+
+    /// The state for closing or opening a pattern whose embedded terms skip
+    /// the innermost frame of binders
+    pub fn shift(self) -> ScopeState {
+        let shift = |depth: Option<u32>| match depth {
+            Some(0) | None => None,
+            Some(depth) => Some(depth - 1),
+        };
+
+        ScopeState {
+            depth: self.depth,
+            outer_depth: shift(self.outer_depth),
+            inner_depth: shift(self.inner_depth),
+        }
+    }
+
+    /// The state for closing or opening a term that is embedded in a pattern,
+    /// outside of the scope of the pattern's binders
+    ///
+    /// Returns `None` if the term is unaffected by the binders.
+    pub fn outer_term(self) -> Option<ScopeState> {
+        self.outer_depth.map(ScopeState::at_depth)
+    }
+
+    /// The state for closing or opening a term that is embedded in a pattern,
+    /// inside the scope of the pattern's binders
+    ///
+    /// Returns `None` if the term is unaffected by the binders.
+    pub fn inner_term(self) -> Option<ScopeState> {
+        self.inner_depth.map(ScopeState::at_depth)
     }
 }
 
@@ -40,6 +113,22 @@ pub trait BoundTerm<N> {
     /// Alpha equivalence for terms
     fn term_eq(&self, other: &Self) -> bool;
 
+    /// Find the first point where this term stops being alpha equivalent to
+    /// `other`, returning `None` if they are alpha equivalent
+    ///
+    /// The default implementation compares the terms with `term_eq`, and
+    /// reports any difference at the root of the term.
+    fn term_diff(&self, other: &Self) -> Option<TermDiff>
+    where
+        N: fmt::Display,
+    {
+        if self.term_eq(other) {
+            None
+        } else {
+            Some(TermDiff::new("..", ".."))
+        }
+    }
+
     /// Close the term using the supplied binders
     fn close_term(&mut self, state: ScopeState, binders: &[Binder<N>]);
 
@@ -54,6 +143,14 @@ pub trait BoundTerm<N> {
     /// of them in turn
     fn visit_mut_vars(&mut self, on_var: &mut impl FnMut(&mut Var<N>));
 
+    /// Walk the binding structure of the term, calling the `walker` on each
+    /// frame of binders and each variable in turn
+    fn walk_term(&self, walker: &mut impl Walker<N>);
+
+    /// Walk the binding structure of the term, calling the `walker` on each
+    /// frame of binders and each variable in turn, with mutable access to them
+    fn walk_mut_term(&mut self, walker: &mut impl WalkerMut<N>);
+
     /// Returns the set of free variables in this term
     fn free_vars(&self) -> HashSet<FreeVar<N>>
     where
@@ -68,6 +165,12 @@ pub trait BoundTerm<N> {
         });
         free_vars
     }
+
+    /// Returns `true` if every bound variable in the term refers to a binder
+    /// that is found within the term itself
+    fn is_locally_closed(&self) -> bool {
+        invariants::check_term(self).is_ok()
+    }
 }
 
 impl<N: PartialEq> BoundTerm<N> for FreeVar<N> {
@@ -75,6 +178,17 @@ impl<N: PartialEq> BoundTerm<N> for FreeVar<N> {
         self == other
     }
 
+    fn term_diff(&self, other: &FreeVar<N>) -> Option<TermDiff>
+    where
+        N: fmt::Display,
+    {
+        if self == other {
+            None
+        } else {
+            Some(TermDiff::new(self.to_string(), other.to_string()))
+        }
+    }
+
     fn close_term(&mut self, _: ScopeState, _: &[Binder<N>]) {}
 
     fn open_term(&mut self, _: ScopeState, _: &[Binder<N>]) {}
@@ -82,6 +196,10 @@ impl<N: PartialEq> BoundTerm<N> for FreeVar<N> {
     fn visit_vars(&self, _: &mut impl FnMut(&Var<N>)) {}
 
     fn visit_mut_vars(&mut self, _: &mut impl FnMut(&mut Var<N>)) {}
+
+    fn walk_term(&self, _: &mut impl Walker<N>) {}
+
+    fn walk_mut_term(&mut self, _: &mut impl WalkerMut<N>) {}
 }
 
 impl<N: PartialEq + Clone> BoundTerm<N> for Var<N> {
@@ -89,6 +207,17 @@ impl<N: PartialEq + Clone> BoundTerm<N> for Var<N> {
         self == other
     }
 
+    fn term_diff(&self, other: &Var<N>) -> Option<TermDiff>
+    where
+        N: fmt::Display,
+    {
+        if self == other {
+            None
+        } else {
+            Some(TermDiff::new(self.to_string(), other.to_string()))
+        }
+    }
+
     fn close_term(&mut self, state: ScopeState, binders: &[Binder<N>]) {
         // NOTE: Working around NLL
         *self = match *self {
@@ -138,6 +267,14 @@ impl<N: PartialEq + Clone> BoundTerm<N> for Var<N> {
     fn visit_mut_vars(&mut self, on_var: &mut impl FnMut(&mut Var<N>)) {
         on_var(self);
     }
+
+    fn walk_term(&self, walker: &mut impl Walker<N>) {
+        walker.on_var(self);
+    }
+
+    fn walk_mut_term(&mut self, walker: &mut impl WalkerMut<N>) {
+        walker.on_var(self);
+    }
 }
 
 // Implementations for common types
@@ -149,6 +286,14 @@ macro_rules! impl_bound_term_partial_eq {
                 self == other
             }
 
+            fn term_diff(&self, other: &$T) -> Option<TermDiff> {
+                if self == other {
+                    None
+                } else {
+                    Some(TermDiff::leaves(self, other))
+                }
+            }
+
             fn close_term(&mut self, _: ScopeState, _: &[Binder<N>]) {}
 
             fn open_term(&mut self, _: ScopeState, _: &[Binder<N>]) {}
@@ -156,6 +301,10 @@ macro_rules! impl_bound_term_partial_eq {
             fn visit_vars(&self, _: &mut impl FnMut(&Var<N>)) {}
 
             fn visit_mut_vars(&mut self, _: &mut impl FnMut(&mut Var<N>)) {}
+
+            fn walk_term(&self, _: &mut impl Walker<N>) {}
+
+            fn walk_mut_term(&mut self, _: &mut impl WalkerMut<N>) {}
         }
     };
 }
@@ -190,6 +339,18 @@ where
         }
     }
 
+    fn term_diff(&self, other: &Option<T>) -> Option<TermDiff>
+    where
+        N: fmt::Display,
+    {
+        match (self.as_ref(), other.as_ref()) {
+            (Some(lhs), Some(rhs)) => T::term_diff(lhs, rhs),
+            (None, None) => None,
+            (Some(_), None) => Some(TermDiff::new("Some(..)", "None")),
+            (None, Some(_)) => Some(TermDiff::new("None", "Some(..)")),
+        }
+    }
+
     fn close_term(&mut self, state: ScopeState, binders: &[Binder<N>]) {
         if let Some(ref mut inner) = *self {
             inner.close_term(state, binders);
@@ -213,6 +374,18 @@ where
             inner.visit_mut_vars(on_var);
         }
     }
+
+    fn walk_term(&self, walker: &mut impl Walker<N>) {
+        if let Some(ref inner) = *self {
+            inner.walk_term(walker);
+        }
+    }
+
+    fn walk_mut_term(&mut self, walker: &mut impl WalkerMut<N>) {
+        if let Some(ref mut inner) = *self {
+            inner.walk_mut_term(walker);
+        }
+    }
 }
 
 impl<N, T> BoundTerm<N> for Box<T>
@@ -223,6 +396,13 @@ where
         T::term_eq(self, other)
     }
 
+    fn term_diff(&self, other: &Box<T>) -> Option<TermDiff>
+    where
+        N: fmt::Display,
+    {
+        T::term_diff(self, other)
+    }
+
     fn close_term(&mut self, state: ScopeState, binders: &[Binder<N>]) {
         T::close_term(self, state, binders);
     }
@@ -238,6 +418,14 @@ where
     fn visit_mut_vars(&mut self, on_var: &mut impl FnMut(&mut Var<N>)) {
         T::visit_mut_vars(self, on_var);
     }
+
+    fn walk_term(&self, walker: &mut impl Walker<N>) {
+        T::walk_term(self, walker);
+    }
+
+    fn walk_mut_term(&mut self, walker: &mut impl WalkerMut<N>) {
+        T::walk_mut_term(self, walker);
+    }
 }
 
 impl<N, T> BoundTerm<N> for Rc<T>
@@ -248,6 +436,13 @@ where
         T::term_eq(self, other)
     }
 
+    fn term_diff(&self, other: &Rc<T>) -> Option<TermDiff>
+    where
+        N: fmt::Display,
+    {
+        T::term_diff(self, other)
+    }
+
     fn close_term(&mut self, state: ScopeState, binders: &[Binder<N>]) {
         T::close_term(Rc::make_mut(self), state, binders);
     }
@@ -263,6 +458,14 @@ where
     fn visit_mut_vars(&mut self, on_var: &mut impl FnMut(&mut Var<N>)) {
         T::visit_mut_vars(Rc::make_mut(self), on_var);
     }
+
+    fn walk_term(&self, walker: &mut impl Walker<N>) {
+        T::walk_term(self, walker);
+    }
+
+    fn walk_mut_term(&mut self, walker: &mut impl WalkerMut<N>) {
+        T::walk_mut_term(Rc::make_mut(self), walker);
+    }
 }
 
 impl<N, T> BoundTerm<N> for Arc<T>
@@ -273,6 +476,13 @@ where
         T::term_eq(self, other)
     }
 
+    fn term_diff(&self, other: &Arc<T>) -> Option<TermDiff>
+    where
+        N: fmt::Display,
+    {
+        T::term_diff(self, other)
+    }
+
     fn close_term(&mut self, state: ScopeState, binders: &[Binder<N>]) {
         T::close_term(Arc::make_mut(self), state, binders);
     }
@@ -288,6 +498,14 @@ where
     fn visit_mut_vars(&mut self, on_var: &mut impl FnMut(&mut Var<N>)) {
         T::visit_mut_vars(Arc::make_mut(self), on_var);
     }
+
+    fn walk_term(&self, walker: &mut impl Walker<N>) {
+        T::walk_term(self, walker);
+    }
+
+    fn walk_mut_term(&mut self, walker: &mut impl WalkerMut<N>) {
+        T::walk_mut_term(Arc::make_mut(self), walker);
+    }
 }
 
 impl<N, T1, T2> BoundTerm<N> for (T1, T2)
@@ -299,6 +517,15 @@ where
         T1::term_eq(&self.0, &other.0) && T2::term_eq(&self.1, &other.1)
     }
 
+    fn term_diff(&self, other: &(T1, T2)) -> Option<TermDiff>
+    where
+        N: fmt::Display,
+    {
+        T1::term_diff(&self.0, &other.0)
+            .map(|diff| diff.within("0"))
+            .or_else(|| T2::term_diff(&self.1, &other.1).map(|diff| diff.within("1")))
+    }
+
     fn close_term(&mut self, state: ScopeState, binders: &[Binder<N>]) {
         self.0.close_term(state, binders);
         self.1.close_term(state, binders);
@@ -318,6 +545,16 @@ where
         self.0.visit_mut_vars(on_var);
         self.1.visit_mut_vars(on_var);
     }
+
+    fn walk_term(&self, walker: &mut impl Walker<N>) {
+        self.0.walk_term(walker);
+        self.1.walk_term(walker);
+    }
+
+    fn walk_mut_term(&mut self, walker: &mut impl WalkerMut<N>) {
+        self.0.walk_mut_term(walker);
+        self.1.walk_mut_term(walker);
+    }
 }
 
 impl<N, T1, T2, T3> BoundTerm<N> for (T1, T2, T3)
@@ -332,6 +569,16 @@ where
             && T3::term_eq(&self.2, &other.2)
     }
 
+    fn term_diff(&self, other: &(T1, T2, T3)) -> Option<TermDiff>
+    where
+        N: fmt::Display,
+    {
+        T1::term_diff(&self.0, &other.0)
+            .map(|diff| diff.within("0"))
+            .or_else(|| T2::term_diff(&self.1, &other.1).map(|diff| diff.within("1")))
+            .or_else(|| T3::term_diff(&self.2, &other.2).map(|diff| diff.within("2")))
+    }
+
     fn close_term(&mut self, state: ScopeState, binders: &[Binder<N>]) {
         self.0.close_term(state, binders);
         self.1.close_term(state, binders);
@@ -355,6 +602,18 @@ where
         self.1.visit_mut_vars(on_var);
         self.2.visit_mut_vars(on_var);
     }
+
+    fn walk_term(&self, walker: &mut impl Walker<N>) {
+        self.0.walk_term(walker);
+        self.1.walk_term(walker);
+        self.2.walk_term(walker);
+    }
+
+    fn walk_mut_term(&mut self, walker: &mut impl WalkerMut<N>) {
+        self.0.walk_mut_term(walker);
+        self.1.walk_mut_term(walker);
+        self.2.walk_mut_term(walker);
+    }
 }
 
 impl<N, T1, T2, T3, T4> BoundTerm<N> for (T1, T2, T3, T4)
@@ -371,6 +630,17 @@ where
             && T4::term_eq(&self.3, &other.3)
     }
 
+    fn term_diff(&self, other: &(T1, T2, T3, T4)) -> Option<TermDiff>
+    where
+        N: fmt::Display,
+    {
+        T1::term_diff(&self.0, &other.0)
+            .map(|diff| diff.within("0"))
+            .or_else(|| T2::term_diff(&self.1, &other.1).map(|diff| diff.within("1")))
+            .or_else(|| T3::term_diff(&self.2, &other.2).map(|diff| diff.within("2")))
+            .or_else(|| T4::term_diff(&self.3, &other.3).map(|diff| diff.within("3")))
+    }
+
     fn close_term(&mut self, state: ScopeState, binders: &[Binder<N>]) {
         self.0.close_term(state, binders);
         self.1.close_term(state, binders);
@@ -398,6 +668,20 @@ where
         self.2.visit_mut_vars(on_var);
         self.3.visit_mut_vars(on_var);
     }
+
+    fn walk_term(&self, walker: &mut impl Walker<N>) {
+        self.0.walk_term(walker);
+        self.1.walk_term(walker);
+        self.2.walk_term(walker);
+        self.3.walk_term(walker);
+    }
+
+    fn walk_mut_term(&mut self, walker: &mut impl WalkerMut<N>) {
+        self.0.walk_mut_term(walker);
+        self.1.walk_mut_term(walker);
+        self.2.walk_mut_term(walker);
+        self.3.walk_mut_term(walker);
+    }
 }
 
 impl<N, T1, T2, T3, T4, T5> BoundTerm<N> for (T1, T2, T3, T4, T5)
@@ -416,6 +700,18 @@ where
             && T5::term_eq(&self.4, &other.4)
     }
 
+    fn term_diff(&self, other: &(T1, T2, T3, T4, T5)) -> Option<TermDiff>
+    where
+        N: fmt::Display,
+    {
+        T1::term_diff(&self.0, &other.0)
+            .map(|diff| diff.within("0"))
+            .or_else(|| T2::term_diff(&self.1, &other.1).map(|diff| diff.within("1")))
+            .or_else(|| T3::term_diff(&self.2, &other.2).map(|diff| diff.within("2")))
+            .or_else(|| T4::term_diff(&self.3, &other.3).map(|diff| diff.within("3")))
+            .or_else(|| T5::term_diff(&self.4, &other.4).map(|diff| diff.within("4")))
+    }
+
     fn close_term(&mut self, state: ScopeState, binders: &[Binder<N>]) {
         self.0.close_term(state, binders);
         self.1.close_term(state, binders);
@@ -447,6 +743,22 @@ where
         self.3.visit_mut_vars(on_var);
         self.4.visit_mut_vars(on_var);
     }
+
+    fn walk_term(&self, walker: &mut impl Walker<N>) {
+        self.0.walk_term(walker);
+        self.1.walk_term(walker);
+        self.2.walk_term(walker);
+        self.3.walk_term(walker);
+        self.4.walk_term(walker);
+    }
+
+    fn walk_mut_term(&mut self, walker: &mut impl WalkerMut<N>) {
+        self.0.walk_mut_term(walker);
+        self.1.walk_mut_term(walker);
+        self.2.walk_mut_term(walker);
+        self.3.walk_mut_term(walker);
+        self.4.walk_mut_term(walker);
+    }
 }
 
 impl<N, T> BoundTerm<N> for [T]
@@ -458,6 +770,23 @@ where
             && <_>::zip(self.iter(), other.iter()).all(|(lhs, rhs)| T::term_eq(lhs, rhs))
     }
 
+    fn term_diff(&self, other: &[T]) -> Option<TermDiff>
+    where
+        N: fmt::Display,
+    {
+        if self.len() != other.len() {
+            return Some(TermDiff::new(
+                format!("[..; {}]", self.len()),
+                format!("[..; {}]", other.len()),
+            ));
+        }
+
+        <_>::zip(self.iter(), other.iter())
+            .enumerate()
+            .filter_map(|(i, (lhs, rhs))| Some(T::term_diff(lhs, rhs)?.within_index(i)))
+            .next()
+    }
+
     fn close_term(&mut self, state: ScopeState, binders: &[Binder<N>]) {
         for elem in self {
             elem.close_term(state, binders);
@@ -481,6 +810,18 @@ where
             elem.visit_mut_vars(on_var);
         }
     }
+
+    fn walk_term(&self, walker: &mut impl Walker<N>) {
+        for elem in self {
+            elem.walk_term(walker);
+        }
+    }
+
+    fn walk_mut_term(&mut self, walker: &mut impl WalkerMut<N>) {
+        for elem in self.iter_mut() {
+            elem.walk_mut_term(walker);
+        }
+    }
 }
 
 impl<N, T> BoundTerm<N> for Vec<T>
@@ -491,6 +832,13 @@ where
         <[T]>::term_eq(self, other)
     }
 
+    fn term_diff(&self, other: &Vec<T>) -> Option<TermDiff>
+    where
+        N: fmt::Display,
+    {
+        <[T]>::term_diff(self, other)
+    }
+
     fn close_term(&mut self, state: ScopeState, binders: &[Binder<N>]) {
         <[T]>::close_term(self, state, binders)
     }
@@ -506,6 +854,14 @@ where
     fn visit_mut_vars(&mut self, on_var: &mut impl FnMut(&mut Var<N>)) {
         <[T]>::visit_mut_vars(self, on_var);
     }
+
+    fn walk_term(&self, walker: &mut impl Walker<N>) {
+        <[T]>::walk_term(self, walker);
+    }
+
+    fn walk_mut_term(&mut self, walker: &mut impl WalkerMut<N>) {
+        <[T]>::walk_mut_term(self, walker);
+    }
 }
 
 /// Patterns that bind variables in terms
@@ -513,6 +869,22 @@ pub trait BoundPattern<N> {
     /// Alpha equivalence for patterns
     fn pattern_eq(&self, other: &Self) -> bool;
 
+    /// Find the first point where this pattern stops being alpha equivalent to
+    /// `other`, returning `None` if they are alpha equivalent
+    ///
+    /// The default implementation compares the patterns with `pattern_eq`, and
+    /// reports any difference at the root of the pattern.
+    fn pattern_diff(&self, other: &Self) -> Option<TermDiff>
+    where
+        N: fmt::Display,
+    {
+        if self.pattern_eq(other) {
+            None
+        } else {
+            Some(TermDiff::new("..", ".."))
+        }
+    }
+
     /// Close the terms in the pattern using the supplied binders
     fn close_pattern(&mut self, state: ScopeState, binders: &[Binder<N>]);
 
@@ -527,6 +899,16 @@ pub trait BoundPattern<N> {
     /// on each of them in turn
     fn visit_mut_binders(&mut self, on_binder: &mut impl FnMut(&mut Binder<N>));
 
+    /// Walk the binding structure of the selected terms embedded in the
+    /// pattern, calling the `walker` on each frame of binders and each variable
+    /// in turn
+    fn walk_pattern(&self, terms: PatternTerms, walker: &mut impl Walker<N>);
+
+    /// Walk the binding structure of the selected terms embedded in the
+    /// pattern, calling the `walker` on each frame of binders and each variable
+    /// in turn, with mutable access to them
+    fn walk_mut_pattern(&mut self, terms: PatternTerms, walker: &mut impl WalkerMut<N>);
+
     /// Returns the binders in this pattern
     fn binders(&self) -> Vec<Binder<N>>
     where
@@ -538,6 +920,64 @@ pub trait BoundPattern<N> {
         });
         binders
     }
+
+    /// Returns `true` if every bound variable in the terms embedded in the
+    /// pattern refers to a binder that is found within the pattern itself
+    fn is_locally_closed(&self) -> bool {
+        invariants::check_pattern(self).is_ok()
+    }
+
+    /// Try the ways of reordering `other` that might make it alpha equivalent
+    /// to this pattern, stopping once `on_reordering` returns `true`
+    ///
+    /// This is overridden by patterns like `Unordered`, where the order of
+    /// the parts of the pattern is not significant, and is used by `Scope` to
+    /// search for a matching order once comparing the patterns in order has
+    /// failed. `on_reordering` is called with each reordered copy of `other`,
+    /// along with the original position of each of its binders. The bound
+    /// variables that refer to those binders still use their original
+    /// positions, so it is up to whoever owns the binders to renumber them.
+    /// The default implementation has no other orders to try.
+    fn try_reorderings(&self, _: &Self, _: &mut dyn FnMut(&Self, &[usize]) -> bool) -> bool
+    where
+        Self: Sized,
+    {
+        false
+    }
+}
+
+/// Renumber the bound variables that refer to some reordered binders, given
+/// the original position of each of the binders
+///
+/// `renumber` is called with placeholder binders for the original order and
+/// for the new order, and should open the affected terms with the first and
+/// then close them with the second.
+pub(crate) fn renumber_binders<N>(
+    original_positions: &[usize],
+    renumber: impl FnOnce(&[Binder<N>], &[Binder<N>]),
+) {
+    let unique_ids = original_positions
+        .iter()
+        .map(|_| UniqueId::new())
+        .collect::<Vec<_>>();
+    let placeholder = |unique_id| {
+        Binder(FreeVar {
+            unique_id,
+            pretty_name: None,
+        })
+    };
+
+    let original_binders = unique_ids
+        .iter()
+        .cloned()
+        .map(placeholder)
+        .collect::<Vec<_>>();
+    let reordered_binders = original_positions
+        .iter()
+        .map(|&position| placeholder(unique_ids[position]))
+        .collect::<Vec<_>>();
+
+    renumber(&original_binders, &reordered_binders);
 }
 
 impl<N> BoundPattern<N> for Binder<N>
@@ -559,6 +999,10 @@ where
     fn visit_mut_binders(&mut self, on_binder: &mut impl FnMut(&mut Binder<N>)) {
         on_binder(self)
     }
+
+    fn walk_pattern(&self, _: PatternTerms, _: &mut impl Walker<N>) {}
+
+    fn walk_mut_pattern(&mut self, _: PatternTerms, _: &mut impl WalkerMut<N>) {}
 }
 
 // Implementations for common types
@@ -570,6 +1014,14 @@ macro_rules! impl_bound_pattern_partial_eq {
                 self == other
             }
 
+            fn pattern_diff(&self, other: &$T) -> Option<TermDiff> {
+                if self == other {
+                    None
+                } else {
+                    Some(TermDiff::leaves(self, other))
+                }
+            }
+
             fn close_pattern(&mut self, _: ScopeState, _: &[Binder<N>]) {}
 
             fn open_pattern(&mut self, _: ScopeState, _: &[Binder<N>]) {}
@@ -577,6 +1029,10 @@ macro_rules! impl_bound_pattern_partial_eq {
             fn visit_binders(&self, _: &mut impl FnMut(&Binder<N>)) {}
 
             fn visit_mut_binders(&mut self, _: &mut impl FnMut(&mut Binder<N>)) {}
+
+            fn walk_pattern(&self, _: PatternTerms, _: &mut impl Walker<N>) {}
+
+            fn walk_mut_pattern(&mut self, _: PatternTerms, _: &mut impl WalkerMut<N>) {}
         }
     };
 }
@@ -611,6 +1067,18 @@ where
         }
     }
 
+    fn pattern_diff(&self, other: &Option<P>) -> Option<TermDiff>
+    where
+        N: fmt::Display,
+    {
+        match (self.as_ref(), other.as_ref()) {
+            (Some(lhs), Some(rhs)) => P::pattern_diff(lhs, rhs),
+            (None, None) => None,
+            (Some(_), None) => Some(TermDiff::new("Some(..)", "None")),
+            (None, Some(_)) => Some(TermDiff::new("None", "Some(..)")),
+        }
+    }
+
     fn close_pattern(&mut self, state: ScopeState, binders: &[Binder<N>]) {
         if let Some(ref mut inner) = *self {
             inner.close_pattern(state, binders);
@@ -634,6 +1102,18 @@ where
             inner.visit_mut_binders(on_binder);
         }
     }
+
+    fn walk_pattern(&self, terms: PatternTerms, walker: &mut impl Walker<N>) {
+        if let Some(ref inner) = *self {
+            inner.walk_pattern(terms, walker);
+        }
+    }
+
+    fn walk_mut_pattern(&mut self, terms: PatternTerms, walker: &mut impl WalkerMut<N>) {
+        if let Some(ref mut inner) = *self {
+            inner.walk_mut_pattern(terms, walker);
+        }
+    }
 }
 
 impl<N, P1, P2> BoundPattern<N> for (P1, P2)
@@ -645,6 +1125,15 @@ where
         P1::pattern_eq(&self.0, &other.0) && P2::pattern_eq(&self.1, &other.1)
     }
 
+    fn pattern_diff(&self, other: &(P1, P2)) -> Option<TermDiff>
+    where
+        N: fmt::Display,
+    {
+        P1::pattern_diff(&self.0, &other.0)
+            .map(|diff| diff.within("0"))
+            .or_else(|| P2::pattern_diff(&self.1, &other.1).map(|diff| diff.within("1")))
+    }
+
     fn close_pattern(&mut self, state: ScopeState, binders: &[Binder<N>]) {
         self.0.close_pattern(state, binders);
         self.1.close_pattern(state, binders);
@@ -664,6 +1153,16 @@ where
         self.0.visit_mut_binders(on_binder);
         self.1.visit_mut_binders(on_binder);
     }
+
+    fn walk_pattern(&self, terms: PatternTerms, walker: &mut impl Walker<N>) {
+        self.0.walk_pattern(terms, walker);
+        self.1.walk_pattern(terms, walker);
+    }
+
+    fn walk_mut_pattern(&mut self, terms: PatternTerms, walker: &mut impl WalkerMut<N>) {
+        self.0.walk_mut_pattern(terms, walker);
+        self.1.walk_mut_pattern(terms, walker);
+    }
 }
 
 impl<N, P1, P2, P3> BoundPattern<N> for (P1, P2, P3)
@@ -678,6 +1177,16 @@ where
             && P3::pattern_eq(&self.2, &other.2)
     }
 
+    fn pattern_diff(&self, other: &(P1, P2, P3)) -> Option<TermDiff>
+    where
+        N: fmt::Display,
+    {
+        P1::pattern_diff(&self.0, &other.0)
+            .map(|diff| diff.within("0"))
+            .or_else(|| P2::pattern_diff(&self.1, &other.1).map(|diff| diff.within("1")))
+            .or_else(|| P3::pattern_diff(&self.2, &other.2).map(|diff| diff.within("2")))
+    }
+
     fn close_pattern(&mut self, state: ScopeState, binders: &[Binder<N>]) {
         self.0.close_pattern(state, binders);
         self.1.close_pattern(state, binders);
@@ -701,6 +1210,18 @@ where
         self.1.visit_mut_binders(on_binder);
         self.2.visit_mut_binders(on_binder);
     }
+
+    fn walk_pattern(&self, terms: PatternTerms, walker: &mut impl Walker<N>) {
+        self.0.walk_pattern(terms, walker);
+        self.1.walk_pattern(terms, walker);
+        self.2.walk_pattern(terms, walker);
+    }
+
+    fn walk_mut_pattern(&mut self, terms: PatternTerms, walker: &mut impl WalkerMut<N>) {
+        self.0.walk_mut_pattern(terms, walker);
+        self.1.walk_mut_pattern(terms, walker);
+        self.2.walk_mut_pattern(terms, walker);
+    }
 }
 
 impl<N, P1, P2, P3, P4> BoundPattern<N> for (P1, P2, P3, P4)
@@ -717,6 +1238,17 @@ where
             && P4::pattern_eq(&self.3, &other.3)
     }
 
+    fn pattern_diff(&self, other: &(P1, P2, P3, P4)) -> Option<TermDiff>
+    where
+        N: fmt::Display,
+    {
+        P1::pattern_diff(&self.0, &other.0)
+            .map(|diff| diff.within("0"))
+            .or_else(|| P2::pattern_diff(&self.1, &other.1).map(|diff| diff.within("1")))
+            .or_else(|| P3::pattern_diff(&self.2, &other.2).map(|diff| diff.within("2")))
+            .or_else(|| P4::pattern_diff(&self.3, &other.3).map(|diff| diff.within("3")))
+    }
+
     fn close_pattern(&mut self, state: ScopeState, binders: &[Binder<N>]) {
         self.0.close_pattern(state, binders);
         self.1.close_pattern(state, binders);
@@ -744,6 +1276,20 @@ where
         self.2.visit_mut_binders(on_binder);
         self.3.visit_mut_binders(on_binder);
     }
+
+    fn walk_pattern(&self, terms: PatternTerms, walker: &mut impl Walker<N>) {
+        self.0.walk_pattern(terms, walker);
+        self.1.walk_pattern(terms, walker);
+        self.2.walk_pattern(terms, walker);
+        self.3.walk_pattern(terms, walker);
+    }
+
+    fn walk_mut_pattern(&mut self, terms: PatternTerms, walker: &mut impl WalkerMut<N>) {
+        self.0.walk_mut_pattern(terms, walker);
+        self.1.walk_mut_pattern(terms, walker);
+        self.2.walk_mut_pattern(terms, walker);
+        self.3.walk_mut_pattern(terms, walker);
+    }
 }
 
 impl<N, P1, P2, P3, P4, P5> BoundPattern<N> for (P1, P2, P3, P4, P5)
@@ -762,6 +1308,18 @@ where
             && P5::pattern_eq(&self.4, &other.4)
     }
 
+    fn pattern_diff(&self, other: &(P1, P2, P3, P4, P5)) -> Option<TermDiff>
+    where
+        N: fmt::Display,
+    {
+        P1::pattern_diff(&self.0, &other.0)
+            .map(|diff| diff.within("0"))
+            .or_else(|| P2::pattern_diff(&self.1, &other.1).map(|diff| diff.within("1")))
+            .or_else(|| P3::pattern_diff(&self.2, &other.2).map(|diff| diff.within("2")))
+            .or_else(|| P4::pattern_diff(&self.3, &other.3).map(|diff| diff.within("3")))
+            .or_else(|| P5::pattern_diff(&self.4, &other.4).map(|diff| diff.within("4")))
+    }
+
     fn close_pattern(&mut self, state: ScopeState, binders: &[Binder<N>]) {
         self.0.close_pattern(state, binders);
         self.1.close_pattern(state, binders);
@@ -793,6 +1351,22 @@ where
         self.3.visit_mut_binders(on_binder);
         self.4.visit_mut_binders(on_binder);
     }
+
+    fn walk_pattern(&self, terms: PatternTerms, walker: &mut impl Walker<N>) {
+        self.0.walk_pattern(terms, walker);
+        self.1.walk_pattern(terms, walker);
+        self.2.walk_pattern(terms, walker);
+        self.3.walk_pattern(terms, walker);
+        self.4.walk_pattern(terms, walker);
+    }
+
+    fn walk_mut_pattern(&mut self, terms: PatternTerms, walker: &mut impl WalkerMut<N>) {
+        self.0.walk_mut_pattern(terms, walker);
+        self.1.walk_mut_pattern(terms, walker);
+        self.2.walk_mut_pattern(terms, walker);
+        self.3.walk_mut_pattern(terms, walker);
+        self.4.walk_mut_pattern(terms, walker);
+    }
 }
 
 impl<N, P> BoundPattern<N> for Box<P>
@@ -803,6 +1377,13 @@ where
         P::pattern_eq(self, other)
     }
 
+    fn pattern_diff(&self, other: &Box<P>) -> Option<TermDiff>
+    where
+        N: fmt::Display,
+    {
+        P::pattern_diff(self, other)
+    }
+
     fn close_pattern(&mut self, state: ScopeState, binders: &[Binder<N>]) {
         P::close_pattern(self, state, binders);
     }
@@ -818,6 +1399,14 @@ where
     fn visit_mut_binders(&mut self, on_binder: &mut impl FnMut(&mut Binder<N>)) {
         P::visit_mut_binders(self, on_binder);
     }
+
+    fn walk_pattern(&self, terms: PatternTerms, walker: &mut impl Walker<N>) {
+        P::walk_pattern(self, terms, walker);
+    }
+
+    fn walk_mut_pattern(&mut self, terms: PatternTerms, walker: &mut impl WalkerMut<N>) {
+        P::walk_mut_pattern(self, terms, walker);
+    }
 }
 
 impl<N, P> BoundPattern<N> for Rc<P>
@@ -828,6 +1417,13 @@ where
         P::pattern_eq(self, other)
     }
 
+    fn pattern_diff(&self, other: &Rc<P>) -> Option<TermDiff>
+    where
+        N: fmt::Display,
+    {
+        P::pattern_diff(self, other)
+    }
+
     fn close_pattern(&mut self, state: ScopeState, binders: &[Binder<N>]) {
         P::close_pattern(Rc::make_mut(self), state, binders);
     }
@@ -843,6 +1439,14 @@ where
     fn visit_mut_binders(&mut self, on_binder: &mut impl FnMut(&mut Binder<N>)) {
         P::visit_mut_binders(Rc::make_mut(self), on_binder);
     }
+
+    fn walk_pattern(&self, terms: PatternTerms, walker: &mut impl Walker<N>) {
+        P::walk_pattern(self, terms, walker);
+    }
+
+    fn walk_mut_pattern(&mut self, terms: PatternTerms, walker: &mut impl WalkerMut<N>) {
+        P::walk_mut_pattern(Rc::make_mut(self), terms, walker);
+    }
 }
 
 impl<N, P> BoundPattern<N> for Arc<P>
@@ -853,6 +1457,13 @@ where
         P::pattern_eq(self, other)
     }
 
+    fn pattern_diff(&self, other: &Arc<P>) -> Option<TermDiff>
+    where
+        N: fmt::Display,
+    {
+        P::pattern_diff(self, other)
+    }
+
     fn close_pattern(&mut self, state: ScopeState, binders: &[Binder<N>]) {
         P::close_pattern(Arc::make_mut(self), state, binders);
     }
@@ -868,6 +1479,14 @@ where
     fn visit_mut_binders(&mut self, on_binder: &mut impl FnMut(&mut Binder<N>)) {
         P::visit_mut_binders(Arc::make_mut(self), on_binder);
     }
+
+    fn walk_pattern(&self, terms: PatternTerms, walker: &mut impl Walker<N>) {
+        P::walk_pattern(self, terms, walker);
+    }
+
+    fn walk_mut_pattern(&mut self, terms: PatternTerms, walker: &mut impl WalkerMut<N>) {
+        P::walk_mut_pattern(Arc::make_mut(self), terms, walker);
+    }
 }
 
 impl<N, P> BoundPattern<N> for [P]
@@ -880,6 +1499,23 @@ where
             && <_>::zip(self.iter(), other.iter()).all(|(lhs, rhs)| P::pattern_eq(lhs, rhs))
     }
 
+    fn pattern_diff(&self, other: &[P]) -> Option<TermDiff>
+    where
+        N: fmt::Display,
+    {
+        if self.len() != other.len() {
+            return Some(TermDiff::new(
+                format!("[..; {}]", self.len()),
+                format!("[..; {}]", other.len()),
+            ));
+        }
+
+        <_>::zip(self.iter(), other.iter())
+            .enumerate()
+            .filter_map(|(i, (lhs, rhs))| Some(P::pattern_diff(lhs, rhs)?.within_index(i)))
+            .next()
+    }
+
     fn close_pattern(&mut self, state: ScopeState, binders: &[Binder<N>]) {
         for elem in self {
             elem.close_pattern(state, binders);
@@ -903,6 +1539,18 @@ where
             elem.visit_mut_binders(on_binder);
         }
     }
+
+    fn walk_pattern(&self, terms: PatternTerms, walker: &mut impl Walker<N>) {
+        for elem in self {
+            elem.walk_pattern(terms, walker);
+        }
+    }
+
+    fn walk_mut_pattern(&mut self, terms: PatternTerms, walker: &mut impl WalkerMut<N>) {
+        for elem in self.iter_mut() {
+            elem.walk_mut_pattern(terms, walker);
+        }
+    }
 }
 
 impl<N, P> BoundPattern<N> for Vec<P>
@@ -914,6 +1562,13 @@ where
         <[P]>::pattern_eq(self, other)
     }
 
+    fn pattern_diff(&self, other: &Vec<P>) -> Option<TermDiff>
+    where
+        N: fmt::Display,
+    {
+        <[P]>::pattern_diff(self, other)
+    }
+
     fn close_pattern(&mut self, state: ScopeState, binders: &[Binder<N>]) {
         <[P]>::close_pattern(self, state, binders);
     }
@@ -929,4 +1584,12 @@ where
     fn visit_mut_binders(&mut self, on_binder: &mut impl FnMut(&mut Binder<N>)) {
         <[P]>::visit_mut_binders(self, on_binder);
     }
+
+    fn walk_pattern(&self, terms: PatternTerms, walker: &mut impl Walker<N>) {
+        <[P]>::walk_pattern(self, terms, walker);
+    }
+
+    fn walk_mut_pattern(&mut self, terms: PatternTerms, walker: &mut impl WalkerMut<N>) {
+        <[P]>::walk_mut_pattern(self, terms, walker);
+    }
 }
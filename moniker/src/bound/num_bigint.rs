@@ -9,6 +9,14 @@ macro_rules! impl_bound_term_partial_eq {
                 self == other
             }
 
+            fn term_diff(&self, other: &$T) -> Option<TermDiff> {
+                if self == other {
+                    None
+                } else {
+                    Some(TermDiff::leaves(self, other))
+                }
+            }
+
             fn close_term(&mut self, _: ScopeState, _: &[Binder<N>]) {}
 
             fn open_term(&mut self, _: ScopeState, _: &[Binder<N>]) {}
@@ -16,6 +24,10 @@ macro_rules! impl_bound_term_partial_eq {
             fn visit_vars(&self, _: &mut impl FnMut(&Var<N>)) {}
 
             fn visit_mut_vars(&mut self, _: &mut impl FnMut(&mut Var<N>)) {}
+
+            fn walk_term(&self, _: &mut impl Walker<N>) {}
+
+            fn walk_mut_term(&mut self, _: &mut impl WalkerMut<N>) {}
         }
     };
 }
@@ -30,6 +42,14 @@ macro_rules! impl_bound_pattern_partial_eq {
                 self == other
             }
 
+            fn pattern_diff(&self, other: &$T) -> Option<TermDiff> {
+                if self == other {
+                    None
+                } else {
+                    Some(TermDiff::leaves(self, other))
+                }
+            }
+
             fn close_pattern(&mut self, _: ScopeState, _: &[Binder<N>]) {}
 
             fn open_pattern(&mut self, _: ScopeState, _: &[Binder<N>]) {}
@@ -37,6 +57,10 @@ macro_rules! impl_bound_pattern_partial_eq {
             fn visit_binders(&self, _: &mut impl FnMut(&Binder<N>)) {}
 
             fn visit_mut_binders(&mut self, _: &mut impl FnMut(&mut Binder<N>)) {}
+
+            fn walk_pattern(&self, _: PatternTerms, _: &mut impl Walker<N>) {}
+
+            fn walk_mut_pattern(&mut self, _: PatternTerms, _: &mut impl WalkerMut<N>) {}
         }
     };
 }
@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use bound::{BoundPattern, BoundTerm};
+use free_var::FreeVar;
+use unique_id::UniqueId;
+use var::Var;
+use walk::{FrameKind, WalkerMut};
+
+/// Give the free variables and binders in a term canonical ids, in order of
+/// their first occurrence
+///
+/// The ids of the free variables and binders in a term depend on the order in
+/// which fresh variables happened to be generated, which makes it hard to
+/// compare the printed forms of terms, for example in snapshot tests. After
+/// canonicalizing, alpha equivalent terms print identically, as long as their
+/// free variables occur in the same places, and their binders and free
+/// variables have the same pretty names. The pretty names of the variables are
+/// left alone, so for example `\x => x` and `\y => y` still print
+/// differently. Use `canonicalize_with_names` to give the binders canonical
+/// names as well.
+///
+/// Each free variable is renamed consistently, so the result is equivalent to
+/// the original term up to a renaming of its free variables. Note that the
+/// canonical ids are shared between terms, so the free variables of two
+/// canonicalized terms should not be mixed up with each other.
+pub fn canonicalize<N, T>(mut term: T) -> T
+where
+    N: Clone + Eq + Hash,
+    T: BoundTerm<N>,
+{
+    term.walk_mut_term(&mut Canonicalizer {
+        free_vars: HashMap::new(),
+        next_index: 0,
+    });
+    term
+}
+
+/// Give the free variables and binders in a term canonical ids, like
+/// `canonicalize`, and also rename the binders using the given function
+///
+/// The function is called with the index of the canonical id of each binder,
+/// for example to give them names like `x0` and `x1`, and the bound variables
+/// that refer to the binders are renamed to match. The free variables keep
+/// their pretty names, so alpha equivalent terms print identically as long as
+/// their free variables occur in the same places and have the same pretty
+/// names.
+pub fn canonicalize_with_names<N, T>(term: T, mut name: impl FnMut(u32) -> N) -> T
+where
+    N: Clone + Eq + Hash,
+    T: BoundTerm<N>,
+{
+    let mut term = canonicalize(term);
+    term.walk_mut_term(&mut BinderNamer {
+        frames: Vec::new(),
+        shifted: Vec::new(),
+        name: &mut name,
+    });
+    term
+}
+
+/// Gives canonical ids to the free variables and binders of a term
+struct Canonicalizer<N> {
+    free_vars: HashMap<FreeVar<N>, UniqueId>,
+    next_index: u32,
+}
+
+impl<N> Canonicalizer<N> {
+    fn next_id(&mut self) -> UniqueId {
+        let unique_id = UniqueId::canonical(self.next_index);
+        self.next_index += 1;
+        unique_id
+    }
+}
+
+impl<N> WalkerMut<N> for Canonicalizer<N>
+where
+    N: Clone + Eq + Hash,
+{
+    fn enter_frame(&mut self, kind: FrameKind, pattern: &mut impl BoundPattern<N>) {
+        // The binders of `Nest` and `Rec` frames are also found in the frame of
+        // the enclosing scope, so we only need to renumber them once. The
+        // variables that refer to them are bound, so they don't need updating.
+        if kind == FrameKind::Scope {
+            pattern.visit_mut_binders(&mut |binder| {
+                binder.0.unique_id = self.next_id();
+            });
+        }
+    }
+
+    fn on_var(&mut self, var: &mut Var<N>) {
+        if let Var::Free(ref mut free_var) = *var {
+            let unique_id = match self.free_vars.get(free_var) {
+                Some(&unique_id) => unique_id,
+                None => {
+                    let unique_id = self.next_id();
+                    self.free_vars.insert(free_var.clone(), unique_id);
+                    unique_id
+                },
+            };
+            free_var.unique_id = unique_id;
+        }
+    }
+}
+
+/// Renames the binders of a canonicalized term, along with the bound variables
+/// that refer to them
+struct BinderNamer<'a, F: 'a> {
+    frames: Vec<Vec<UniqueId>>,
+    shifted: Vec<Option<Vec<UniqueId>>>,
+    name: &'a mut F,
+}
+
+impl<'a, F> BinderNamer<'a, F> {
+    fn name_of<N>(&mut self, unique_id: UniqueId) -> Option<N>
+    where
+        F: FnMut(u32) -> N,
+    {
+        unique_id.canonical_index().map(|index| (self.name)(index))
+    }
+}
+
+impl<'a, N, F> WalkerMut<N> for BinderNamer<'a, F>
+where
+    N: Clone,
+    F: FnMut(u32) -> N,
+{
+    fn enter_frame(&mut self, kind: FrameKind, pattern: &mut impl BoundPattern<N>) {
+        // The binders of `Nest` and `Rec` frames are also found in the frame of
+        // the enclosing scope, so we only need to rename them once
+        if kind == FrameKind::Scope {
+            pattern.visit_mut_binders(&mut |binder| {
+                if let Some(name) = self.name_of(binder.0.unique_id) {
+                    binder.0.pretty_name = Some(name);
+                }
+            });
+        }
+
+        let unique_ids = pattern
+            .binders()
+            .into_iter()
+            .map(|binder| binder.0.unique_id)
+            .collect();
+        self.frames.push(unique_ids);
+    }
+
+    fn exit_frame(&mut self, _: FrameKind) {
+        self.frames.pop().expect("unbalanced frames");
+    }
+
+    fn enter_shift(&mut self) {
+        let frame = self.frames.pop();
+        self.shifted.push(frame);
+    }
+
+    fn exit_shift(&mut self) {
+        if let Some(frame) = self.shifted.pop().expect("unbalanced shifts") {
+            self.frames.push(frame);
+        }
+    }
+
+    fn on_var(&mut self, var: &mut Var<N>) {
+        if let Var::Bound(ref mut bound_var) = *var {
+            let scope = bound_var.scope.0 as usize;
+            let unique_id = self
+                .frames
+                .len()
+                .checked_sub(scope + 1)
+                .and_then(|frame| self.frames[frame].get(bound_var.binder.to_usize()))
+                .cloned();
+
+            if let Some(name) = unique_id.and_then(|unique_id| self.name_of(unique_id)) {
+                bound_var.pretty_name = Some(name);
+            }
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use std::fmt;
+
+/// The first point at which two terms or patterns stop being alpha equivalent
+///
+/// This is returned by `BoundTerm::term_diff` and `BoundPattern::pattern_diff`,
+/// and is used by `assert_term_eq!` and `assert_pattern_eq!` to report
+/// failures without printing the whole of both values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TermDiff {
+    /// The path from the roots of the values to the differing subterms, made
+    /// up of field names (qualified by their variant names for enums), and
+    /// the indices of elements of sequences
+    pub path: Vec<String>,
+    /// A compact rendering of the differing subterm on the left
+    ///
+    /// Variables are rendered with their `Display` impls, and other leaves
+    /// with their `Debug` impls. Terms that differ in their constructors are
+    /// rendered as just the constructor, for example `App(..)`.
+    pub lhs: String,
+    /// A compact rendering of the differing subterm on the right
+    pub rhs: String,
+}
+
+impl TermDiff {
+    /// Create a difference between the roots of two values
+    pub fn new(lhs: impl Into<String>, rhs: impl Into<String>) -> TermDiff {
+        TermDiff {
+            path: Vec::new(),
+            lhs: lhs.into(),
+            rhs: rhs.into(),
+        }
+    }
+
+    /// Create a difference between two leaves, using their debug
+    /// representations
+    pub fn leaves<T: fmt::Debug + ?Sized>(lhs: &T, rhs: &T) -> TermDiff {
+        TermDiff::new(format!("{:?}", lhs), format!("{:?}", rhs))
+    }
+
+    /// Move the difference inside the child of a value with the given name
+    pub fn within(mut self, name: impl Into<String>) -> TermDiff {
+        self.path.insert(0, name.into());
+        self
+    }
+
+    /// Move the difference inside the element of a sequence at the given index
+    pub fn within_index(self, index: usize) -> TermDiff {
+        self.within(format!("[{}]", index))
+    }
+
+    /// The path to the differing subterms, for example `inner.App.0.body`
+    pub fn path_string(&self) -> String {
+        let mut path = String::new();
+        for name in &self.path {
+            if !path.is_empty() && !name.starts_with('[') {
+                path.push('.');
+            }
+            path.push_str(name);
+        }
+        if path.is_empty() {
+            path.push_str("<root>");
+        }
+        path
+    }
+}
+
+impl fmt::Display for TermDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "`{}` != `{}` at `{}`",
+            self.lhs,
+            self.rhs,
+            self.path_string()
+        )
+    }
+}
@@ -0,0 +1,186 @@
+use binder::Binder;
+use free_var::FreeVar;
+use node::Node;
+use var::Var;
+
+/// The version of the encoding used by `term_fingerprint`
+///
+/// This is incremented whenever the encoding changes, and is included in the
+/// encoding itself, so fingerprints from different versions never match.
+pub const FINGERPRINT_VERSION: u32 = 1;
+
+/// A 128-bit fingerprint of a term that is stable across processes and
+/// machines
+///
+/// Alpha equivalent terms have the same fingerprint, as long as their free
+/// variables have the same keys. Bound variables are identified by their
+/// indices, and the names of binders are ignored. Free variables are identified
+/// by the keys returned by `free_var_key`, for example the paths of global
+/// definitions. Unlike the hashes in `std`, this doesn't depend on the
+/// `UniqueId`s of the variables, the order of fields in memory, or the word
+/// size of the machine.
+///
+/// # Encoding
+///
+/// The fingerprint is the 128-bit [FNV-1a] hash of the following bytes. All
+/// integers are little endian, lengths are `u64`s, and strings are encoded as
+/// their length followed by their UTF-8 bytes.
+///
+/// First comes `FINGERPRINT_VERSION`, as a `u32`. Then each of the nodes of
+/// the term's structural view (see `Node`) is encoded in pre-order:
+///
+/// - other nodes: the byte `0`, followed by the node name, the number of
+///   children, and then the encodings of each of the children, as returned
+///   by `Node::eq_children`
+/// - bound variables: the byte `1`, followed by the scope offset and the
+///   binder index of the variable, as `u32`s
+/// - free variables: the byte `2`, followed by the length of the key and the
+///   bytes of the key
+/// - binders: the byte `3`
+/// - strings, characters, booleans, numbers, and `()`: the byte `4`, followed
+///   by the name of the type, for example `i32`, and then the value. Characters
+///   are encoded as `u32`s, booleans as a single byte, `usize` and `isize` as
+///   `u64` and `i64`, and floats as the bits of their IEEE 754 representation.
+///
+/// Leaves with other types are encoded by their node names alone. This means
+/// that data that is ignored by alpha equivalence, like the contents of an
+/// `Ignore` or the fields marked with `#[moniker(eq = "skip")]`, does not
+/// affect the fingerprint, but it also means that leaves with hand-written
+/// `Node` impls should expose any data that matters as children.
+///
+/// [FNV-1a]: http://www.isthe.com/chongo/tech/comp/fnv/index.html
+pub fn term_fingerprint<N, K>(
+    term: &dyn Node,
+    mut free_var_key: impl FnMut(&FreeVar<N>) -> K,
+) -> u128
+where
+    N: 'static,
+    K: AsRef<[u8]>,
+{
+    let mut hasher = Fnv1a128::new();
+    hasher.write(&FINGERPRINT_VERSION.to_le_bytes());
+    encode_node(&mut hasher, term, &mut free_var_key);
+    hasher.finish()
+}
+
+fn encode_node<N, K>(
+    hasher: &mut Fnv1a128,
+    node: &dyn Node,
+    free_var_key: &mut impl FnMut(&FreeVar<N>) -> K,
+) where
+    N: 'static,
+    K: AsRef<[u8]>,
+{
+    if let Some(var) = node.downcast_ref::<Var<N>>() {
+        match *var {
+            Var::Bound(ref bound_var) => {
+                hasher.write(&[1]);
+                hasher.write(&bound_var.scope.0.to_le_bytes());
+                hasher.write(&bound_var.binder.0.to_le_bytes());
+            },
+            Var::Free(ref free_var) => encode_free_var(hasher, free_var, free_var_key),
+        }
+    } else if let Some(free_var) = node.downcast_ref::<FreeVar<N>>() {
+        encode_free_var(hasher, free_var, free_var_key);
+    } else if node.downcast_ref::<Binder<N>>().is_some() {
+        hasher.write(&[3]);
+    } else if !encode_primitive(hasher, node) {
+        let children = node.eq_children();
+        hasher.write(&[0]);
+        hasher.write_str(node.node_name());
+        hasher.write(&(children.len() as u64).to_le_bytes());
+        for child in children {
+            encode_node(hasher, child, free_var_key);
+        }
+    }
+}
+
+fn encode_free_var<N, K>(
+    hasher: &mut Fnv1a128,
+    free_var: &FreeVar<N>,
+    free_var_key: &mut impl FnMut(&FreeVar<N>) -> K,
+) where
+    K: AsRef<[u8]>,
+{
+    let key = free_var_key(free_var);
+    hasher.write(&[2]);
+    hasher.write(&(key.as_ref().len() as u64).to_le_bytes());
+    hasher.write(key.as_ref());
+}
+
+/// Encode the node and return `true` from the enclosing function if it has
+/// one of the given primitive types
+macro_rules! encode_primitive {
+    ($hasher:expr, $node:expr, $($T:ident => |$value:ident| $bytes:expr),+ $(,)*) => {
+        $(if let Some($value) = $node.downcast_ref::<$T>() {
+            $hasher.write(&[4]);
+            $hasher.write_str(stringify!($T));
+            $hasher.write(&$bytes);
+            return true;
+        })+
+    };
+}
+
+fn encode_primitive(hasher: &mut Fnv1a128, node: &dyn Node) -> bool {
+    if let Some(value) = node.downcast_ref::<String>() {
+        hasher.write(&[4]);
+        hasher.write_str("String");
+        hasher.write_str(value);
+        return true;
+    }
+
+    encode_primitive!(
+        hasher,
+        node,
+        char => |value| (*value as u32).to_le_bytes(),
+        bool => |value| [*value as u8],
+        u8 => |value| value.to_le_bytes(),
+        u16 => |value| value.to_le_bytes(),
+        u32 => |value| value.to_le_bytes(),
+        u64 => |value| value.to_le_bytes(),
+        usize => |value| (*value as u64).to_le_bytes(),
+        i8 => |value| value.to_le_bytes(),
+        i16 => |value| value.to_le_bytes(),
+        i32 => |value| value.to_le_bytes(),
+        i64 => |value| value.to_le_bytes(),
+        isize => |value| (*value as i64).to_le_bytes(),
+        f32 => |value| value.to_bits().to_le_bytes(),
+        f64 => |value| value.to_bits().to_le_bytes(),
+    );
+
+    if node.downcast_ref::<()>().is_some() {
+        hasher.write(&[4]);
+        hasher.write_str("()");
+        return true;
+    }
+
+    false
+}
+
+/// The 128-bit variant of the FNV-1a hash function
+struct Fnv1a128(u128);
+
+impl Fnv1a128 {
+    const OFFSET_BASIS: u128 = 0x6c62_272e_07bb_0142_62b8_2175_6295_c58d;
+    const PRIME: u128 = 0x0000_0000_0100_0000_0000_0000_0000_013b;
+
+    fn new() -> Fnv1a128 {
+        Fnv1a128(Fnv1a128::OFFSET_BASIS)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u128::from(byte);
+            self.0 = self.0.wrapping_mul(Fnv1a128::PRIME);
+        }
+    }
+
+    fn write_str(&mut self, value: &str) {
+        self.write(&(value.len() as u64).to_le_bytes());
+        self.write(value.as_bytes());
+    }
+
+    fn finish(&self) -> u128 {
+        self.0
+    }
+}
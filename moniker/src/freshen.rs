@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use binder::Binder;
+use bound::{BoundPattern, BoundTerm};
+use bound_var::{BinderIndex, BoundVar, ScopeOffset};
+use free_var::FreeVar;
+use var::Var;
+use walk::{FrameKind, WalkerMut};
+
+/// Give every binder in the term a brand new free variable, and replace the
+/// bound variables that refer to them with free variables
+///
+/// This results in a fully named term, where every binder is globally unique.
+/// This is useful for passes that want to use a flat symbol table, like code
+/// generators. The scopes in the term are left open, so the term should be
+/// closed again with `close_binders` before it is used with the rest of
+/// `moniker`. The returned map contains the original pretty name of each of
+/// the new binders.
+pub fn freshen_binders<N, T>(mut term: T) -> (T, HashMap<Binder<N>, Option<N>>)
+where
+    N: Clone + Eq + Hash,
+    T: BoundTerm<N>,
+{
+    // Freshen all of the binders before opening any variables, because the
+    // terms embedded in a pattern can be walked before the binders of its
+    // scope are entered
+    let mut freshener = Freshener {
+        pretty_names: HashMap::new(),
+    };
+    term.walk_mut_term(&mut freshener);
+    term.walk_mut_term(&mut Opener {
+        frames: Vec::new(),
+        shifted: Vec::new(),
+    });
+
+    (term, freshener.pretty_names)
+}
+
+/// Replace the free variables in a fully named term that refer to the binders
+/// within the term with bound variables
+///
+/// This is the inverse of `freshen_binders`.
+pub fn close_binders<N, T>(mut term: T) -> T
+where
+    N: Clone + PartialEq,
+    T: BoundTerm<N>,
+{
+    term.walk_mut_term(&mut Closer {
+        frames: Vec::new(),
+        shifted: Vec::new(),
+    });
+
+    term
+}
+
+/// Replaces the binders of each scope with fresh free variables
+struct Freshener<N> {
+    pretty_names: HashMap<Binder<N>, Option<N>>,
+}
+
+impl<N> WalkerMut<N> for Freshener<N>
+where
+    N: Clone + Eq + Hash,
+{
+    fn enter_frame(&mut self, kind: FrameKind, pattern: &mut impl BoundPattern<N>) {
+        // The binders of `Nest` and `Rec` frames are also found in the frame of
+        // the enclosing scope, so we only need to freshen them once
+        if kind == FrameKind::Scope {
+            let pretty_names = &mut self.pretty_names;
+            pattern.visit_mut_binders(&mut |binder| {
+                let pretty_name = binder.0.pretty_name.clone();
+                *binder = Binder(FreeVar::fresh(pretty_name.clone()));
+                pretty_names.insert(binder.clone(), pretty_name);
+            });
+        }
+    }
+}
+
+/// Replaces bound variables with the free variables of the binders they
+/// refer to
+struct Opener<N> {
+    frames: Vec<Vec<Binder<N>>>,
+    shifted: Vec<Option<Vec<Binder<N>>>>,
+}
+
+impl<N: Clone> WalkerMut<N> for Opener<N> {
+    fn enter_frame(&mut self, _: FrameKind, pattern: &mut impl BoundPattern<N>) {
+        self.frames.push(pattern.binders());
+    }
+
+    fn exit_frame(&mut self, _: FrameKind) {
+        self.frames.pop();
+    }
+
+    fn enter_shift(&mut self) {
+        let frame = self.frames.pop();
+        self.shifted.push(frame);
+    }
+
+    fn exit_shift(&mut self) {
+        if let Some(frame) = self.shifted.pop().expect("unbalanced shifts") {
+            self.frames.push(frame);
+        }
+    }
+
+    fn on_var(&mut self, var: &mut Var<N>) {
+        // NOTE: Working around NLL
+        *var = match *var {
+            Var::Bound(ref bound_var) => {
+                let scope = bound_var.scope.0 as usize;
+                let binder = self
+                    .frames
+                    .len()
+                    .checked_sub(scope + 1)
+                    .and_then(|frame| self.frames[frame].get(bound_var.binder.to_usize()));
+
+                match binder {
+                    Some(Binder(free_var)) => Var::Free(free_var.clone()),
+                    None => return,
+                }
+            },
+            Var::Free(_) => return,
+        };
+    }
+}
+
+/// Replaces free variables with bound variables that refer to the innermost
+/// binder with the same free variable
+struct Closer<N> {
+    frames: Vec<Vec<Binder<N>>>,
+    shifted: Vec<Option<Vec<Binder<N>>>>,
+}
+
+impl<N: Clone + PartialEq> WalkerMut<N> for Closer<N> {
+    fn enter_frame(&mut self, _: FrameKind, pattern: &mut impl BoundPattern<N>) {
+        self.frames.push(pattern.binders());
+    }
+
+    fn exit_frame(&mut self, _: FrameKind) {
+        self.frames.pop();
+    }
+
+    fn enter_shift(&mut self) {
+        let frame = self.frames.pop();
+        self.shifted.push(frame);
+    }
+
+    fn exit_shift(&mut self) {
+        if let Some(frame) = self.shifted.pop().expect("unbalanced shifts") {
+            self.frames.push(frame);
+        }
+    }
+
+    fn on_var(&mut self, var: &mut Var<N>) {
+        // NOTE: Working around NLL
+        *var = match *var {
+            Var::Bound(_) => return,
+            Var::Free(ref free_var) => {
+                let bound_var = self
+                    .frames
+                    .iter()
+                    .rev()
+                    .enumerate()
+                    .filter_map(|(scope, binders)| {
+                        let binder = binders.iter().position(|binder| binder == free_var)?;
+                        Some(BoundVar {
+                            scope: ScopeOffset(scope as u32),
+                            binder: BinderIndex(binder as u32),
+                            pretty_name: free_var.pretty_name.clone(),
+                        })
+                    })
+                    .next();
+
+                match bound_var {
+                    Some(bound_var) => Var::Bound(bound_var),
+                    None => return,
+                }
+            },
+        };
+    }
+}
@@ -0,0 +1,53 @@
+use std::collections::HashSet;
+use std::hash::Hash;
+
+use binder::Binder;
+use bound::BoundTerm;
+use free_var::FreeVar;
+use scope::Scope;
+use var::Var;
+
+/// Bind the free variables of a term that are not free in the context
+///
+/// This is the generalization step of Hindley-Milner style type inference,
+/// where `context_free_vars` would be the free type variables of the typing
+/// context. The binders are ordered by the first occurrence of their free
+/// variables in the term, so that printed type schemes are stable.
+pub fn generalize<N, T>(
+    context_free_vars: &HashSet<FreeVar<N>>,
+    term: T,
+) -> Scope<Vec<Binder<N>>, T>
+where
+    N: Clone + Eq + Hash,
+    T: Clone + BoundTerm<N>,
+{
+    let mut seen = HashSet::new();
+    let mut binders = Vec::new();
+    term.visit_vars(&mut |var| {
+        if let Var::Free(ref free_var) = *var {
+            if !context_free_vars.contains(free_var) && seen.insert(free_var.clone()) {
+                binders.push(Binder(free_var.clone()));
+            }
+        }
+    });
+
+    Scope::new(binders, term)
+}
+
+/// Instantiate the binders of a generalized term with fresh free variables
+///
+/// The fresh free variables are returned in the same order as the binders, so
+/// that they can be used for things like creating unification variables.
+pub fn instantiate_fresh<N, T>(scope: Scope<Vec<Binder<N>>, T>) -> (Vec<FreeVar<N>>, T)
+where
+    N: Clone + Eq + Hash,
+    T: BoundTerm<N>,
+{
+    let (binders, term) = scope.unbind();
+    let free_vars = binders
+        .into_iter()
+        .map(|Binder(free_var)| free_var)
+        .collect();
+
+    (free_vars, term)
+}
@@ -1,6 +1,13 @@
+use std::any::Any;
+
 use binder::Binder;
 use bound::{BoundPattern, BoundTerm, ScopeState};
+use meta_var::MetaVar;
+use node::Node;
+use strict::{PatternEqStrict, TermEqStrict};
+use unify::{Unifier, Unify, UnifyError};
 use var::Var;
+use walk::{PatternTerms, Walker, WalkerMut};
 
 /// Data that does not participate in name binding
 ///
@@ -21,6 +28,10 @@ impl<N, T> BoundTerm<N> for Ignore<T> {
     fn visit_vars(&self, _: &mut impl FnMut(&Var<N>)) {}
 
     fn visit_mut_vars(&mut self, _: &mut impl FnMut(&mut Var<N>)) {}
+
+    fn walk_term(&self, _: &mut impl Walker<N>) {}
+
+    fn walk_mut_term(&mut self, _: &mut impl WalkerMut<N>) {}
 }
 
 impl<N, T> BoundPattern<N> for Ignore<T> {
@@ -35,4 +46,54 @@ impl<N, T> BoundPattern<N> for Ignore<T> {
     fn visit_binders(&self, _: &mut impl FnMut(&Binder<N>)) {}
 
     fn visit_mut_binders(&mut self, _: &mut impl FnMut(&mut Binder<N>)) {}
+
+    fn walk_pattern(&self, _: PatternTerms, _: &mut impl Walker<N>) {}
+
+    fn walk_mut_pattern(&mut self, _: PatternTerms, _: &mut impl WalkerMut<N>) {}
+}
+
+impl<N, T, U> Unify<N, T> for Ignore<U> {
+    fn unify(&self, _: &Ignore<U>, _: &mut Unifier<N, T>) -> Result<(), UnifyError<N>> {
+        Ok(())
+    }
+
+    fn zonk(&mut self, _: &Unifier<N, T>) {}
+
+    fn visit_meta_vars(&self, _: &mut impl FnMut(&MetaVar<N>)) {}
+
+    fn visit_mut_subterms(&mut self, _: &mut impl FnMut(&mut T)) {}
+}
+
+impl<T: 'static> Node for Ignore<T> {
+    fn node_name(&self) -> &'static str {
+        "Ignore"
+    }
+
+    fn children(&self) -> Vec<&dyn Node> {
+        Vec::new()
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut dyn Node> {
+        Vec::new()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl<T: PartialEq> TermEqStrict for Ignore<T> {
+    fn term_eq_strict(&self, other: &Ignore<T>) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<T: PartialEq> PatternEqStrict for Ignore<T> {
+    fn pattern_eq_strict(&self, other: &Ignore<T>) -> bool {
+        self.0 == other.0
+    }
 }
@@ -0,0 +1,174 @@
+//! Checks for the invariants of the locally nameless representation
+//!
+//! These are used to implement `BoundTerm::is_locally_closed` and
+//! `BoundPattern::is_locally_closed`. When the `check-invariants` feature is
+//! enabled they are also run by `Scope`, `Nest`, and `Rec` whenever they are
+//! bound or unbound, panicking with a report of the first violation that was
+//! found.
+
+use std::fmt;
+
+use binder::DuplicateBinderError;
+use bound::{BoundPattern, BoundTerm};
+use bound_var::{BinderIndex, ScopeOffset};
+use unique_id::UniqueId;
+use var::Var;
+use walk::{FrameKind, PatternTerms, Walker};
+
+/// A violation of the invariants of the locally nameless representation
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation {
+    /// A bound variable refers to a scope beyond the enclosing scopes
+    DanglingScope {
+        scope: ScopeOffset,
+        binder: BinderIndex,
+        depth: usize,
+    },
+    /// A bound variable refers to a binder beyond the binders of its scope
+    DanglingBinder {
+        scope: ScopeOffset,
+        binder: BinderIndex,
+        binders: usize,
+    },
+    /// The same free variable is bound more than once by a pattern
+    DuplicateBinder { unique_id: UniqueId },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Violation::DanglingScope {
+                scope,
+                binder,
+                depth,
+            } => write!(
+                f,
+                "the bound variable `@{}.{}` refers to a scope outside of the {} enclosing scope(s)",
+                scope, binder, depth,
+            ),
+            Violation::DanglingBinder {
+                scope,
+                binder,
+                binders,
+            } => write!(
+                f,
+                "the bound variable `@{}.{}` refers to a binder outside of the {} binder(s) in its scope",
+                scope, binder, binders,
+            ),
+            Violation::DuplicateBinder { unique_id } => write!(
+                f,
+                "the free variable `${}` is bound more than once in the same pattern",
+                unique_id,
+            ),
+        }
+    }
+}
+
+/// Tracks the number of binders in each frame, reporting the first variable
+/// that does not refer to one of them
+struct Checker {
+    frames: Vec<usize>,
+    shifted: Vec<Option<usize>>,
+    violation: Option<Violation>,
+}
+
+impl Checker {
+    fn new() -> Checker {
+        Checker {
+            frames: Vec::new(),
+            shifted: Vec::new(),
+            violation: None,
+        }
+    }
+}
+
+impl<N> Walker<N> for Checker {
+    fn enter_frame(&mut self, _: FrameKind, pattern: &impl BoundPattern<N>) {
+        let mut binders = 0;
+        pattern.visit_binders(&mut |_| binders += 1);
+        self.frames.push(binders);
+    }
+
+    fn exit_frame(&mut self, _: FrameKind) {
+        self.frames.pop();
+    }
+
+    fn enter_shift(&mut self) {
+        let frame = self.frames.pop();
+        self.shifted.push(frame);
+    }
+
+    fn exit_shift(&mut self) {
+        if let Some(frame) = self.shifted.pop().expect("unbalanced shifts") {
+            self.frames.push(frame);
+        }
+    }
+
+    fn on_var(&mut self, var: &Var<N>) {
+        if self.violation.is_some() {
+            return;
+        }
+
+        if let Var::Bound(ref bound_var) = *var {
+            let (scope, binder) = (bound_var.scope, bound_var.binder);
+            let depth = self.frames.len();
+
+            self.violation = match depth.checked_sub(scope.0 as usize + 1) {
+                None => Some(Violation::DanglingScope {
+                    scope,
+                    binder,
+                    depth,
+                }),
+                Some(frame) if binder.to_usize() >= self.frames[frame] => {
+                    Some(Violation::DanglingBinder {
+                        scope,
+                        binder,
+                        binders: self.frames[frame],
+                    })
+                },
+                Some(_) => None,
+            };
+        }
+    }
+}
+
+/// Find the first bound variable in the term that does not refer to a binder
+/// from within the term
+pub fn check_term<N, T>(term: &T) -> Result<(), Violation>
+where
+    T: BoundTerm<N> + ?Sized,
+{
+    let mut checker = Checker::new();
+    term.walk_term(&mut checker);
+    checker.violation.map_or(Ok(()), Err)
+}
+
+/// Find the first bound variable in the terms embedded in the pattern that
+/// does not refer to a binder from within the pattern
+pub fn check_pattern<N, P>(pattern: &P) -> Result<(), Violation>
+where
+    P: BoundPattern<N> + ?Sized,
+{
+    let mut checker = Checker::new();
+    pattern.walk_pattern(PatternTerms::All, &mut checker);
+    checker.violation.map_or(Ok(()), Err)
+}
+
+/// Find the first free variable that is bound more than once by the pattern
+pub fn check_linear<N, P>(pattern: &P) -> Result<(), Violation>
+where
+    N: Clone,
+    P: BoundPattern<N> + ?Sized,
+{
+    DuplicateBinderError::check(pattern).map_err(|error| Violation::DuplicateBinder {
+        unique_id: error.binder.0.unique_id,
+    })
+}
+
+/// Panic with a report of the violation if the check failed
+#[cfg(feature = "check-invariants")]
+pub(crate) fn assert_ok(operation: &str, result: Result<(), Violation>) {
+    if let Err(violation) = result {
+        panic!("invariant violated in `{}`: {}", operation, violation,);
+    }
+}
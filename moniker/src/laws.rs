@@ -0,0 +1,479 @@
+//! Laws that implementations of `BoundTerm` and `BoundPattern` should obey
+//!
+//! The derived implementations obey these laws, but hand-written
+//! implementations are easy to get subtly wrong, for example by forgetting to
+//! call `ScopeState::incr` when closing or opening the body of a binding
+//! construct. Each check runs a law on a sample value, and reports which law
+//! was violated and where. The samples can come from unit tests, or from the
+//! generators in `moniker::arbitrary` when the `proptest` feature is enabled.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::Hash;
+
+use binder::Binder;
+use bound::{BoundPattern, BoundTerm, ScopeState};
+use diff::TermDiff;
+use free_var::FreeVar;
+use invariants;
+use scope::Scope;
+use var::Var;
+use walk::{FrameKind, Walker, WalkerMut};
+use witness::FreeVarRenamer;
+
+/// A violation of one of the laws
+#[derive(Debug, Clone, PartialEq)]
+pub struct LawViolation {
+    /// The name of the law that was violated
+    pub law: &'static str,
+    /// A description of how the law was violated
+    pub message: String,
+    /// The first difference between the values that were compared, if the
+    /// law was violated by values that should have been equal
+    pub diff: Option<TermDiff>,
+}
+
+impl LawViolation {
+    fn new(law: &'static str, message: impl Into<String>) -> LawViolation {
+        LawViolation {
+            law,
+            message: message.into(),
+            diff: None,
+        }
+    }
+
+    fn with_diff(law: &'static str, message: impl Into<String>, diff: TermDiff) -> LawViolation {
+        LawViolation {
+            law,
+            message: message.into(),
+            diff: Some(diff),
+        }
+    }
+}
+
+impl fmt::Display for LawViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "law `{}` violated: {}", self.law, self.message)?;
+        if let Some(ref diff) = self.diff {
+            write!(f, ": {}", diff)?;
+        }
+        Ok(())
+    }
+}
+
+/// Check that closing a term over its free variables and then opening it
+/// with the same binders results in the original term
+///
+/// The term is closed as if it were the body of a scope that binds its free
+/// variables, so every occurrence of these variables must be bound to that
+/// scope. This is checked against `BoundTerm::walk_term`, which catches
+/// implementations that forget to call `ScopeState::incr` when closing the
+/// bodies of nested binding constructs.
+pub fn check_open_close_roundtrip<N, T>(term: &T) -> Result<(), LawViolation>
+where
+    N: Clone + PartialEq + fmt::Display,
+    T: Clone + BoundTerm<N>,
+{
+    const LAW: &str = "open_close_roundtrip";
+
+    if let Err(violation) = invariants::check_term(term) {
+        return Err(LawViolation::new(
+            LAW,
+            format!("the sample term is not locally closed: {}", violation),
+        ));
+    }
+
+    let mut occurrences = VarOccurrences(Vec::new());
+    term.walk_term(&mut occurrences);
+    let mut binders = Vec::<Binder<N>>::new();
+    let mut expected = 0;
+    for var in occurrences.0 {
+        if let Var::Free(free_var) = var {
+            expected += 1;
+            if !binders.iter().any(|binder| binder.0 == free_var) {
+                binders.push(Binder(free_var));
+            }
+        }
+    }
+
+    let mut closed = term.clone();
+    closed.close_term(ScopeState::new(), &binders);
+
+    let mut counter = OuterVarCounter::new(&binders);
+    closed.walk_term(&mut counter);
+    if let Some(free_var) = counter.unclosed {
+        return Err(LawViolation::new(
+            LAW,
+            format!(
+                "closing the term left the free variable `{}` unbound",
+                free_var
+            ),
+        ));
+    }
+    if counter.bound != expected {
+        return Err(LawViolation::new(
+            LAW,
+            format!(
+                "closing the term bound {} of the {} occurrence(s) of its free variables to the \
+                 enclosing scope (is `ScopeState::incr` called for nested binding constructs?)",
+                counter.bound, expected,
+            ),
+        ));
+    }
+
+    let mut opened = closed;
+    opened.open_term(ScopeState::new(), &binders);
+
+    if let Err(violation) = invariants::check_term(&opened) {
+        return Err(LawViolation::new(
+            LAW,
+            format!(
+                "opening the closed term left a dangling bound variable: {}",
+                violation
+            ),
+        ));
+    }
+    match term.term_diff(&opened) {
+        None => Ok(()),
+        Some(diff) => Err(LawViolation::with_diff(
+            LAW,
+            "opening the closed term did not restore the original term",
+            diff,
+        )),
+    }
+}
+
+/// Check that a term is alpha equivalent to itself, and that
+/// `BoundTerm::term_diff` agrees with `BoundTerm::term_eq`
+pub fn check_term_eq_reflexive<N, T>(term: &T) -> Result<(), LawViolation>
+where
+    N: fmt::Display,
+    T: BoundTerm<N>,
+{
+    const LAW: &str = "term_eq_reflexive";
+
+    match (term.term_eq(term), term.term_diff(term)) {
+        (true, None) => Ok(()),
+        (false, None) => Err(LawViolation::new(
+            LAW,
+            "the term is not alpha equivalent to itself",
+        )),
+        (false, Some(diff)) => Err(LawViolation::with_diff(
+            LAW,
+            "the term is not alpha equivalent to itself",
+            diff,
+        )),
+        (true, Some(diff)) => Err(LawViolation::with_diff(
+            LAW,
+            "`term_diff` found a difference between the term and itself, but `term_eq` did not",
+            diff,
+        )),
+    }
+}
+
+/// Check that a pattern is alpha equivalent to itself, and that
+/// `BoundPattern::pattern_diff` agrees with `BoundPattern::pattern_eq`
+pub fn check_pattern_eq_reflexive<N, P>(pattern: &P) -> Result<(), LawViolation>
+where
+    N: fmt::Display,
+    P: BoundPattern<N>,
+{
+    const LAW: &str = "pattern_eq_reflexive";
+
+    match (pattern.pattern_eq(pattern), pattern.pattern_diff(pattern)) {
+        (true, None) => Ok(()),
+        (false, None) => Err(LawViolation::new(
+            LAW,
+            "the pattern is not alpha equivalent to itself",
+        )),
+        (false, Some(diff)) => Err(LawViolation::with_diff(
+            LAW,
+            "the pattern is not alpha equivalent to itself",
+            diff,
+        )),
+        (true, Some(diff)) => Err(LawViolation::with_diff(
+            LAW,
+            "`pattern_diff` found a difference between the pattern and itself, but \
+             `pattern_eq` did not",
+            diff,
+        )),
+    }
+}
+
+/// Check that the variables of a term are visited consistently
+///
+/// `visit_vars` and `visit_mut_vars` must visit the same variables in the same
+/// order, as must `walk_term` and `walk_mut_term`. The walks also visit the
+/// terms embedded in patterns, so the variables that are visited by
+/// `visit_vars` must appear in the same order in the walk. Finally,
+/// `free_vars` must return the free variables that are visited by
+/// `visit_vars`.
+pub fn check_free_vars_consistent_with_visit<N, T>(term: &T) -> Result<(), LawViolation>
+where
+    N: Clone + Eq + Hash + fmt::Display,
+    T: Clone + BoundTerm<N>,
+{
+    const LAW: &str = "free_vars_consistent_with_visit";
+
+    let mut visited = Vec::new();
+    term.visit_vars(&mut |var| visited.push(var.clone()));
+    let mut visited_mut = Vec::new();
+    term.clone()
+        .visit_mut_vars(&mut |var| visited_mut.push(var.clone()));
+    let mut walked = VarOccurrences(Vec::new());
+    term.walk_term(&mut walked);
+    let mut walked_mut = VarOccurrences(Vec::new());
+    term.clone().walk_mut_term(&mut walked_mut);
+
+    compare_visits(
+        LAW,
+        ("visit_vars", &visited),
+        ("visit_mut_vars", &visited_mut),
+    )?;
+    compare_visits(
+        LAW,
+        ("walk_term", &walked.0),
+        ("walk_mut_term", &walked_mut.0),
+    )?;
+
+    let mut walked_vars = walked.0.iter();
+    for var in &visited {
+        if !walked_vars.any(|walked_var| walked_var == var) {
+            return Err(LawViolation::new(
+                LAW,
+                format!(
+                    "`visit_vars` visits `{}`, but `walk_term` does not visit it in the same order",
+                    var,
+                ),
+            ));
+        }
+    }
+
+    let free_vars = term.free_vars();
+    let mut visited_free_vars = HashSet::new();
+    for var in &visited {
+        if let Var::Free(ref free_var) = *var {
+            if !free_vars.contains(free_var) {
+                return Err(LawViolation::new(
+                    LAW,
+                    format!(
+                        "`visit_vars` visits the free variable `{}`, but it is missing from \
+                         `free_vars`",
+                        free_var,
+                    ),
+                ));
+            }
+            visited_free_vars.insert(free_var.clone());
+        }
+    }
+    if let Some(free_var) = free_vars.difference(&visited_free_vars).next() {
+        return Err(LawViolation::new(
+            LAW,
+            format!(
+                "`free_vars` contains `{}`, but it is not visited by `visit_vars`",
+                free_var,
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Report the first difference between the variables that were visited by
+/// two visitors that should agree
+fn compare_visits<N>(
+    law: &'static str,
+    (lhs_name, lhs): (&str, &[Var<N>]),
+    (rhs_name, rhs): (&str, &[Var<N>]),
+) -> Result<(), LawViolation>
+where
+    N: PartialEq + fmt::Display,
+{
+    for (index, (lhs_var, rhs_var)) in <_>::zip(lhs.iter(), rhs).enumerate() {
+        if lhs_var != rhs_var {
+            return Err(LawViolation::new(
+                law,
+                format!(
+                    "`{}` visits `{}` at position {}, but `{}` visits `{}`",
+                    lhs_name, lhs_var, index, rhs_name, rhs_var,
+                ),
+            ));
+        }
+    }
+
+    if lhs.len() != rhs.len() {
+        return Err(LawViolation::new(
+            law,
+            format!(
+                "`{}` visits {} variable(s), but `{}` visits {}",
+                lhs_name,
+                lhs.len(),
+                rhs_name,
+                rhs.len(),
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Check that unbinding a scope is invariant under the choice of fresh names
+///
+/// The scope is unbound twice, and the results must be the same up to a
+/// renaming of the binders of the pattern. The unbound body must not contain
+/// any dangling bound variables, and binding the unbound pattern and body
+/// again must result in the original scope.
+pub fn check_alpha_invariance_of_unbind<N, P, T>(scope: &Scope<P, T>) -> Result<(), LawViolation>
+where
+    N: Clone + Eq + Hash + fmt::Display,
+    P: Clone + BoundPattern<N>,
+    T: Clone + BoundTerm<N>,
+{
+    const LAW: &str = "alpha_invariance_of_unbind";
+
+    if let Err(violation) = invariants::check_term(scope) {
+        return Err(LawViolation::new(
+            LAW,
+            format!("the sample scope is not locally closed: {}", violation),
+        ));
+    }
+
+    let (pattern1, body1) = scope.clone().unbind();
+    let (pattern2, body2) = scope.clone().unbind();
+
+    if let Err(violation) = invariants::check_term(&body1) {
+        return Err(LawViolation::new(
+            LAW,
+            format!(
+                "unbinding the scope left a dangling bound variable in the body: {}",
+                violation
+            ),
+        ));
+    }
+    if let Some(diff) = pattern1.pattern_diff(&pattern2) {
+        return Err(LawViolation::with_diff(
+            LAW,
+            "unbinding the scope twice resulted in patterns that are not alpha equivalent",
+            diff.within("pattern"),
+        ));
+    }
+
+    let binders1 = pattern1.binders();
+    let binders2 = pattern2.binders();
+    if binders1.len() != binders2.len() {
+        return Err(LawViolation::new(
+            LAW,
+            format!(
+                "unbinding the scope twice resulted in patterns with {} and {} binder(s)",
+                binders1.len(),
+                binders2.len(),
+            ),
+        ));
+    }
+
+    let renaming = <_>::zip(binders1.into_iter(), binders2)
+        .map(|(Binder(free_var1), Binder(free_var2))| (free_var1, free_var2))
+        .collect::<HashMap<FreeVar<N>, FreeVar<N>>>();
+    let mut renamed_body1 = body1.clone();
+    renamed_body1.walk_mut_term(&mut FreeVarRenamer(&renaming));
+
+    if let Some(diff) = renamed_body1.term_diff(&body2) {
+        return Err(LawViolation::with_diff(
+            LAW,
+            "unbinding the scope twice resulted in bodies that differ by more than the names of \
+             the binders",
+            diff.within("body"),
+        ));
+    }
+
+    match scope.term_diff(&Scope::new(pattern1, body1)) {
+        None => Ok(()),
+        Some(diff) => Err(LawViolation::with_diff(
+            LAW,
+            "binding the unbound pattern and body did not restore the original scope",
+            diff,
+        )),
+    }
+}
+
+/// Collects each occurrence of a variable that is found when walking a term,
+/// in order
+struct VarOccurrences<N>(Vec<Var<N>>);
+
+impl<N: Clone> Walker<N> for VarOccurrences<N> {
+    fn on_var(&mut self, var: &Var<N>) {
+        self.0.push(var.clone());
+    }
+}
+
+impl<N: Clone> WalkerMut<N> for VarOccurrences<N> {
+    fn on_var(&mut self, var: &mut Var<N>) {
+        self.0.push(var.clone());
+    }
+}
+
+/// Counts the variables that are bound to the given binders in the scope
+/// enclosing a term, and finds the first occurrence of the binders that was
+/// left free
+struct OuterVarCounter<'a, N: 'a> {
+    binders: &'a [Binder<N>],
+    depth: usize,
+    shifted: Vec<bool>,
+    bound: usize,
+    unclosed: Option<Var<N>>,
+}
+
+impl<'a, N> OuterVarCounter<'a, N> {
+    fn new(binders: &'a [Binder<N>]) -> OuterVarCounter<'a, N> {
+        OuterVarCounter {
+            binders,
+            depth: 0,
+            shifted: Vec::new(),
+            bound: 0,
+            unclosed: None,
+        }
+    }
+}
+
+impl<'a, N: Clone + PartialEq> Walker<N> for OuterVarCounter<'a, N> {
+    fn enter_frame(&mut self, _: FrameKind, _: &impl BoundPattern<N>) {
+        self.depth += 1;
+    }
+
+    fn exit_frame(&mut self, _: FrameKind) {
+        self.depth -= 1;
+    }
+
+    fn enter_shift(&mut self) {
+        let has_frame = self.depth > 0;
+        if has_frame {
+            self.depth -= 1;
+        }
+        self.shifted.push(has_frame);
+    }
+
+    fn exit_shift(&mut self) {
+        if self.shifted.pop().expect("unbalanced shifts") {
+            self.depth += 1;
+        }
+    }
+
+    fn on_var(&mut self, var: &Var<N>) {
+        match *var {
+            Var::Free(ref free_var) => {
+                if self.unclosed.is_none()
+                    && self.binders.iter().any(|binder| binder.0 == *free_var)
+                {
+                    self.unclosed = Some(var.clone());
+                }
+            },
+            Var::Bound(ref bound_var) => {
+                if bound_var.scope.0 as usize == self.depth
+                    && bound_var.binder.to_usize() < self.binders.len()
+                {
+                    self.bound += 1;
+                }
+            },
+        }
+    }
+}
@@ -42,6 +42,7 @@
 //! Terms are data types that implement the [`BoundTerm`] trait.
 //!
 //! - [`Var<N>`]: A variable that is either free or bound
+//! - [`MetaVar<N>`]: A meta variable that can be solved by [`Unify`]
 //! - [`Scope<P: BoundPattern<N>, T: BoundTerm<N>>`]: bind the term `T` using the pattern `P`
 //! - [`Ignore<T>`]: Ignores `T` when comparing for alpha equality
 //!
@@ -50,6 +51,8 @@
 //!
 //! [`BoundTerm`]: trait.BoundTerm.html
 //! [`Var<N>`]: enum.Var.html
+//! [`MetaVar<N>`]: struct.MetaVar.html
+//! [`Unify`]: trait.Unify.html
 //! [`Scope<P: BoundPattern<N>, T: BoundTerm<N>>`]: struct.Scope.html
 //!
 //! ## Patterns
@@ -59,8 +62,15 @@
 //! - [`Binder<N>`]: Captures a free variables within a term, but is ignored for alpha equality
 //! - [`Ignore<T>`]: Ignores `T` when comparing for alpha equality
 //! - [`Embed<T: BoundTerm<N>>`]: Embed a term `T` in a pattern
+//! - [`Inner<T: BoundTerm<N>>`]: Embed a term `T` in a pattern, in the scope of the pattern's binders
+//! - [`Outer<T: BoundTerm<N>>`]: Embed a term `T` in a pattern, outside the scope of the pattern's binders
+//! - [`Neutral<T: BoundTerm<N>>`]: Embed a term `T` in a pattern, unaffected by any binders
+//! - [`Multi<P: BoundPattern<N>>`]: Multiple binding patterns, bound simultaneously
+//! - [`Alt<P: BoundPattern<N>>`]: Alternative binding patterns that share the same binders
 //! - [`Nest<P: BoundPattern<N>>`]: Multiple nested binding patterns
 //! - [`Rec<P: BoundPattern<N>>`]: Recursively bind a pattern in itself
+//! - [`Shift<P: BoundPattern<N>>`]: Skip the innermost frame of binders in the terms embedded in a pattern
+//! - [`Unordered<P: BoundPattern<N>>`]: Multiple binding patterns, where the order of the patterns is not significant
 //!
 //! Implementations for tuples, strings, numbers, slices, vectors, and mart pointers
 //! are also provided for convenience.
@@ -69,8 +79,15 @@
 //! [`Binder<N>`]: enum.Binder.html
 //! [`Ignore<T>`]: struct.Ignore.html
 //! [`Embed<T: BoundTerm<N>>`]: struct.Embed.html
+//! [`Inner<T: BoundTerm<N>>`]: struct.Inner.html
+//! [`Outer<T: BoundTerm<N>>`]: struct.Outer.html
+//! [`Neutral<T: BoundTerm<N>>`]: struct.Neutral.html
+//! [`Multi<P: BoundPattern<N>>`]: struct.Multi.html
+//! [`Alt<P: BoundPattern<N>>`]: struct.Alt.html
 //! [`Nest<P: BoundPattern<N>>`]: struct.Nest.html
 //! [`Rec<P: BoundPattern<N>>`]: struct.Rec.html
+//! [`Shift<P: BoundPattern<N>>`]: struct.Shift.html
+//! [`Unordered<P: BoundPattern<N>>`]: struct.Unordered.html
 
 #[macro_use]
 extern crate lazy_static;
@@ -86,6 +103,8 @@ extern crate codespan;
 extern crate im;
 #[cfg(feature = "num-bigint")]
 extern crate num_bigint;
+#[cfg(feature = "proptest")]
+extern crate proptest;
 
 #[cfg(feature = "moniker-derive")]
 #[doc(hidden)]
@@ -95,26 +114,75 @@ pub use moniker_derive::*;
 #[doc(hidden)]
 pub mod macros;
 
+mod alt;
+#[cfg(feature = "proptest")]
+pub mod arbitrary;
 mod binder;
 mod bound;
 mod bound_var;
+mod canonicalize;
+mod diff;
 mod embed;
+mod fingerprint;
 mod free_var;
+mod freshen;
+mod generalize;
 mod ignore;
+mod inner;
+pub mod invariants;
+pub mod laws;
+pub mod lint;
+mod locally_closed;
+mod meta_var;
+mod multi;
 mod nest;
+mod neutral;
+mod node;
+mod outer;
 mod rec;
+mod rewrite;
 mod scope;
+mod shift;
+mod strict;
+mod unify;
 mod unique_id;
+mod unordered;
 mod var;
+mod walk;
+mod witness;
 
-pub use self::binder::Binder;
+pub use self::alt::{Alt, AltBindersError};
+#[cfg(feature = "proptest")]
+pub use self::arbitrary::ArbitraryTerm;
+pub use self::binder::{Binder, DuplicateBinderError};
 pub use self::bound::{BoundPattern, BoundTerm, ScopeState};
 pub use self::bound_var::{BinderIndex, BoundVar, ScopeOffset};
+pub use self::canonicalize::{canonicalize, canonicalize_with_names};
+pub use self::diff::TermDiff;
 pub use self::embed::Embed;
+pub use self::fingerprint::{term_fingerprint, FINGERPRINT_VERSION};
 pub use self::free_var::FreeVar;
+pub use self::freshen::{close_binders, freshen_binders};
+pub use self::generalize::{generalize, instantiate_fresh};
 pub use self::ignore::Ignore;
+pub use self::inner::Inner;
+pub use self::locally_closed::LocallyClosed;
+pub use self::meta_var::MetaVar;
+pub use self::multi::Multi;
 pub use self::nest::Nest;
-pub use self::rec::Rec;
-pub use self::scope::Scope;
+pub use self::neutral::Neutral;
+pub use self::node::{
+    depth, find_matches, find_subterms, nodes, size, subterms, visit_nodes_mut, Node, Nodes,
+};
+pub use self::outer::Outer;
+pub use self::rec::{Rec, RecBindings};
+pub use self::rewrite::{instantiate, match_term, rewrite, subst, Bindings, Rule, Strategy};
+pub use self::scope::{LiftedScope, OuterFreeVarError, Scope};
+pub use self::shift::Shift;
+pub use self::strict::{PatternEqStrict, TermEqStrict};
+pub use self::unify::{MetaTerm, Unifier, Unify, UnifyError};
 pub use self::unique_id::UniqueId;
+pub use self::unordered::Unordered;
 pub use self::var::Var;
+pub use self::walk::{FrameKind, PatternTerms, Walker, WalkerMut};
+pub use self::witness::{pattern_eq_witness, term_eq_renaming, term_eq_witness};
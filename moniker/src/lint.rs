@@ -0,0 +1,175 @@
+//! Lints for unused and shadowed binders
+//!
+//! Lints are reported with the binders that were stored in the patterns when
+//! the scopes were constructed, so the `unique_id`s of these binders can be
+//! used to look up any extra information (like source spans) that were
+//! associated with them during parsing.
+
+use std::collections::HashSet;
+
+use binder::Binder;
+use bound::{BoundPattern, BoundTerm};
+use unique_id::UniqueId;
+use var::Var;
+use walk::{FrameKind, Walker};
+
+/// A lint that was found when walking a term
+#[derive(Debug, Clone, PartialEq)]
+pub enum Lint<N> {
+    /// A binder that is never referred to from within its scope
+    UnusedBinder(Binder<N>),
+    /// A binder with the same pretty name as a binder that it hides, either
+    /// from an enclosing pattern or from earlier in the same pattern
+    ShadowedBinder {
+        binder: Binder<N>,
+        shadowed: Binder<N>,
+    },
+}
+
+/// Walk the term, calling `on_lint` for every unused or shadowed binder that
+/// is found
+///
+/// Binders in `Nest` and `Rec` patterns are considered to be used if they are
+/// referred to from later patterns in the `Nest`, or from within the `Rec`
+/// respectively.
+pub fn lint_term<N, T>(term: &T, on_lint: &mut impl FnMut(Lint<N>))
+where
+    N: Clone + PartialEq,
+    T: BoundTerm<N>,
+{
+    term.walk_term(&mut Linter {
+        frames: Vec::new(),
+        shifted: Vec::new(),
+        pending_uses: HashSet::new(),
+        on_lint,
+    });
+}
+
+/// A frame of binders, along with the binders that have been used so far
+struct Frame<N> {
+    kind: FrameKind,
+    binders: Vec<Binder<N>>,
+    used: Vec<bool>,
+}
+
+struct Linter<'a, N: 'a, F: 'a> {
+    frames: Vec<Frame<N>>,
+    shifted: Vec<Option<Frame<N>>>,
+    /// Binders that were used from within `Nest` and `Rec` frames, along with
+    /// the depth of the frame of the scope that they belong to
+    ///
+    /// These frames are exited before the frame of the scope is entered when
+    /// walking the terms outside of the scope of the pattern, and after it is
+    /// entered when walking the terms inside of its scope.
+    pending_uses: HashSet<(usize, UniqueId)>,
+    on_lint: &'a mut F,
+}
+
+impl<'a, N, F> Linter<'a, N, F>
+where
+    N: PartialEq,
+{
+    /// Find the closest binder to the binder at `index` in the current frame
+    /// that has the same pretty name
+    fn find_shadowed<'b>(
+        &'b self,
+        binders: &'b [Binder<N>],
+        index: usize,
+    ) -> Option<&'b Binder<N>> {
+        let pretty_name = binders[index].0.pretty_name.as_ref()?;
+        let outer_binders = self
+            .frames
+            .iter()
+            .rev()
+            .filter(|frame| frame.kind == FrameKind::Scope)
+            .flat_map(|frame| frame.binders.iter().rev());
+
+        binders[..index]
+            .iter()
+            .rev()
+            .chain(outer_binders)
+            .find(|binder| binder.0.pretty_name.as_ref() == Some(pretty_name))
+    }
+}
+
+impl<'a, N, F> Walker<N> for Linter<'a, N, F>
+where
+    N: Clone + PartialEq,
+    F: FnMut(Lint<N>),
+{
+    fn enter_frame(&mut self, kind: FrameKind, pattern: &impl BoundPattern<N>) {
+        let binders = pattern.binders();
+
+        // Binders in `Nest` and `Rec` frames are also found in the frame of the
+        // enclosing scope, so we only need to check for shadowing once
+        if kind == FrameKind::Scope {
+            for index in 0..binders.len() {
+                if let Some(shadowed) = self.find_shadowed(&binders, index).cloned() {
+                    (self.on_lint)(Lint::ShadowedBinder {
+                        binder: binders[index].clone(),
+                        shadowed,
+                    });
+                }
+            }
+        }
+
+        let depth = self.frames.len();
+        let used = binders
+            .iter()
+            .map(|binder| {
+                kind == FrameKind::Scope && self.pending_uses.remove(&(depth, binder.0.unique_id))
+            })
+            .collect();
+
+        self.frames.push(Frame {
+            kind,
+            binders,
+            used,
+        });
+    }
+
+    fn exit_frame(&mut self, kind: FrameKind) {
+        let frame = self.frames.pop().expect("unbalanced frames");
+        let depth = self.frames.len();
+        let used_binders = <_>::zip(frame.binders.into_iter(), frame.used);
+
+        if kind == FrameKind::Scope {
+            for (binder, used) in used_binders {
+                if !used && !self.pending_uses.remove(&(depth + 1, binder.0.unique_id)) {
+                    (self.on_lint)(Lint::UnusedBinder(binder));
+                }
+            }
+            self.pending_uses
+                .retain(|&(pending_depth, _)| pending_depth <= depth);
+        } else {
+            for (binder, used) in used_binders {
+                if used {
+                    self.pending_uses.insert((depth, binder.0.unique_id));
+                }
+            }
+        }
+    }
+
+    fn enter_shift(&mut self) {
+        let frame = self.frames.pop();
+        self.shifted.push(frame);
+    }
+
+    fn exit_shift(&mut self) {
+        if let Some(frame) = self.shifted.pop().expect("unbalanced shifts") {
+            self.frames.push(frame);
+        }
+    }
+
+    fn on_var(&mut self, var: &Var<N>) {
+        if let Var::Bound(ref bound_var) = *var {
+            let scope = bound_var.scope.0 as usize;
+            if scope < self.frames.len() {
+                let index = self.frames.len() - scope - 1;
+                if let Some(used) = self.frames[index].used.get_mut(bound_var.binder.to_usize()) {
+                    *used = true;
+                }
+            }
+        }
+    }
+}
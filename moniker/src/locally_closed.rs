@@ -0,0 +1,124 @@
+use std::hash::Hash;
+use std::ops::Deref;
+
+use bound::{BoundPattern, BoundTerm};
+use free_var::FreeVar;
+use invariants::{self, Violation};
+use scope::Scope;
+use var::Var;
+
+/// A term that is known to be locally closed
+///
+/// Locally closed terms contain no bound variables that refer to binders from
+/// outside of the term, so any variables that are not underneath a binder are
+/// guaranteed to be free. This makes it possible to write type checkers and
+/// evaluators that can't encounter dangling bound variables.
+///
+/// Locally closed terms can be constructed by:
+///
+/// - checking an existing term with `LocallyClosed::new`
+/// - wrapping a free variable with `LocallyClosed::from_free_var`
+/// - unbinding a locally closed scope with `LocallyClosed::unbind`
+/// - projecting a part of a locally closed term with `LocallyClosed::unsafe_project`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LocallyClosed<T> {
+    inner: T,
+}
+
+impl<T> LocallyClosed<T> {
+    /// Check that a term is locally closed
+    pub fn new<N>(term: T) -> Result<LocallyClosed<T>, Violation>
+    where
+        T: BoundTerm<N>,
+    {
+        invariants::check_term(&term)?;
+        Ok(LocallyClosed { inner: term })
+    }
+
+    /// Wrap a part of this term without checking it again
+    ///
+    /// The parts of a locally closed term that are outside of the bodies (and
+    /// patterns) of its scopes are also locally closed, so this is safe as long
+    /// as `project` doesn't reach into the `unsafe_` fields of a `Scope` or a
+    /// `Rec`. Use `LocallyClosed::unbind` to get at the body of a scope
+    /// instead. The result is only checked when the `check-invariants` feature
+    /// is enabled.
+    pub fn unsafe_project<'a, N, U>(
+        &'a self,
+        project: impl FnOnce(&'a T) -> &'a U,
+    ) -> LocallyClosed<U>
+    where
+        T: 'a,
+        U: BoundTerm<N> + Clone + 'a,
+    {
+        let inner = project(&self.inner).clone();
+
+        #[cfg(feature = "check-invariants")]
+        invariants::assert_ok(
+            "LocallyClosed::unsafe_project",
+            invariants::check_term(&inner),
+        );
+
+        LocallyClosed { inner }
+    }
+
+    /// Return the underlying term
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<N> LocallyClosed<Var<N>> {
+    /// Wrap a free variable
+    pub fn from_free_var(free_var: FreeVar<N>) -> LocallyClosed<Var<N>> {
+        LocallyClosed {
+            inner: Var::Free(free_var),
+        }
+    }
+}
+
+impl<P, T> LocallyClosed<Scope<P, T>> {
+    /// Unbind the scope, returning the freshened pattern and a locally closed
+    /// body
+    pub fn unbind<N>(self) -> (P, LocallyClosed<T>)
+    where
+        N: Clone + Eq + Hash,
+        P: BoundPattern<N>,
+        T: BoundTerm<N>,
+    {
+        let (pattern, body) = self.inner.unbind();
+        (pattern, LocallyClosed { inner: body })
+    }
+
+    /// Simultaneously unbind two locally closed scopes
+    ///
+    /// The fresh names in the first pattern will be used for the second pattern
+    pub fn unbind2<N, P2, T2>(
+        self,
+        other: LocallyClosed<Scope<P2, T2>>,
+    ) -> (P, LocallyClosed<T>, P2, LocallyClosed<T2>)
+    where
+        N: Clone + Eq + Hash,
+        P: BoundPattern<N>,
+        T: BoundTerm<N>,
+        P2: BoundPattern<N>,
+        T2: BoundTerm<N>,
+    {
+        let (self_pattern, self_body, other_pattern, other_body) = self.inner.unbind2(other.inner);
+
+        (
+            self_pattern,
+            LocallyClosed { inner: self_body },
+            other_pattern,
+            LocallyClosed { inner: other_body },
+        )
+    }
+}
+
+impl<T> Deref for LocallyClosed<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
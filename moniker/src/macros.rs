@@ -3,8 +3,9 @@
 /// Assert that two expressions are alpha equivalent to each other (using
 /// `BoundTerm::term_eq`).
 ///
-/// On panic, this macro will print the values of the expressions with their
-/// debug representations.
+/// On panic, this macro will print the path to the first point where the
+/// expressions stop being alpha equivalent, along with compact renderings of
+/// the subterms that differ there (using `BoundTerm::term_diff`).
 ///
 /// Like `assert!`, this macro has a second form, where a custom
 /// panic message can be provided.
@@ -14,9 +15,12 @@ macro_rules! assert_term_eq {
         match (&$left, &$right) {
             (left_val, right_val) => {
                 if !::moniker::BoundTerm::term_eq(left_val, right_val) {
+                    let diff = ::moniker::BoundTerm::term_diff(left_val, right_val)
+                        .unwrap_or_else(|| ::moniker::TermDiff::new("..", ".."));
                     panic!(r#"assertion failed: `<_>::term_eq(&left, &right)`
-  left: `{:?}`,
- right: `{:?}`"#, left_val, right_val)
+  left: `{}`,
+ right: `{}`,
+    at: `{}`"#, diff.lhs, diff.rhs, diff.path_string())
                 }
             }
         }
@@ -28,9 +32,12 @@ macro_rules! assert_term_eq {
         match (&($left), &($right)) {
             (left_val, right_val) => {
                 if !::moniker::BoundTerm::term_eq(left_val, right_val) {
+                    let diff = ::moniker::BoundTerm::term_diff(left_val, right_val)
+                        .unwrap_or_else(|| ::moniker::TermDiff::new("..", ".."));
                     panic!(r#"assertion failed: `<_>::term_eq(&left, &right)`
-  left: `{:?}`,
- right: `{:?}`: {}"#, left_val, right_val,
+  left: `{}`,
+ right: `{}`,
+    at: `{}`: {}"#, diff.lhs, diff.rhs, diff.path_string(),
                            format_args!($($arg)+))
                 }
             }
@@ -41,8 +48,9 @@ macro_rules! assert_term_eq {
 /// Assert that two expressions are alpha equivalent to each other (using
 /// `BoundPattern::pattern_eq`).
 ///
-/// On panic, this macro will print the values of the expressions with their
-/// debug representations.
+/// On panic, this macro will print the path to the first point where the
+/// expressions stop being alpha equivalent, along with compact renderings of
+/// the subterms that differ there (using `BoundPattern::pattern_diff`).
 ///
 /// Like `assert!`, this macro has a second form, where a custom
 /// panic message can be provided.
@@ -52,9 +60,12 @@ macro_rules! assert_pattern_eq {
         match (&$left, &$right) {
             (left_val, right_val) => {
                 if !::moniker::BoundPattern::pattern_eq(left_val, right_val) {
+                    let diff = ::moniker::BoundPattern::pattern_diff(left_val, right_val)
+                        .unwrap_or_else(|| ::moniker::TermDiff::new("..", ".."));
                     panic!(r#"assertion failed: `<_>::pattern_eq(&left, &right)`
-  left: `{:?}`,
- right: `{:?}`"#, left_val, right_val)
+  left: `{}`,
+ right: `{}`,
+    at: `{}`"#, diff.lhs, diff.rhs, diff.path_string())
                 }
             }
         }
@@ -66,9 +77,12 @@ macro_rules! assert_pattern_eq {
         match (&($left), &($right)) {
             (left_val, right_val) => {
                 if !::moniker::BoundPattern::pattern_eq(left_val, right_val) {
+                    let diff = ::moniker::BoundPattern::pattern_diff(left_val, right_val)
+                        .unwrap_or_else(|| ::moniker::TermDiff::new("..", ".."));
                     panic!(r#"assertion failed: `<_>::pattern_eq(&left, &right)`
-  left: `{:?}`,
- right: `{:?}`: {}"#, left_val, right_val,
+  left: `{}`,
+ right: `{}`,
+    at: `{}`: {}"#, diff.lhs, diff.rhs, diff.path_string(),
                            format_args!($($arg)+))
                 }
             }
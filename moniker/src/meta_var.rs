@@ -0,0 +1,151 @@
+use std::any::Any;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use binder::Binder;
+use bound::{BoundTerm, ScopeState};
+use diff::TermDiff;
+use node::Node;
+use strict::TermEqStrict;
+use unify::{Unifier, Unify, UnifyError};
+use unique_id::UniqueId;
+use var::Var;
+use walk::{Walker, WalkerMut};
+
+/// A meta variable, standing for an unknown term that can be solved by
+/// unification
+///
+/// Unlike free variables, meta variables are never bound by patterns, so they
+/// are unaffected by closing and opening terms.
+#[derive(Debug, Clone)]
+pub struct MetaVar<N> {
+    /// A generated id
+    pub unique_id: UniqueId,
+    /// programmer-provided name for pretty-printing
+    pub pretty_name: Option<N>,
+}
+
+impl<N> MetaVar<N> {
+    /// Create a fresh meta variable, with an optional name hint for pretty
+    /// printing
+    pub fn fresh(pretty_name: Option<N>) -> MetaVar<N> {
+        MetaVar {
+            unique_id: UniqueId::new(),
+            pretty_name,
+        }
+    }
+
+    /// Create a fresh meta variable, with no name hint
+    pub fn fresh_unnamed() -> MetaVar<N> {
+        MetaVar::fresh(None)
+    }
+
+    /// Create a fresh meta variable, with a name hint for pretty printing
+    pub fn fresh_named(pretty_name: impl Into<N>) -> MetaVar<N> {
+        MetaVar::fresh(Some(pretty_name.into()))
+    }
+}
+
+impl<N> PartialEq for MetaVar<N>
+where
+    N: PartialEq,
+{
+    fn eq(&self, other: &MetaVar<N>) -> bool {
+        self.unique_id == other.unique_id
+    }
+}
+
+impl<N> Eq for MetaVar<N> where N: Eq {}
+
+impl<N> Hash for MetaVar<N>
+where
+    N: Hash,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.unique_id.hash(state);
+    }
+}
+
+impl<N: fmt::Display> fmt::Display for MetaVar<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.pretty_name {
+            None => write!(f, "?${}", self.unique_id),
+            Some(ref pretty_name) => write!(f, "?{}${}", pretty_name, self.unique_id),
+        }
+    }
+}
+
+impl<N: PartialEq> BoundTerm<N> for MetaVar<N> {
+    fn term_eq(&self, other: &MetaVar<N>) -> bool {
+        self == other
+    }
+
+    fn term_diff(&self, other: &MetaVar<N>) -> Option<TermDiff>
+    where
+        N: fmt::Display,
+    {
+        if self == other {
+            None
+        } else {
+            Some(TermDiff::new(self.to_string(), other.to_string()))
+        }
+    }
+
+    fn close_term(&mut self, _: ScopeState, _: &[Binder<N>]) {}
+
+    fn open_term(&mut self, _: ScopeState, _: &[Binder<N>]) {}
+
+    fn visit_vars(&self, _: &mut impl FnMut(&Var<N>)) {}
+
+    fn visit_mut_vars(&mut self, _: &mut impl FnMut(&mut Var<N>)) {}
+
+    fn walk_term(&self, _: &mut impl Walker<N>) {}
+
+    fn walk_mut_term(&mut self, _: &mut impl WalkerMut<N>) {}
+}
+
+impl<N: PartialEq, T> Unify<N, T> for MetaVar<N> {
+    fn unify(&self, other: &MetaVar<N>, _: &mut Unifier<N, T>) -> Result<(), UnifyError<N>> {
+        if self == other {
+            Ok(())
+        } else {
+            Err(UnifyError::Mismatch)
+        }
+    }
+
+    fn zonk(&mut self, _: &Unifier<N, T>) {}
+
+    fn visit_meta_vars(&self, on_meta_var: &mut impl FnMut(&MetaVar<N>)) {
+        on_meta_var(self);
+    }
+
+    fn visit_mut_subterms(&mut self, _: &mut impl FnMut(&mut T)) {}
+}
+
+impl<N: 'static> Node for MetaVar<N> {
+    fn node_name(&self) -> &'static str {
+        "MetaVar"
+    }
+
+    fn children(&self) -> Vec<&dyn Node> {
+        Vec::new()
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut dyn Node> {
+        Vec::new()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl<N: PartialEq> TermEqStrict for MetaVar<N> {
+    fn term_eq_strict(&self, other: &MetaVar<N>) -> bool {
+        self == other
+    }
+}
@@ -0,0 +1,151 @@
+use std::any::Any;
+use std::fmt;
+
+use binder::{Binder, DuplicateBinderError};
+use bound::{BoundPattern, ScopeState};
+use diff::TermDiff;
+#[cfg(feature = "check-invariants")]
+use invariants;
+use meta_var::MetaVar;
+use node::Node;
+use strict::PatternEqStrict;
+use unify::{Unifier, Unify, UnifyError};
+use walk::{PatternTerms, Walker, WalkerMut};
+
+/// Multiple binding patterns, bound simultaneously
+///
+/// The binders of all of the patterns are bound at the same depth, so the
+/// terms embedded in each pattern can't refer to the binders of the other
+/// patterns. This is useful for things like `let x = a and y = b in ...`.
+///
+/// Contrast with `Nest`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Multi<P> {
+    pub patterns: Vec<P>,
+}
+
+impl<P> Multi<P> {
+    /// Bind the given patterns simultaneously
+    pub fn new<N>(patterns: Vec<P>) -> Multi<P>
+    where
+        N: Clone,
+        P: BoundPattern<N>,
+    {
+        #[cfg(feature = "check-invariants")]
+        invariants::assert_ok("Multi::new", invariants::check_linear(&patterns));
+
+        Multi { patterns }
+    }
+
+    /// Bind the given patterns simultaneously, returning an error if the same
+    /// free variable is bound more than once across all of the patterns
+    pub fn try_new<N>(patterns: Vec<P>) -> Result<Multi<P>, DuplicateBinderError<N>>
+    where
+        N: Clone,
+        P: BoundPattern<N>,
+    {
+        DuplicateBinderError::check(&patterns)?;
+        Ok(Multi::new(patterns))
+    }
+
+    /// Return the patterns
+    pub fn unmulti(self) -> Vec<P> {
+        self.patterns
+    }
+}
+
+impl<N, P> BoundPattern<N> for Multi<P>
+where
+    N: Clone,
+    P: BoundPattern<N>,
+{
+    fn pattern_eq(&self, other: &Multi<P>) -> bool {
+        <[P]>::pattern_eq(&self.patterns, &other.patterns)
+    }
+
+    fn pattern_diff(&self, other: &Multi<P>) -> Option<TermDiff>
+    where
+        N: fmt::Display,
+    {
+        <[P]>::pattern_diff(&self.patterns, &other.patterns)
+    }
+
+    fn close_pattern(&mut self, state: ScopeState, binders: &[Binder<N>]) {
+        <[P]>::close_pattern(&mut self.patterns, state, binders);
+    }
+
+    fn open_pattern(&mut self, state: ScopeState, binders: &[Binder<N>]) {
+        <[P]>::open_pattern(&mut self.patterns, state, binders);
+    }
+
+    fn visit_binders(&self, on_binder: &mut impl FnMut(&Binder<N>)) {
+        <[P]>::visit_binders(&self.patterns, on_binder);
+    }
+
+    fn visit_mut_binders(&mut self, on_binder: &mut impl FnMut(&mut Binder<N>)) {
+        <[P]>::visit_mut_binders(&mut self.patterns, on_binder);
+    }
+
+    fn walk_pattern(&self, terms: PatternTerms, walker: &mut impl Walker<N>) {
+        <[P]>::walk_pattern(&self.patterns, terms, walker);
+    }
+
+    fn walk_mut_pattern(&mut self, terms: PatternTerms, walker: &mut impl WalkerMut<N>) {
+        <[P]>::walk_mut_pattern(&mut self.patterns, terms, walker);
+    }
+}
+
+impl<N, T, P> Unify<N, T> for Multi<P>
+where
+    P: Unify<N, T>,
+{
+    fn unify(&self, other: &Multi<P>, unifier: &mut Unifier<N, T>) -> Result<(), UnifyError<N>> {
+        <[P]>::unify(&self.patterns, &other.patterns, unifier)
+    }
+
+    fn zonk(&mut self, unifier: &Unifier<N, T>) {
+        <[P]>::zonk(&mut self.patterns, unifier);
+    }
+
+    fn visit_meta_vars(&self, on_meta_var: &mut impl FnMut(&MetaVar<N>)) {
+        <[P]>::visit_meta_vars(&self.patterns, on_meta_var);
+    }
+
+    fn visit_mut_subterms(&mut self, on_subterm: &mut impl FnMut(&mut T)) {
+        <[P]>::visit_mut_subterms(&mut self.patterns, on_subterm);
+    }
+}
+
+impl<P: Node> Node for Multi<P> {
+    fn node_name(&self) -> &'static str {
+        "Multi"
+    }
+
+    fn children(&self) -> Vec<&dyn Node> {
+        self.patterns
+            .iter()
+            .map(|pattern| pattern as &dyn Node)
+            .collect()
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut dyn Node> {
+        self.patterns
+            .iter_mut()
+            .map(|pattern| pattern as &mut dyn Node)
+            .collect()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl<P: PatternEqStrict> PatternEqStrict for Multi<P> {
+    fn pattern_eq_strict(&self, other: &Multi<P>) -> bool {
+        <[P]>::pattern_eq_strict(&self.patterns, &other.patterns)
+    }
+}
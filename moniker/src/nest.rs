@@ -1,5 +1,16 @@
-use binder::Binder;
+use std::any::Any;
+use std::fmt;
+
+use binder::{Binder, DuplicateBinderError};
 use bound::{BoundPattern, ScopeState};
+use diff::TermDiff;
+#[cfg(feature = "check-invariants")]
+use invariants;
+use meta_var::MetaVar;
+use node::Node;
+use strict::PatternEqStrict;
+use unify::{Unifier, Unify, UnifyError};
+use walk::{FrameKind, PatternTerms, Walker, WalkerMut};
 
 /// Nested binding patterns
 ///
@@ -16,6 +27,9 @@ impl<P> Nest<P> {
         N: Clone,
         P: BoundPattern<N>,
     {
+        #[cfg(feature = "check-invariants")]
+        invariants::assert_ok("Nest::new", invariants::check_linear(&patterns));
+
         // FIXME: Avoid allocating new vector
         let mut rebound_patterns = Vec::<P>::with_capacity(patterns.len());
 
@@ -28,9 +42,25 @@ impl<P> Nest<P> {
             rebound_patterns.push(pattern);
         }
 
-        Nest {
+        let nest = Nest {
             unsafe_patterns: rebound_patterns,
-        }
+        };
+
+        #[cfg(feature = "check-invariants")]
+        invariants::assert_ok("Nest::new", invariants::check_pattern(&nest));
+
+        nest
+    }
+
+    /// Nest a term with the given patterns, returning an error if the same
+    /// free variable is bound more than once across all of the patterns
+    pub fn try_new<N>(patterns: Vec<P>) -> Result<Nest<P>, DuplicateBinderError<N>>
+    where
+        N: Clone,
+        P: BoundPattern<N>,
+    {
+        DuplicateBinderError::check(&patterns)?;
+        Ok(Nest::new(patterns))
     }
 
     /// Unnest a term, returning the freshened patterns
@@ -51,6 +81,12 @@ impl<P> Nest<P> {
             unrebound_patterns.push(pattern);
         }
 
+        #[cfg(feature = "check-invariants")]
+        invariants::assert_ok(
+            "Nest::unnest",
+            invariants::check_pattern(&unrebound_patterns),
+        );
+
         unrebound_patterns
     }
 }
@@ -64,6 +100,13 @@ where
         <[P]>::pattern_eq(&self.unsafe_patterns, &other.unsafe_patterns)
     }
 
+    fn pattern_diff(&self, other: &Nest<P>) -> Option<TermDiff>
+    where
+        N: fmt::Display,
+    {
+        <[P]>::pattern_diff(&self.unsafe_patterns, &other.unsafe_patterns)
+    }
+
     fn close_pattern(&mut self, mut state: ScopeState, binders: &[Binder<N>]) {
         for elem in &mut self.unsafe_patterns {
             elem.close_pattern(state, binders);
@@ -85,4 +128,88 @@ where
     fn visit_mut_binders(&mut self, on_binder: &mut impl FnMut(&mut Binder<N>)) {
         <[P]>::visit_mut_binders(&mut self.unsafe_patterns, on_binder);
     }
+
+    fn walk_pattern(&self, terms: PatternTerms, walker: &mut impl Walker<N>) {
+        for (i, elem) in self.unsafe_patterns.iter().enumerate() {
+            // The first pattern is bound at a depth of zero, so it needs to be
+            // the last frame that is entered
+            for bound_pattern in self.unsafe_patterns[..i].iter().rev() {
+                walker.enter_frame(FrameKind::Nest, bound_pattern);
+            }
+            elem.walk_pattern(terms, walker);
+            for _ in 0..i {
+                walker.exit_frame(FrameKind::Nest);
+            }
+        }
+    }
+
+    fn walk_mut_pattern(&mut self, terms: PatternTerms, walker: &mut impl WalkerMut<N>) {
+        for i in 0..self.unsafe_patterns.len() {
+            let (bound_patterns, patterns) = self.unsafe_patterns.split_at_mut(i);
+            // The first pattern is bound at a depth of zero, so it needs to be
+            // the last frame that is entered
+            for bound_pattern in bound_patterns.iter_mut().rev() {
+                walker.enter_frame(FrameKind::Nest, bound_pattern);
+            }
+            patterns[0].walk_mut_pattern(terms, walker);
+            for _ in 0..i {
+                walker.exit_frame(FrameKind::Nest);
+            }
+        }
+    }
+}
+
+impl<N, T, P> Unify<N, T> for Nest<P>
+where
+    P: Unify<N, T>,
+{
+    fn unify(&self, other: &Nest<P>, unifier: &mut Unifier<N, T>) -> Result<(), UnifyError<N>> {
+        <[P]>::unify(&self.unsafe_patterns, &other.unsafe_patterns, unifier)
+    }
+
+    fn zonk(&mut self, unifier: &Unifier<N, T>) {
+        <[P]>::zonk(&mut self.unsafe_patterns, unifier);
+    }
+
+    fn visit_meta_vars(&self, on_meta_var: &mut impl FnMut(&MetaVar<N>)) {
+        <[P]>::visit_meta_vars(&self.unsafe_patterns, on_meta_var);
+    }
+
+    fn visit_mut_subterms(&mut self, on_subterm: &mut impl FnMut(&mut T)) {
+        <[P]>::visit_mut_subterms(&mut self.unsafe_patterns, on_subterm);
+    }
+}
+
+impl<P: Node> Node for Nest<P> {
+    fn node_name(&self) -> &'static str {
+        "Nest"
+    }
+
+    fn children(&self) -> Vec<&dyn Node> {
+        self.unsafe_patterns
+            .iter()
+            .map(|pattern| pattern as &dyn Node)
+            .collect()
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut dyn Node> {
+        self.unsafe_patterns
+            .iter_mut()
+            .map(|pattern| pattern as &mut dyn Node)
+            .collect()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl<P: PatternEqStrict> PatternEqStrict for Nest<P> {
+    fn pattern_eq_strict(&self, other: &Nest<P>) -> bool {
+        <[P]>::pattern_eq_strict(&self.unsafe_patterns, &other.unsafe_patterns)
+    }
 }
@@ -0,0 +1,105 @@
+use std::any::Any;
+use std::fmt;
+
+use binder::Binder;
+use bound::{BoundPattern, BoundTerm, ScopeState};
+use diff::TermDiff;
+use meta_var::MetaVar;
+use node::Node;
+use strict::{PatternEqStrict, TermEqStrict};
+use unify::{Unifier, Unify, UnifyError};
+use walk::{PatternTerms, Walker, WalkerMut};
+
+/// Embed a term in a pattern, where it is unaffected by any binders
+///
+/// This corresponds to the `neutral` annotation from Cαml. The variables in
+/// the term are neither bound by the pattern, nor by any of the scopes that
+/// enclose it, so they will remain free. This can be useful for names that
+/// refer to global definitions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Neutral<T>(pub T);
+
+impl<N, T> BoundPattern<N> for Neutral<T>
+where
+    T: BoundTerm<N>,
+{
+    fn pattern_eq(&self, other: &Neutral<T>) -> bool {
+        T::term_eq(&self.0, &other.0)
+    }
+
+    fn pattern_diff(&self, other: &Neutral<T>) -> Option<TermDiff>
+    where
+        N: fmt::Display,
+    {
+        T::term_diff(&self.0, &other.0)
+    }
+
+    fn close_pattern(&mut self, _: ScopeState, _: &[Binder<N>]) {}
+
+    fn open_pattern(&mut self, _: ScopeState, _: &[Binder<N>]) {}
+
+    fn visit_binders(&self, _: &mut impl FnMut(&Binder<N>)) {}
+
+    fn visit_mut_binders(&mut self, _: &mut impl FnMut(&mut Binder<N>)) {}
+
+    fn walk_pattern(&self, terms: PatternTerms, walker: &mut impl Walker<N>) {
+        if terms.outer() {
+            self.0.walk_term(walker);
+        }
+    }
+
+    fn walk_mut_pattern(&mut self, terms: PatternTerms, walker: &mut impl WalkerMut<N>) {
+        if terms.outer() {
+            self.0.walk_mut_term(walker);
+        }
+    }
+}
+
+impl<N, T, U> Unify<N, T> for Neutral<U>
+where
+    U: Unify<N, T>,
+{
+    fn unify(&self, other: &Neutral<U>, unifier: &mut Unifier<N, T>) -> Result<(), UnifyError<N>> {
+        U::unify(&self.0, &other.0, unifier)
+    }
+
+    fn zonk(&mut self, unifier: &Unifier<N, T>) {
+        U::zonk(&mut self.0, unifier);
+    }
+
+    fn visit_meta_vars(&self, on_meta_var: &mut impl FnMut(&MetaVar<N>)) {
+        U::visit_meta_vars(&self.0, on_meta_var);
+    }
+
+    fn visit_mut_subterms(&mut self, on_subterm: &mut impl FnMut(&mut T)) {
+        U::visit_mut_subterms(&mut self.0, on_subterm);
+    }
+}
+
+impl<T: Node> Node for Neutral<T> {
+    fn node_name(&self) -> &'static str {
+        "Neutral"
+    }
+
+    fn children(&self) -> Vec<&dyn Node> {
+        vec![&self.0]
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut dyn Node> {
+        vec![&mut self.0]
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl<T: TermEqStrict> PatternEqStrict for Neutral<T> {
+    fn pattern_eq_strict(&self, other: &Neutral<T>) -> bool {
+        T::term_eq_strict(&self.0, &other.0)
+    }
+}
@@ -0,0 +1,410 @@
+use std::any::Any;
+use std::hash::Hash;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use binder::Binder;
+use bound::BoundTerm;
+use free_var::FreeVar;
+use rewrite::{self, Bindings};
+use unify::{MetaTerm, Unify};
+use var::Var;
+
+/// A node in the structural view of a term or pattern
+///
+/// This allows the children of a node to be enumerated without knowing their
+/// types, so that generic utilities like `size`, `depth`, and `subterms` can be
+/// written once for any AST. The children of a `Scope` are its pattern and its
+/// body, which are left closed, so nodes found under scopes might contain
+/// bound variables that refer to the binders of those scopes.
+pub trait Node: Any {
+    /// The name of the constructor of this node, for example the name of the
+    /// variant of an enum
+    fn node_name(&self) -> &'static str;
+
+    /// The immediate children of this node
+    fn children(&self) -> Vec<&dyn Node>;
+
+    /// The immediate children of this node, borrowed mutably
+    fn children_mut(&mut self) -> Vec<&mut dyn Node>;
+
+    /// The immediate children of this node that are compared for alpha
+    /// equivalence
+    ///
+    /// This leaves out the children that `BoundTerm::term_eq` skips, like the
+    /// fields marked with `#[moniker(eq = "skip")]` in derived impls. By
+    /// default this is the same as `children`.
+    fn eq_children(&self) -> Vec<&dyn Node> {
+        self.children()
+    }
+
+    /// Upcast this node, for downcasting to a concrete type
+    fn as_any(&self) -> &dyn Any;
+
+    /// Upcast this node mutably, for downcasting to a concrete type
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl dyn Node {
+    /// Returns the node as a `T` if it has that type
+    pub fn downcast_ref<T: Node>(&self) -> Option<&T> {
+        self.as_any().downcast_ref()
+    }
+
+    /// Returns the node as a mutable `T` if it has that type
+    pub fn downcast_mut<T: Node>(&mut self) -> Option<&mut T> {
+        self.as_any_mut().downcast_mut()
+    }
+}
+
+/// An iterator over the nodes of a term, in pre-order
+#[derive(Clone)]
+pub struct Nodes<'a> {
+    stack: Vec<&'a dyn Node>,
+}
+
+impl<'a> Iterator for Nodes<'a> {
+    type Item = &'a dyn Node;
+
+    fn next(&mut self) -> Option<&'a dyn Node> {
+        let node = self.stack.pop()?;
+        self.stack.extend(node.children().into_iter().rev());
+        Some(node)
+    }
+}
+
+/// Iterate over all of the nodes of a term, including the term itself, in
+/// pre-order
+pub fn nodes<'a>(root: &'a dyn Node) -> Nodes<'a> {
+    Nodes { stack: vec![root] }
+}
+
+/// Visit all of the nodes of a term mutably, including the term itself, in
+/// pre-order
+///
+/// The children of a node are visited after it has been visited, so any
+/// changes that it makes to them will be seen.
+///
+/// Shared nodes are copied on write: the children of `Rc`s and `Arc`s are
+/// borrowed mutably with `make_mut`, so they are cloned when they are shared,
+/// even if `on_node` leaves them unchanged. Use `nodes` to visit a term
+/// without copying it.
+pub fn visit_nodes_mut(root: &mut dyn Node, on_node: &mut impl FnMut(&mut dyn Node)) {
+    on_node(root);
+    for child in root.children_mut() {
+        visit_nodes_mut(child, on_node);
+    }
+}
+
+/// Iterate over the subterms of type `T` in a term, including the term
+/// itself, in pre-order
+pub fn subterms<T: Node>(root: &dyn Node) -> impl Iterator<Item = &T> {
+    nodes(root).filter_map(|node| node.downcast_ref())
+}
+
+/// The number of subterms of type `T` in a term, including the term itself
+pub fn size<T: Node>(root: &dyn Node) -> usize {
+    subterms::<T>(root).count()
+}
+
+/// The greatest number of subterms of type `T` that are nested inside each
+/// other in a term, including the term itself
+pub fn depth<T: Node>(root: &dyn Node) -> usize {
+    let children_depth = root
+        .children()
+        .into_iter()
+        .map(depth::<T>)
+        .max()
+        .unwrap_or(0);
+
+    match root.downcast_ref::<T>() {
+        Some(_) => children_depth + 1,
+        None => children_depth,
+    }
+}
+
+/// The subterms of type `T` in a term that satisfy a predicate, in pre-order
+pub fn find_subterms<T: Node>(root: &dyn Node, mut predicate: impl FnMut(&T) -> bool) -> Vec<&T> {
+    subterms(root).filter(|term| predicate(term)).collect()
+}
+
+/// The subterms of type `T` in a term that match a pattern, along with the
+/// bindings of the meta variables of the pattern, in pre-order
+///
+/// See `match_term` for how the pattern is matched. Subterms that are found
+/// under scopes are matched as they are, without being opened, so matches
+/// that would bind a meta variable to a term that refers to the binders of
+/// those scopes are skipped.
+pub fn find_matches<'a, N, T>(root: &'a dyn Node, pattern: &T) -> Vec<(&'a T, Bindings<N, T>)>
+where
+    N: Clone + Eq + Hash,
+    T: Node + Clone + MetaTerm<N> + BoundTerm<N> + Unify<N, T>,
+{
+    subterms(root)
+        .filter_map(|term| Some((term, rewrite::match_term(pattern, term)?)))
+        .filter(|(_, bindings)| bindings.values().all(T::is_locally_closed))
+        .collect()
+}
+
+macro_rules! impl_node_leaf {
+    ($name:expr, $T:ty) => {
+        impl Node for $T {
+            fn node_name(&self) -> &'static str {
+                $name
+            }
+
+            fn children(&self) -> Vec<&dyn Node> {
+                Vec::new()
+            }
+
+            fn children_mut(&mut self) -> Vec<&mut dyn Node> {
+                Vec::new()
+            }
+
+            fn as_any(&self) -> &dyn Any {
+                self
+            }
+
+            fn as_any_mut(&mut self) -> &mut dyn Any {
+                self
+            }
+        }
+    };
+    ($T:ty) => {
+        impl_node_leaf!(stringify!($T), $T);
+    };
+}
+
+impl_node_leaf!(());
+impl_node_leaf!(String);
+impl_node_leaf!(char);
+impl_node_leaf!(bool);
+impl_node_leaf!(u8);
+impl_node_leaf!(u16);
+impl_node_leaf!(u32);
+impl_node_leaf!(u64);
+impl_node_leaf!(usize);
+impl_node_leaf!(i8);
+impl_node_leaf!(i16);
+impl_node_leaf!(i32);
+impl_node_leaf!(i64);
+impl_node_leaf!(isize);
+impl_node_leaf!(f32);
+impl_node_leaf!(f64);
+
+impl<N: 'static> Node for FreeVar<N> {
+    fn node_name(&self) -> &'static str {
+        "FreeVar"
+    }
+
+    fn children(&self) -> Vec<&dyn Node> {
+        Vec::new()
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut dyn Node> {
+        Vec::new()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl<N: 'static> Node for Var<N> {
+    fn node_name(&self) -> &'static str {
+        match *self {
+            Var::Free(_) => "Free",
+            Var::Bound(_) => "Bound",
+        }
+    }
+
+    fn children(&self) -> Vec<&dyn Node> {
+        Vec::new()
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut dyn Node> {
+        Vec::new()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl<N: 'static> Node for Binder<N> {
+    fn node_name(&self) -> &'static str {
+        "Binder"
+    }
+
+    fn children(&self) -> Vec<&dyn Node> {
+        Vec::new()
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut dyn Node> {
+        Vec::new()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl<T: Node> Node for Option<T> {
+    fn node_name(&self) -> &'static str {
+        match *self {
+            Some(_) => "Some",
+            None => "None",
+        }
+    }
+
+    fn children(&self) -> Vec<&dyn Node> {
+        self.iter().map(|inner| inner as &dyn Node).collect()
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut dyn Node> {
+        self.iter_mut()
+            .map(|inner| inner as &mut dyn Node)
+            .collect()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl<T: Node> Node for Box<T> {
+    fn node_name(&self) -> &'static str {
+        "Box"
+    }
+
+    fn children(&self) -> Vec<&dyn Node> {
+        vec![&**self]
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut dyn Node> {
+        vec![&mut **self]
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl<T: Node + Clone> Node for Rc<T> {
+    fn node_name(&self) -> &'static str {
+        "Rc"
+    }
+
+    fn children(&self) -> Vec<&dyn Node> {
+        vec![&**self]
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut dyn Node> {
+        // Clones the child if it is shared
+        vec![Rc::make_mut(self) as &mut dyn Node]
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl<T: Node + Clone> Node for Arc<T> {
+    fn node_name(&self) -> &'static str {
+        "Arc"
+    }
+
+    fn children(&self) -> Vec<&dyn Node> {
+        vec![&**self]
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut dyn Node> {
+        // Clones the child if it is shared
+        vec![Arc::make_mut(self) as &mut dyn Node]
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+macro_rules! impl_node_tuple {
+    ($($T:ident: $index:tt),+) => {
+        impl<$($T: Node),+> Node for ($($T,)+) {
+            fn node_name(&self) -> &'static str {
+                "Tuple"
+            }
+
+            fn children(&self) -> Vec<&dyn Node> {
+                vec![$(&self.$index),+]
+            }
+
+            fn children_mut(&mut self) -> Vec<&mut dyn Node> {
+                vec![$(&mut self.$index),+]
+            }
+
+            fn as_any(&self) -> &dyn Any {
+                self
+            }
+
+            fn as_any_mut(&mut self) -> &mut dyn Any {
+                self
+            }
+        }
+    };
+}
+
+impl_node_tuple!(T1: 0, T2: 1);
+impl_node_tuple!(T1: 0, T2: 1, T3: 2);
+impl_node_tuple!(T1: 0, T2: 1, T3: 2, T4: 3);
+impl_node_tuple!(T1: 0, T2: 1, T3: 2, T4: 3, T5: 4);
+
+impl<T: Node> Node for Vec<T> {
+    fn node_name(&self) -> &'static str {
+        "Vec"
+    }
+
+    fn children(&self) -> Vec<&dyn Node> {
+        self.iter().map(|elem| elem as &dyn Node).collect()
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut dyn Node> {
+        self.iter_mut().map(|elem| elem as &mut dyn Node).collect()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
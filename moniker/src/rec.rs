@@ -1,5 +1,20 @@
-use binder::Binder;
-use bound::{BoundPattern, ScopeState};
+use std::any::Any;
+use std::cmp;
+use std::fmt;
+use std::hash::Hash;
+
+use binder::{Binder, DuplicateBinderError};
+use bound::{renumber_binders, BoundPattern, BoundTerm, ScopeState};
+use diff::TermDiff;
+use embed::Embed;
+#[cfg(feature = "check-invariants")]
+use invariants;
+use meta_var::MetaVar;
+use nest::Nest;
+use node::Node;
+use strict::PatternEqStrict;
+use unify::{Unifier, Unify, UnifyError};
+use walk::{FrameKind, PatternTerms, Walker, WalkerMut};
 
 /// Recursively bind a pattern in itself
 ///
@@ -14,40 +29,200 @@ impl<P> Rec<P> {
     pub fn new<N>(mut pattern: P) -> Rec<P>
     where
         N: Clone,
-        P: BoundPattern<N>,
+        P: Clone + BoundPattern<N>,
     {
+        #[cfg(feature = "check-invariants")]
+        invariants::assert_ok("Rec::new", invariants::check_linear(&pattern));
+
         let binders = pattern.binders();
         pattern.close_pattern(ScopeState::new(), &binders);
-        Rec {
+        let rec = Rec {
             unsafe_pattern: pattern,
-        }
+        };
+
+        #[cfg(feature = "check-invariants")]
+        invariants::assert_ok("Rec::new", invariants::check_pattern(&rec));
+
+        rec
+    }
+
+    /// Recursively bind a pattern in itself, returning an error if the pattern
+    /// binds the same free variable more than once
+    pub fn try_new<N>(pattern: P) -> Result<Rec<P>, DuplicateBinderError<N>>
+    where
+        N: Clone,
+        P: Clone + BoundPattern<N>,
+    {
+        DuplicateBinderError::check(&pattern)?;
+        Ok(Rec::new(pattern))
     }
 
     pub fn unrec<N>(mut self) -> P
     where
         N: Clone,
-        P: BoundPattern<N>,
+        P: Clone + BoundPattern<N>,
     {
         let binders = self.unsafe_pattern.binders();
-        self.open_pattern(ScopeState::new(), &binders);
+        self.unsafe_pattern
+            .open_pattern(ScopeState::new(), &binders);
+
+        #[cfg(feature = "check-invariants")]
+        invariants::assert_ok(
+            "Rec::unrec",
+            invariants::check_pattern(&self.unsafe_pattern),
+        );
+
         self.unsafe_pattern
     }
 }
 
+/// A list of bindings, as found in a `let rec`
+pub type RecBindings<N, T> = Vec<(Binder<N>, Embed<T>)>;
+
+impl<N, T> Rec<RecBindings<N, T>> {
+    /// Split the bindings into their strongly connected components
+    ///
+    /// Dependencies between the bindings are found using the free variables
+    /// of the embedded terms. The components are returned as a `Nest` of
+    /// smaller `Rec` groups, ordered so that each group only refers to itself
+    /// or to the groups that come before it. Bindings keep their original
+    /// order within each group.
+    ///
+    /// This is useful for dependency analysis before type inference, where
+    /// the bindings that are not actually mutually recursive should be
+    /// generalized separately.
+    pub fn into_sccs(self) -> Nest<Rec<RecBindings<N, T>>>
+    where
+        N: Clone + Eq + Hash,
+        T: Clone + BoundTerm<N>,
+    {
+        let bindings = self.unrec();
+        let dependencies = bindings
+            .iter()
+            .map(|&(_, Embed(ref term))| {
+                let free_vars = term.free_vars();
+                bindings
+                    .iter()
+                    .enumerate()
+                    .filter(|&(_, &(Binder(ref free_var), _))| free_vars.contains(free_var))
+                    .map(|(index, _)| index)
+                    .collect()
+            })
+            .collect::<Vec<Vec<_>>>();
+
+        let mut bindings = bindings.into_iter().map(Some).collect::<Vec<_>>();
+        let groups = strongly_connected_components(&dependencies)
+            .into_iter()
+            .map(|mut component| {
+                component.sort();
+                let group = component
+                    .into_iter()
+                    .map(|index| bindings[index].take().unwrap())
+                    .collect();
+
+                Rec::new(group)
+            })
+            .collect();
+
+        Nest::new(groups)
+    }
+}
+
+/// Find the strongly connected components of a dependency graph using
+/// Tarjan's algorithm
+///
+/// The components are returned in dependency order, so each component only
+/// depends on itself and on the components that come before it.
+fn strongly_connected_components(dependencies: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    struct Tarjan<'a> {
+        dependencies: &'a [Vec<usize>],
+        indices: Vec<Option<usize>>,
+        low_links: Vec<usize>,
+        on_stack: Vec<bool>,
+        stack: Vec<usize>,
+        next_index: usize,
+        components: Vec<Vec<usize>>,
+    }
+
+    impl<'a> Tarjan<'a> {
+        fn visit(&mut self, node: usize) {
+            self.indices[node] = Some(self.next_index);
+            self.low_links[node] = self.next_index;
+            self.next_index += 1;
+            self.stack.push(node);
+            self.on_stack[node] = true;
+
+            for &dependency in &self.dependencies[node] {
+                match self.indices[dependency] {
+                    None => {
+                        self.visit(dependency);
+                        self.low_links[node] =
+                            cmp::min(self.low_links[node], self.low_links[dependency]);
+                    },
+                    Some(index) if self.on_stack[dependency] => {
+                        self.low_links[node] = cmp::min(self.low_links[node], index);
+                    },
+                    Some(_) => {},
+                }
+            }
+
+            if Some(self.low_links[node]) == self.indices[node] {
+                let mut component = Vec::new();
+                loop {
+                    let member = self.stack.pop().unwrap();
+                    self.on_stack[member] = false;
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                self.components.push(component);
+            }
+        }
+    }
+
+    let mut tarjan = Tarjan {
+        dependencies,
+        indices: vec![None; dependencies.len()],
+        low_links: vec![0; dependencies.len()],
+        on_stack: vec![false; dependencies.len()],
+        stack: Vec::new(),
+        next_index: 0,
+        components: Vec::new(),
+    };
+
+    for node in 0..dependencies.len() {
+        if tarjan.indices[node].is_none() {
+            tarjan.visit(node);
+        }
+    }
+
+    tarjan.components
+}
+
 impl<N, P> BoundPattern<N> for Rec<P>
 where
-    P: BoundPattern<N>,
+    P: Clone + BoundPattern<N>,
 {
     fn pattern_eq(&self, other: &Rec<P>) -> bool {
         P::pattern_eq(&self.unsafe_pattern, &other.unsafe_pattern)
     }
 
+    fn pattern_diff(&self, other: &Rec<P>) -> Option<TermDiff>
+    where
+        N: fmt::Display,
+    {
+        P::pattern_diff(&self.unsafe_pattern, &other.unsafe_pattern)
+    }
+
     fn close_pattern(&mut self, state: ScopeState, binders: &[Binder<N>]) {
-        self.unsafe_pattern.close_pattern(state, binders);
+        // The pattern's own binders occupy the innermost scope, so the binders
+        // of the enclosing scopes are one level further out
+        self.unsafe_pattern.close_pattern(state.incr(), binders);
     }
 
     fn open_pattern(&mut self, state: ScopeState, binders: &[Binder<N>]) {
-        self.unsafe_pattern.open_pattern(state, binders);
+        self.unsafe_pattern.open_pattern(state.incr(), binders);
     }
 
     fn visit_binders(&self, on_binder: &mut impl FnMut(&Binder<N>)) {
@@ -57,4 +232,89 @@ where
     fn visit_mut_binders(&mut self, on_binder: &mut impl FnMut(&mut Binder<N>)) {
         self.unsafe_pattern.visit_mut_binders(on_binder);
     }
+
+    fn walk_pattern(&self, terms: PatternTerms, walker: &mut impl Walker<N>) {
+        walker.enter_frame(FrameKind::Rec, &self.unsafe_pattern);
+        self.unsafe_pattern.walk_pattern(terms, walker);
+        walker.exit_frame(FrameKind::Rec);
+    }
+
+    fn walk_mut_pattern(&mut self, terms: PatternTerms, walker: &mut impl WalkerMut<N>) {
+        walker.enter_frame(FrameKind::Rec, &mut self.unsafe_pattern);
+        self.unsafe_pattern.walk_mut_pattern(terms, walker);
+        walker.exit_frame(FrameKind::Rec);
+    }
+
+    fn try_reorderings(
+        &self,
+        other: &Rec<P>,
+        on_reordering: &mut dyn FnMut(&Rec<P>, &[usize]) -> bool,
+    ) -> bool {
+        self.unsafe_pattern
+            .try_reorderings(&other.unsafe_pattern, &mut |pattern, positions| {
+                // The pattern refers to its own binders, so they are renumbered
+                // here rather than by the enclosing scope
+                let mut pattern = pattern.clone();
+                renumber_binders(positions, |original_binders, reordered_binders| {
+                    pattern.open_pattern(ScopeState::new(), original_binders);
+                    pattern.close_pattern(ScopeState::new(), reordered_binders);
+                });
+
+                on_reordering(
+                    &Rec {
+                        unsafe_pattern: pattern,
+                    },
+                    positions,
+                )
+            })
+    }
+}
+
+impl<N, T, P> Unify<N, T> for Rec<P>
+where
+    P: Unify<N, T>,
+{
+    fn unify(&self, other: &Rec<P>, unifier: &mut Unifier<N, T>) -> Result<(), UnifyError<N>> {
+        P::unify(&self.unsafe_pattern, &other.unsafe_pattern, unifier)
+    }
+
+    fn zonk(&mut self, unifier: &Unifier<N, T>) {
+        P::zonk(&mut self.unsafe_pattern, unifier);
+    }
+
+    fn visit_meta_vars(&self, on_meta_var: &mut impl FnMut(&MetaVar<N>)) {
+        P::visit_meta_vars(&self.unsafe_pattern, on_meta_var);
+    }
+
+    fn visit_mut_subterms(&mut self, on_subterm: &mut impl FnMut(&mut T)) {
+        P::visit_mut_subterms(&mut self.unsafe_pattern, on_subterm);
+    }
+}
+
+impl<P: Node> Node for Rec<P> {
+    fn node_name(&self) -> &'static str {
+        "Rec"
+    }
+
+    fn children(&self) -> Vec<&dyn Node> {
+        vec![&self.unsafe_pattern]
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut dyn Node> {
+        vec![&mut self.unsafe_pattern]
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl<P: PatternEqStrict> PatternEqStrict for Rec<P> {
+    fn pattern_eq_strict(&self, other: &Rec<P>) -> bool {
+        P::pattern_eq_strict(&self.unsafe_pattern, &other.unsafe_pattern)
+    }
 }
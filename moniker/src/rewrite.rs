@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+
+use bound::BoundTerm;
+use free_var::FreeVar;
+use meta_var::MetaVar;
+use unify::{MetaTerm, Unifier, Unify};
+
+/// The terms that the meta variables of a pattern were matched against
+pub type Bindings<N, T> = HashMap<MetaVar<N>, T>;
+
+/// Match a pattern against a term, modulo alpha equivalence
+///
+/// The meta variables in the pattern stand for arbitrary terms, and the
+/// returned bindings record the terms that they were matched against. Meta
+/// variables that occur more than once must be matched against alpha
+/// equivalent terms. The bindings never refer to the binders of the scopes in
+/// the matched term, so for example `\x => ?e` matches `\y => 1` but not
+/// `\y => y`. Higher-order patterns, where meta variables stand for terms
+/// with holes that are filled by binders, are not supported. The meta
+/// variables in the term itself are treated as constants.
+pub fn match_term<N, T, U>(pattern: &U, term: &U) -> Option<Bindings<N, T>>
+where
+    N: Clone + Eq + Hash,
+    T: Clone + MetaTerm<N> + BoundTerm<N> + Unify<N, T>,
+    U: Unify<N, T>,
+{
+    let mut unifier = Unifier::new_one_way();
+    match unifier.unify(pattern, term) {
+        Ok(()) => Some(unifier.into_subst()),
+        Err(_) => None,
+    }
+}
+
+/// Replace the meta variables in a term with the terms that they were bound to
+///
+/// This is capture-avoiding, because the bindings never contain dangling bound
+/// variables, and the free variables in the bindings can't be captured by the
+/// scopes in the term.
+pub fn instantiate<N, T, U>(bindings: &Bindings<N, T>, term: U) -> U
+where
+    N: Clone + Eq + Hash,
+    T: Clone + MetaTerm<N> + BoundTerm<N> + Unify<N, T>,
+    U: Unify<N, T>,
+{
+    Unifier::from_subst(bindings.clone()).zonk(term)
+}
+
+/// Replace a free variable in a term with another term
+///
+/// This is the substitution `e[x := a]` of beta reduction. Scopes are unbound
+/// on the way down, so like `instantiate` this is capture-avoiding.
+pub fn subst<N, T>(term: &T, free_var: &FreeVar<N>, replacement: &T) -> T
+where
+    N: PartialEq,
+    T: Clone + MetaTerm<N>,
+{
+    fn subst_mut<N, T>(term: &mut T, free_var: &FreeVar<N>, replacement: &T)
+    where
+        N: PartialEq,
+        T: Clone + MetaTerm<N>,
+    {
+        if term.as_free_var() == Some(free_var) {
+            *term = replacement.clone();
+        } else {
+            term.visit_mut_children(&mut |child| subst_mut(child, free_var, replacement));
+        }
+    }
+
+    let mut term = term.clone();
+    subst_mut(&mut term, free_var, replacement);
+    term
+}
+
+/// A rewrite rule
+///
+/// The left hand side is a pattern that is matched against terms with
+/// `match_term`. The right hand side is either a term that is instantiated
+/// with the resulting bindings, or a function that computes the replacement
+/// from the bindings, returning `None` if the rule should not apply.
+///
+/// Because the bindings never refer to the binders of the matched scopes,
+/// templates can't move the bodies of scopes out from under their binders.
+/// Rules like beta reduction, `(\x => e) a ~> e[x := a]`, should instead
+/// bind the whole scope to a meta variable, and unbind it in a function
+/// passed to `Rule::with_fn`, using `subst` to replace the binder in the body.
+pub struct Rule<N, T> {
+    pub lhs: T,
+    rhs: Rhs<N, T>,
+}
+
+/// A function that computes the replacement of a term from the bindings
+type ComputeFn<N, T> = Box<dyn Fn(&Bindings<N, T>) -> Option<T>>;
+
+enum Rhs<N, T> {
+    Template(T),
+    Compute(ComputeFn<N, T>),
+}
+
+impl<N, T> Rule<N, T>
+where
+    N: Clone + Eq + Hash,
+    T: Clone + MetaTerm<N> + BoundTerm<N> + Unify<N, T>,
+{
+    /// Create a rule that replaces terms that match `lhs` with `rhs`
+    pub fn new(lhs: T, rhs: T) -> Rule<N, T> {
+        Rule {
+            lhs,
+            rhs: Rhs::Template(rhs),
+        }
+    }
+
+    /// Create a rule that replaces terms that match `lhs` with the result of
+    /// calling `rhs` on the bindings
+    pub fn with_fn(lhs: T, rhs: impl Fn(&Bindings<N, T>) -> Option<T> + 'static) -> Rule<N, T> {
+        Rule {
+            lhs,
+            rhs: Rhs::Compute(Box::new(rhs)),
+        }
+    }
+
+    /// Apply the rule to the root of a term, returning the replacement if the
+    /// rule applies
+    pub fn apply(&self, term: &T) -> Option<T> {
+        let bindings = match_term(&self.lhs, term)?;
+
+        match self.rhs {
+            Rhs::Template(ref rhs) => Some(instantiate(&bindings, rhs.clone())),
+            Rhs::Compute(ref rhs) => rhs(&bindings),
+        }
+    }
+}
+
+impl<N: fmt::Debug, T: fmt::Debug> fmt::Debug for Rule<N, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut debug = f.debug_struct("Rule");
+        debug.field("lhs", &self.lhs);
+        match self.rhs {
+            Rhs::Template(ref rhs) => debug.field("rhs", rhs),
+            Rhs::Compute(_) => debug.field("rhs", &"<fn>"),
+        };
+        debug.finish()
+    }
+}
+
+/// The order in which the subterms of a term are rewritten
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Strategy {
+    /// Rewrite the children of a term before the term itself
+    BottomUp,
+    /// Rewrite a term before its children
+    TopDown,
+}
+
+/// Rewrite a term with the given rules until none of them apply
+///
+/// The rules are tried in order at each subterm. Scopes are unbound before
+/// rewriting their contents, so the rules never see dangling bound variables.
+/// This will loop forever if the rules are not terminating.
+pub fn rewrite<N, T>(rules: &[Rule<N, T>], strategy: Strategy, mut term: T) -> T
+where
+    N: Clone + Eq + Hash,
+    T: Clone + MetaTerm<N> + BoundTerm<N> + Unify<N, T>,
+{
+    let pass = match strategy {
+        Strategy::BottomUp => rewrite_bottom_up,
+        Strategy::TopDown => rewrite_top_down,
+    };
+
+    while pass(rules, &mut term) {}
+    term
+}
+
+/// Try each of the rules at the root of a term, returning the replacement of
+/// the first rule that applies
+fn apply_rules<N, T>(rules: &[Rule<N, T>], term: &T) -> Option<T>
+where
+    N: Clone + Eq + Hash,
+    T: Clone + MetaTerm<N> + BoundTerm<N> + Unify<N, T>,
+{
+    rules.iter().filter_map(|rule| rule.apply(term)).next()
+}
+
+/// Rewrite the children of a term, and then the term itself, returning `true`
+/// if anything was rewritten
+fn rewrite_bottom_up<N, T>(rules: &[Rule<N, T>], term: &mut T) -> bool
+where
+    N: Clone + Eq + Hash,
+    T: Clone + MetaTerm<N> + BoundTerm<N> + Unify<N, T>,
+{
+    let mut changed = false;
+    term.visit_mut_children(&mut |child| changed |= rewrite_bottom_up(rules, child));
+
+    if let Some(new_term) = apply_rules(rules, term) {
+        *term = new_term;
+        // The children of the new term might now be rewritable
+        rewrite_bottom_up(rules, term);
+        changed = true;
+    }
+
+    changed
+}
+
+/// Rewrite a term until none of the rules apply to it, and then rewrite its
+/// children, returning `true` if anything was rewritten
+fn rewrite_top_down<N, T>(rules: &[Rule<N, T>], term: &mut T) -> bool
+where
+    N: Clone + Eq + Hash,
+    T: Clone + MetaTerm<N> + BoundTerm<N> + Unify<N, T>,
+{
+    let mut changed = false;
+    while let Some(new_term) = apply_rules(rules, term) {
+        *term = new_term;
+        changed = true;
+    }
+
+    term.visit_mut_children(&mut |child| changed |= rewrite_top_down(rules, child));
+    changed
+}
@@ -1,9 +1,21 @@
+use std::any::Any;
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
 use std::hash::Hash;
 
-use binder::Binder;
-use bound::{BoundPattern, BoundTerm, ScopeState};
+use binder::{Binder, DuplicateBinderError};
+use bound::{renumber_binders, BoundPattern, BoundTerm, ScopeState};
+use diff::TermDiff;
 use free_var::FreeVar;
+#[cfg(feature = "check-invariants")]
+use invariants;
+use meta_var::MetaVar;
+use node::Node;
+use strict::{PatternEqStrict, TermEqStrict};
+use unify::{Unifier, Unify, UnifyError};
 use var::Var;
+use walk::{FrameKind, PatternTerms, Walker, WalkerMut};
 
 /// A bound scope
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -22,18 +34,40 @@ pub struct Scope<P, T> {
 
 impl<P, T> Scope<P, T> {
     /// Create a new scope by binding a term with the given pattern
-    pub fn new<N>(pattern: P, mut body: T) -> Scope<P, T>
+    pub fn new<N>(mut pattern: P, mut body: T) -> Scope<P, T>
     where
         N: Clone,
-        P: BoundPattern<N>,
-        T: BoundTerm<N>,
+        P: Clone + BoundPattern<N>,
+        T: Clone + BoundTerm<N>,
     {
-        body.close_term(ScopeState::new(), &pattern.binders());
+        #[cfg(feature = "check-invariants")]
+        invariants::assert_ok("Scope::new", invariants::check_linear(&pattern));
+
+        let binders = pattern.binders();
+        pattern.close_pattern(ScopeState::own(), &binders);
+        body.close_term(ScopeState::new(), &binders);
 
-        Scope {
+        let scope = Scope {
             unsafe_pattern: pattern,
             unsafe_body: body,
-        }
+        };
+
+        #[cfg(feature = "check-invariants")]
+        invariants::assert_ok("Scope::new", invariants::check_term(&scope));
+
+        scope
+    }
+
+    /// Create a new scope by binding a term with the given pattern, returning
+    /// an error if the pattern binds the same free variable more than once
+    pub fn try_new<N>(pattern: P, body: T) -> Result<Scope<P, T>, DuplicateBinderError<N>>
+    where
+        N: Clone,
+        P: Clone + BoundPattern<N>,
+        T: Clone + BoundTerm<N>,
+    {
+        DuplicateBinderError::check(&pattern)?;
+        Ok(Scope::new(pattern, body))
     }
 
     /// Unbind a term, returning the freshened pattern and body
@@ -43,6 +77,12 @@ impl<P, T> Scope<P, T> {
         P: BoundPattern<N>,
         T: BoundTerm<N>,
     {
+        #[cfg(feature = "check-invariants")]
+        invariants::assert_ok(
+            "Scope::unbind",
+            invariants::check_linear(&self.unsafe_pattern),
+        );
+
         let mut pattern = self.unsafe_pattern;
         let mut body = self.unsafe_body;
 
@@ -51,7 +91,15 @@ impl<P, T> Scope<P, T> {
             *binder = Binder(FreeVar::fresh(binder.0.pretty_name.clone()));
         });
         // Use the freshened binders when opening the body
-        body.open_term(ScopeState::new(), &pattern.binders());
+        let binders = pattern.binders();
+        pattern.open_pattern(ScopeState::own(), &binders);
+        body.open_term(ScopeState::new(), &binders);
+
+        #[cfg(feature = "check-invariants")]
+        {
+            invariants::assert_ok("Scope::unbind", invariants::check_pattern(&pattern));
+            invariants::assert_ok("Scope::unbind", invariants::check_term(&body));
+        }
 
         (pattern, body)
     }
@@ -115,30 +163,155 @@ impl<P, T> Scope<P, T> {
         }
 
         // Finally, use the binders from the freshened patterns to open the body terms
-        self_body.open_term(ScopeState::new(), &self_pattern.binders());
-        other_body.open_term(ScopeState::new(), &other_pattern.binders());
+        let self_binders = self_pattern.binders();
+        self_pattern.open_pattern(ScopeState::own(), &self_binders);
+        self_body.open_term(ScopeState::new(), &self_binders);
+
+        let other_binders = other_pattern.binders();
+        other_pattern.open_pattern(ScopeState::own(), &other_binders);
+        other_body.open_term(ScopeState::new(), &other_binders);
 
         (self_pattern, self_body, other_pattern, other_body)
     }
+
+    /// Lift the free variables of the scope into its pattern
+    ///
+    /// This returns the free variables that were captured by the scope, in
+    /// order of their first occurrence, along with a new, closed scope that
+    /// binds them before the binders of the original pattern. This is useful
+    /// for closure conversion, where the captured variables can be used to
+    /// build the environment of the closure.
+    ///
+    /// Terms that are embedded in the pattern outside of the scope of its
+    /// binders, for example with `Embed`, can't refer to the new binders, so
+    /// an error is returned if any of them have free variables.
+    pub fn lift_free_vars<N>(self) -> Result<LiftedScope<N, P, T>, OuterFreeVarError<N>>
+    where
+        N: Clone + Eq + Hash,
+        P: Clone + BoundPattern<N>,
+        T: Clone + BoundTerm<N>,
+    {
+        let mut collector = FreeVarCollector {
+            seen: HashSet::new(),
+            free_vars: Vec::new(),
+        };
+        self.unsafe_pattern
+            .walk_pattern(PatternTerms::Outer, &mut collector);
+        if let Some(free_var) = collector.free_vars.into_iter().next() {
+            return Err(OuterFreeVarError { free_var });
+        }
+
+        let mut collector = FreeVarCollector {
+            seen: HashSet::new(),
+            free_vars: Vec::new(),
+        };
+        self.walk_term(&mut collector);
+
+        let free_vars = collector.free_vars;
+        let binders = free_vars.iter().cloned().map(Binder).collect();
+        let (pattern, body) = self.unbind();
+
+        Ok((free_vars, Scope::new((binders, pattern), body)))
+    }
+}
+
+/// The free variables that were captured by a scope, along with a scope that
+/// binds them, as returned by `Scope::lift_free_vars`
+pub type LiftedScope<N, P, T> = (Vec<FreeVar<N>>, Scope<(Vec<Binder<N>>, P), T>);
+
+/// An error that is returned when the free variables of a scope can't be
+/// lifted into its pattern
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OuterFreeVarError<N> {
+    /// A free variable in a term that is outside of the scope of the pattern
+    pub free_var: FreeVar<N>,
+}
+
+impl<N: fmt::Display> fmt::Display for OuterFreeVarError<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "`{}` is free in a term that is outside of the scope of the pattern",
+            self.free_var
+        )
+    }
+}
+
+impl<N: fmt::Debug + fmt::Display> Error for OuterFreeVarError<N> {}
+
+/// Collects the free variables that are found when walking a term, in order
+/// of their first occurrence
+struct FreeVarCollector<N> {
+    seen: HashSet<FreeVar<N>>,
+    free_vars: Vec<FreeVar<N>>,
+}
+
+impl<N> Walker<N> for FreeVarCollector<N>
+where
+    N: Clone + Eq + Hash,
+{
+    fn on_var(&mut self, var: &Var<N>) {
+        if let Var::Free(ref free_var) = *var {
+            if self.seen.insert(free_var.clone()) {
+                self.free_vars.push(free_var.clone());
+            }
+        }
+    }
 }
 
 impl<N, P, T> BoundTerm<N> for Scope<P, T>
 where
-    P: BoundPattern<N>,
-    T: BoundTerm<N>,
+    P: Clone + BoundPattern<N>,
+    T: Clone + BoundTerm<N>,
 {
     fn term_eq(&self, other: &Scope<P, T>) -> bool {
-        P::pattern_eq(&self.unsafe_pattern, &other.unsafe_pattern)
+        if P::pattern_eq(&self.unsafe_pattern, &other.unsafe_pattern)
             && T::term_eq(&self.unsafe_body, &other.unsafe_body)
+        {
+            return true;
+        }
+
+        // Patterns like `Unordered` might match in a different order, in
+        // which case the body has to be compared using the same order
+        self.unsafe_pattern
+            .try_reorderings(&other.unsafe_pattern, &mut |pattern, positions| {
+                let mut pattern = pattern.clone();
+                let mut body = other.unsafe_body.clone();
+                renumber_binders(positions, |original_binders, reordered_binders| {
+                    pattern.open_pattern(ScopeState::own(), original_binders);
+                    pattern.close_pattern(ScopeState::own(), reordered_binders);
+                    body.open_term(ScopeState::new(), original_binders);
+                    body.close_term(ScopeState::new(), reordered_binders);
+                });
+
+                P::pattern_eq(&self.unsafe_pattern, &pattern)
+                    && T::term_eq(&self.unsafe_body, &body)
+            })
+    }
+
+    fn term_diff(&self, other: &Scope<P, T>) -> Option<TermDiff>
+    where
+        N: fmt::Display,
+    {
+        if self.term_eq(other) {
+            return None;
+        }
+
+        P::pattern_diff(&self.unsafe_pattern, &other.unsafe_pattern)
+            .map(|diff| diff.within("pattern"))
+            .or_else(|| {
+                T::term_diff(&self.unsafe_body, &other.unsafe_body).map(|diff| diff.within("body"))
+            })
     }
 
     fn close_term(&mut self, state: ScopeState, binders: &[Binder<N>]) {
-        self.unsafe_pattern.close_pattern(state, binders);
+        self.unsafe_pattern
+            .close_pattern(state.enclosing(), binders);
         self.unsafe_body.close_term(state.incr(), binders);
     }
 
     fn open_term(&mut self, state: ScopeState, binders: &[Binder<N>]) {
-        self.unsafe_pattern.open_pattern(state, binders);
+        self.unsafe_pattern.open_pattern(state.enclosing(), binders);
         self.unsafe_body.open_term(state.incr(), binders);
     }
 
@@ -149,4 +322,104 @@ where
     fn visit_mut_vars(&mut self, on_var: &mut impl FnMut(&mut Var<N>)) {
         self.unsafe_body.visit_mut_vars(on_var);
     }
+
+    fn walk_term(&self, walker: &mut impl Walker<N>) {
+        self.unsafe_pattern
+            .walk_pattern(PatternTerms::Outer, walker);
+        walker.enter_frame(FrameKind::Scope, &self.unsafe_pattern);
+        self.unsafe_pattern
+            .walk_pattern(PatternTerms::Inner, walker);
+        self.unsafe_body.walk_term(walker);
+        walker.exit_frame(FrameKind::Scope);
+    }
+
+    fn walk_mut_term(&mut self, walker: &mut impl WalkerMut<N>) {
+        self.unsafe_pattern
+            .walk_mut_pattern(PatternTerms::Outer, walker);
+        walker.enter_frame(FrameKind::Scope, &mut self.unsafe_pattern);
+        self.unsafe_pattern
+            .walk_mut_pattern(PatternTerms::Inner, walker);
+        self.unsafe_body.walk_mut_term(walker);
+        walker.exit_frame(FrameKind::Scope);
+    }
+}
+
+impl<N, T, P, U> Unify<N, T> for Scope<P, U>
+where
+    N: Clone + Eq + Hash,
+    P: Clone + BoundPattern<N> + Unify<N, T>,
+    U: Clone + BoundTerm<N> + Unify<N, T>,
+{
+    fn unify(&self, other: &Scope<P, U>, unifier: &mut Unifier<N, T>) -> Result<(), UnifyError<N>> {
+        if self.unsafe_pattern.binders().len() != other.unsafe_pattern.binders().len() {
+            return Err(UnifyError::Mismatch);
+        }
+
+        // Open both scopes with the same fresh binders, which are rigid while
+        // we unify the patterns and the bodies
+        let (self_pattern, self_body, other_pattern, other_body) =
+            self.clone().unbind2(other.clone());
+        let rigid_vars_len = unifier.rigid_vars.len();
+        unifier.rigid_vars.extend(
+            self_pattern
+                .binders()
+                .into_iter()
+                .map(|Binder(free_var)| free_var),
+        );
+
+        let result = self_pattern
+            .unify(&other_pattern, unifier)
+            .and_then(|()| self_body.unify(&other_body, unifier));
+
+        unifier.rigid_vars.truncate(rigid_vars_len);
+        result
+    }
+
+    fn zonk(&mut self, unifier: &Unifier<N, T>) {
+        // The solutions of meta variables can't refer to the binders of the
+        // scope, so they can be substituted without opening it
+        self.unsafe_pattern.zonk(unifier);
+        self.unsafe_body.zonk(unifier);
+    }
+
+    fn visit_meta_vars(&self, on_meta_var: &mut impl FnMut(&MetaVar<N>)) {
+        self.unsafe_pattern.visit_meta_vars(on_meta_var);
+        self.unsafe_body.visit_meta_vars(on_meta_var);
+    }
+
+    fn visit_mut_subterms(&mut self, on_subterm: &mut impl FnMut(&mut T)) {
+        let (mut pattern, mut body) = self.clone().unbind();
+        pattern.visit_mut_subterms(on_subterm);
+        body.visit_mut_subterms(on_subterm);
+        *self = Scope::new(pattern, body);
+    }
+}
+
+impl<P: Node, T: Node> Node for Scope<P, T> {
+    fn node_name(&self) -> &'static str {
+        "Scope"
+    }
+
+    fn children(&self) -> Vec<&dyn Node> {
+        vec![&self.unsafe_pattern, &self.unsafe_body]
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut dyn Node> {
+        vec![&mut self.unsafe_pattern, &mut self.unsafe_body]
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl<P: PatternEqStrict, T: TermEqStrict> TermEqStrict for Scope<P, T> {
+    fn term_eq_strict(&self, other: &Scope<P, T>) -> bool {
+        P::pattern_eq_strict(&self.unsafe_pattern, &other.unsafe_pattern)
+            && T::term_eq_strict(&self.unsafe_body, &other.unsafe_body)
+    }
 }
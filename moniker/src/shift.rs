@@ -0,0 +1,117 @@
+use std::any::Any;
+use std::fmt;
+
+use binder::Binder;
+use bound::{BoundPattern, ScopeState};
+use diff::TermDiff;
+use meta_var::MetaVar;
+use node::Node;
+use strict::PatternEqStrict;
+use unify::{Unifier, Unify, UnifyError};
+use walk::{PatternTerms, Walker, WalkerMut};
+
+/// Shift the terms embedded in a pattern so that they skip the innermost
+/// frame of binders
+///
+/// This corresponds to the `Shift` combinator from Unbound. For example, in
+/// the pattern `Rec<(Binder<String>, Shift<Embed<RcExpr>>)>` the embedded
+/// expression can't refer to the recursive binder, so it can refer to a binder
+/// with the same name from an enclosing scope instead.
+///
+/// Note that the innermost frame of binders for a pattern in a `Nest` is the
+/// frame of the first pattern in the `Nest`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shift<P>(pub P);
+
+impl<N, P> BoundPattern<N> for Shift<P>
+where
+    P: BoundPattern<N>,
+{
+    fn pattern_eq(&self, other: &Shift<P>) -> bool {
+        P::pattern_eq(&self.0, &other.0)
+    }
+
+    fn pattern_diff(&self, other: &Shift<P>) -> Option<TermDiff>
+    where
+        N: fmt::Display,
+    {
+        P::pattern_diff(&self.0, &other.0)
+    }
+
+    fn close_pattern(&mut self, state: ScopeState, binders: &[Binder<N>]) {
+        self.0.close_pattern(state.shift(), binders);
+    }
+
+    fn open_pattern(&mut self, state: ScopeState, binders: &[Binder<N>]) {
+        self.0.open_pattern(state.shift(), binders);
+    }
+
+    fn visit_binders(&self, on_binder: &mut impl FnMut(&Binder<N>)) {
+        self.0.visit_binders(on_binder);
+    }
+
+    fn visit_mut_binders(&mut self, on_binder: &mut impl FnMut(&mut Binder<N>)) {
+        self.0.visit_mut_binders(on_binder);
+    }
+
+    fn walk_pattern(&self, terms: PatternTerms, walker: &mut impl Walker<N>) {
+        walker.enter_shift();
+        self.0.walk_pattern(terms, walker);
+        walker.exit_shift();
+    }
+
+    fn walk_mut_pattern(&mut self, terms: PatternTerms, walker: &mut impl WalkerMut<N>) {
+        walker.enter_shift();
+        self.0.walk_mut_pattern(terms, walker);
+        walker.exit_shift();
+    }
+}
+
+impl<N, T, P> Unify<N, T> for Shift<P>
+where
+    P: Unify<N, T>,
+{
+    fn unify(&self, other: &Shift<P>, unifier: &mut Unifier<N, T>) -> Result<(), UnifyError<N>> {
+        P::unify(&self.0, &other.0, unifier)
+    }
+
+    fn zonk(&mut self, unifier: &Unifier<N, T>) {
+        P::zonk(&mut self.0, unifier);
+    }
+
+    fn visit_meta_vars(&self, on_meta_var: &mut impl FnMut(&MetaVar<N>)) {
+        P::visit_meta_vars(&self.0, on_meta_var);
+    }
+
+    fn visit_mut_subterms(&mut self, on_subterm: &mut impl FnMut(&mut T)) {
+        P::visit_mut_subterms(&mut self.0, on_subterm);
+    }
+}
+
+impl<P: Node> Node for Shift<P> {
+    fn node_name(&self) -> &'static str {
+        "Shift"
+    }
+
+    fn children(&self) -> Vec<&dyn Node> {
+        vec![&self.0]
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut dyn Node> {
+        vec![&mut self.0]
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl<P: PatternEqStrict> PatternEqStrict for Shift<P> {
+    fn pattern_eq_strict(&self, other: &Shift<P>) -> bool {
+        P::pattern_eq_strict(&self.0, &other.0)
+    }
+}
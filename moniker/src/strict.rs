@@ -0,0 +1,146 @@
+use std::rc::Rc;
+use std::sync::Arc;
+
+use binder::Binder;
+use free_var::FreeVar;
+use var::Var;
+
+/// Strict syntactic equality for terms
+///
+/// Unlike `BoundTerm::term_eq`, this also compares the things that are ignored
+/// by alpha equivalence: the pretty names of binders and bound variables, the
+/// payloads of `Ignore`, and the fields that are skipped with
+/// `#[moniker(ignore)]` or `#[moniker(eq = "skip")]`. This is useful for
+/// checking that names and source locations survive a round trip through a
+/// parser or a pretty printer.
+pub trait TermEqStrict {
+    /// Strict syntactic equality for terms
+    fn term_eq_strict(&self, other: &Self) -> bool;
+}
+
+/// Strict syntactic equality for patterns
+///
+/// Binders are compared by their pretty names, positionally. See
+/// `TermEqStrict` for more details.
+pub trait PatternEqStrict {
+    /// Strict syntactic equality for patterns
+    fn pattern_eq_strict(&self, other: &Self) -> bool;
+}
+
+impl<N: PartialEq> TermEqStrict for FreeVar<N> {
+    fn term_eq_strict(&self, other: &FreeVar<N>) -> bool {
+        self == other
+    }
+}
+
+impl<N: PartialEq> TermEqStrict for Var<N> {
+    fn term_eq_strict(&self, other: &Var<N>) -> bool {
+        match *self {
+            Var::Free(_) => self == other,
+            Var::Bound(ref lhs) => match *other {
+                Var::Bound(ref rhs) => lhs == rhs && lhs.pretty_name == rhs.pretty_name,
+                Var::Free(_) => false,
+            },
+        }
+    }
+}
+
+impl<N: PartialEq> PatternEqStrict for Binder<N> {
+    fn pattern_eq_strict(&self, other: &Binder<N>) -> bool {
+        self.0.pretty_name == other.0.pretty_name
+    }
+}
+
+macro_rules! impl_eq_strict_partial_eq {
+    ($T:ty) => {
+        impl TermEqStrict for $T {
+            fn term_eq_strict(&self, other: &$T) -> bool {
+                self == other
+            }
+        }
+
+        impl PatternEqStrict for $T {
+            fn pattern_eq_strict(&self, other: &$T) -> bool {
+                self == other
+            }
+        }
+    };
+}
+
+impl_eq_strict_partial_eq!(());
+impl_eq_strict_partial_eq!(String);
+impl_eq_strict_partial_eq!(str);
+impl_eq_strict_partial_eq!(char);
+impl_eq_strict_partial_eq!(bool);
+impl_eq_strict_partial_eq!(u8);
+impl_eq_strict_partial_eq!(u16);
+impl_eq_strict_partial_eq!(u32);
+impl_eq_strict_partial_eq!(u64);
+impl_eq_strict_partial_eq!(usize);
+impl_eq_strict_partial_eq!(i8);
+impl_eq_strict_partial_eq!(i16);
+impl_eq_strict_partial_eq!(i32);
+impl_eq_strict_partial_eq!(i64);
+impl_eq_strict_partial_eq!(isize);
+impl_eq_strict_partial_eq!(f32);
+impl_eq_strict_partial_eq!(f64);
+
+macro_rules! impl_eq_strict_containers {
+    ($Trait:ident, $eq_strict:ident) => {
+        impl<T: $Trait> $Trait for Option<T> {
+            fn $eq_strict(&self, other: &Option<T>) -> bool {
+                match (self, other) {
+                    (&Some(ref lhs), &Some(ref rhs)) => T::$eq_strict(lhs, rhs),
+                    (&None, &None) => true,
+                    (_, _) => false,
+                }
+            }
+        }
+
+        impl<T: $Trait + ?Sized> $Trait for Box<T> {
+            fn $eq_strict(&self, other: &Box<T>) -> bool {
+                T::$eq_strict(self, other)
+            }
+        }
+
+        impl<T: $Trait + ?Sized> $Trait for Rc<T> {
+            fn $eq_strict(&self, other: &Rc<T>) -> bool {
+                T::$eq_strict(self, other)
+            }
+        }
+
+        impl<T: $Trait + ?Sized> $Trait for Arc<T> {
+            fn $eq_strict(&self, other: &Arc<T>) -> bool {
+                T::$eq_strict(self, other)
+            }
+        }
+
+        impl_eq_strict_containers!(@tuple $Trait, $eq_strict, T1: 0, T2: 1);
+        impl_eq_strict_containers!(@tuple $Trait, $eq_strict, T1: 0, T2: 1, T3: 2);
+        impl_eq_strict_containers!(@tuple $Trait, $eq_strict, T1: 0, T2: 1, T3: 2, T4: 3);
+        impl_eq_strict_containers!(@tuple $Trait, $eq_strict, T1: 0, T2: 1, T3: 2, T4: 3, T5: 4);
+
+        impl<T: $Trait> $Trait for [T] {
+            fn $eq_strict(&self, other: &[T]) -> bool {
+                self.len() == other.len()
+                    && <_>::zip(self.iter(), other.iter()).all(|(lhs, rhs)| T::$eq_strict(lhs, rhs))
+            }
+        }
+
+        impl<T: $Trait> $Trait for Vec<T> {
+            fn $eq_strict(&self, other: &Vec<T>) -> bool {
+                <[T]>::$eq_strict(self, other)
+            }
+        }
+    };
+    (@tuple $Trait:ident, $eq_strict:ident, $($T:ident: $index:tt),+) => {
+        impl<$($T: $Trait),+> $Trait for ($($T,)+) {
+            fn $eq_strict(&self, other: &($($T,)+)) -> bool {
+                true $(&& $T::$eq_strict(&self.$index, &other.$index))+
+            }
+        }
+    };
+}
+
+impl_eq_strict_containers!(TermEqStrict, term_eq_strict);
+impl_eq_strict_containers!(PatternEqStrict, pattern_eq_strict);
@@ -0,0 +1,540 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::hash::Hash;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use binder::Binder;
+use bound::BoundTerm;
+use free_var::FreeVar;
+use meta_var::MetaVar;
+use var::Var;
+
+/// Terms that might be meta variables
+///
+/// This should be implemented by hand for the type that contains the
+/// `MetaVar`s of an AST, for example the type of types in a type checker. The
+/// `Unify` impl of that type should then use `Unifier::unify_meta_vars` and
+/// `Unifier::zonk_meta_var` before falling back to the structural impl, and
+/// should pass itself to the callback of `Unify::visit_mut_subterms`.
+pub trait MetaTerm<N> {
+    /// Return the meta variable if this term is one
+    fn as_meta_var(&self) -> Option<&MetaVar<N>>;
+
+    /// Return the free variable if this term is one
+    ///
+    /// This is used by `subst` to find the variables to replace.
+    fn as_free_var(&self) -> Option<&FreeVar<N>>;
+
+    /// Visit each of the outermost terms of the same type as this term that
+    /// are contained inside it, opening scopes along the way
+    ///
+    /// This is usually implemented by calling `Unify::visit_mut_subterms` on
+    /// the contents of the term.
+    fn visit_mut_children(&mut self, on_child: &mut impl FnMut(&mut Self));
+}
+
+/// Terms and patterns that can be unified, solving the meta variables of type
+/// `T` that they contain
+///
+/// Scopes are unified by simultaneously unbinding them with `Scope::unbind2`,
+/// so unification is modulo alpha equivalence.
+pub trait Unify<N, T> {
+    /// Unify `self` with `other`, recording the solved meta variables in the
+    /// unifier
+    fn unify(&self, other: &Self, unifier: &mut Unifier<N, T>) -> Result<(), UnifyError<N>>;
+
+    /// Replace the solved meta variables in `self` with their solutions
+    fn zonk(&mut self, unifier: &Unifier<N, T>);
+
+    /// Visit each of the meta variables in `self`
+    fn visit_meta_vars(&self, on_meta_var: &mut impl FnMut(&MetaVar<N>));
+
+    /// Visit each of the outermost subterms of type `T` in `self`
+    ///
+    /// Scopes are unbound before their contents are visited, and are closed
+    /// again afterwards, so the subterms never contain dangling bound
+    /// variables and can be freely replaced.
+    fn visit_mut_subterms(&mut self, on_subterm: &mut impl FnMut(&mut T));
+
+    /// Returns the meta variables in `self`, in the order that they are visited
+    fn meta_vars(&self) -> Vec<MetaVar<N>>
+    where
+        N: Clone,
+    {
+        let mut meta_vars = Vec::new();
+        self.visit_meta_vars(&mut |meta_var| meta_vars.push(meta_var.clone()));
+        meta_vars
+    }
+}
+
+/// The state of a unification problem
+///
+/// This records the solutions of the meta variables of type `T`, along with
+/// the free variables of the binders that have been opened while unifying
+/// under scopes. These are rigid: meta variables can't be solved to terms that
+/// refer to them, because they would escape their scope.
+///
+/// A one-way unifier only solves the meta variables on the left hand side,
+/// treating those on the right hand side as rigid. This is used for matching
+/// patterns against terms.
+#[derive(Debug, Clone)]
+pub struct Unifier<N, T> {
+    subst: HashMap<MetaVar<N>, T>,
+    pub(crate) rigid_vars: Vec<FreeVar<N>>,
+    one_way: bool,
+}
+
+impl<N, T> Unifier<N, T>
+where
+    N: Clone + Eq + Hash,
+    T: Clone + MetaTerm<N> + BoundTerm<N> + Unify<N, T>,
+{
+    /// Create a unifier with no solved meta variables
+    pub fn new() -> Unifier<N, T> {
+        Unifier {
+            subst: HashMap::new(),
+            rigid_vars: Vec::new(),
+            one_way: false,
+        }
+    }
+
+    /// Create a one-way unifier with no solved meta variables
+    pub fn new_one_way() -> Unifier<N, T> {
+        Unifier {
+            one_way: true,
+            ..Unifier::new()
+        }
+    }
+
+    /// Create a unifier with the given meta variables already solved
+    pub fn from_subst(subst: HashMap<MetaVar<N>, T>) -> Unifier<N, T> {
+        Unifier {
+            subst,
+            ..Unifier::new()
+        }
+    }
+
+    /// The solutions of the meta variables that have been solved so far
+    ///
+    /// The solutions are not zonked, so they might refer to other solved meta
+    /// variables.
+    pub fn subst(&self) -> &HashMap<MetaVar<N>, T> {
+        &self.subst
+    }
+
+    /// Return the solutions of the meta variables
+    pub fn into_subst(self) -> HashMap<MetaVar<N>, T> {
+        self.subst
+    }
+
+    /// The free variables of the binders that are currently being unified
+    /// under, from outermost to innermost
+    pub fn rigid_vars(&self) -> &[FreeVar<N>] {
+        &self.rigid_vars
+    }
+
+    /// Unify two terms
+    pub fn unify<U>(&mut self, lhs: &U, rhs: &U) -> Result<(), UnifyError<N>>
+    where
+        U: Unify<N, T>,
+    {
+        lhs.unify(rhs, self)
+    }
+
+    /// Replace the solved meta variables in a term with their solutions
+    pub fn zonk<U>(&self, mut term: U) -> U
+    where
+        U: Unify<N, T>,
+    {
+        term.zonk(self);
+        term
+    }
+
+    /// Unify two terms if either of them are meta variables
+    ///
+    /// Returns `None` if neither term is a meta variable, in which case the
+    /// terms should be unified structurally.
+    pub fn unify_meta_vars(&mut self, lhs: &T, rhs: &T) -> Option<Result<(), UnifyError<N>>> {
+        if self.one_way {
+            let lhs_mv = lhs.as_meta_var()?;
+            // Meta variables that occur more than once on the left hand side
+            // must be matched against alpha equivalent terms
+            return Some(match self.subst.get(lhs_mv) {
+                Some(solution) if solution.term_eq(rhs) => Ok(()),
+                Some(_) => Err(UnifyError::Mismatch),
+                None => self.solve(lhs_mv, rhs),
+            });
+        }
+
+        let lhs_solution = lhs.as_meta_var().and_then(|mv| self.subst.get(mv)).cloned();
+        let rhs_solution = rhs.as_meta_var().and_then(|mv| self.subst.get(mv)).cloned();
+
+        match (lhs.as_meta_var(), rhs.as_meta_var()) {
+            (Some(lhs_mv), Some(rhs_mv)) if lhs_mv == rhs_mv => Some(Ok(())),
+            _ if lhs_solution.is_some() || rhs_solution.is_some() => {
+                let lhs = lhs_solution.as_ref().unwrap_or(lhs);
+                let rhs = rhs_solution.as_ref().unwrap_or(rhs);
+                Some(lhs.unify(rhs, self))
+            },
+            (Some(lhs_mv), _) => Some(self.solve(lhs_mv, rhs)),
+            (_, Some(rhs_mv)) => Some(self.solve(rhs_mv, lhs)),
+            (None, None) => None,
+        }
+    }
+
+    /// Replace a term with the solution of its meta variable, if it is a
+    /// solved meta variable
+    ///
+    /// Returns `false` if the term was left untouched, in which case it should
+    /// be zonked structurally.
+    pub fn zonk_meta_var(&self, term: &mut T) -> bool {
+        let solution = match term.as_meta_var().and_then(|mv| self.subst.get(mv)) {
+            Some(solution) => solution.clone(),
+            None => return false,
+        };
+
+        *term = solution;
+        term.zonk(self);
+        true
+    }
+
+    /// Solve an unsolved meta variable, checking that the solution does not
+    /// refer to the meta variable or to any rigid variables
+    fn solve(&mut self, meta_var: &MetaVar<N>, term: &T) -> Result<(), UnifyError<N>> {
+        // The right hand side of a one-way unification problem can't contain
+        // any solved meta variables
+        let term = if self.one_way {
+            term.clone()
+        } else {
+            self.zonk(term.clone())
+        };
+
+        if term.as_meta_var() == Some(meta_var) {
+            return Ok(());
+        }
+        if term.meta_vars().contains(meta_var) {
+            return Err(UnifyError::Occurs(meta_var.clone()));
+        }
+        let free_vars = term.free_vars();
+        if let Some(free_var) = self.rigid_vars.iter().find(|fv| free_vars.contains(fv)) {
+            return Err(UnifyError::Escape {
+                meta_var: meta_var.clone(),
+                free_var: free_var.clone(),
+            });
+        }
+
+        self.subst.insert(meta_var.clone(), term);
+        Ok(())
+    }
+}
+
+impl<N, T> Default for Unifier<N, T>
+where
+    N: Clone + Eq + Hash,
+    T: Clone + MetaTerm<N> + BoundTerm<N> + Unify<N, T>,
+{
+    fn default() -> Unifier<N, T> {
+        Unifier::new()
+    }
+}
+
+/// An error that is returned when two terms could not be unified
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnifyError<N> {
+    /// The terms have different structures
+    Mismatch,
+    /// The solution of a meta variable would refer to the meta variable itself
+    Occurs(MetaVar<N>),
+    /// The solution of a meta variable would refer to a variable that was
+    /// bound in a scope that the meta variable is not in
+    Escape {
+        meta_var: MetaVar<N>,
+        free_var: FreeVar<N>,
+    },
+}
+
+impl<N: fmt::Display> fmt::Display for UnifyError<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            UnifyError::Mismatch => write!(f, "mismatched terms"),
+            UnifyError::Occurs(ref meta_var) => {
+                write!(f, "`{}` occurs in its own solution", meta_var)
+            },
+            UnifyError::Escape {
+                ref meta_var,
+                ref free_var,
+            } => write!(
+                f,
+                "the solution of `{}` would refer to `{}` outside of its scope",
+                meta_var, free_var,
+            ),
+        }
+    }
+}
+
+impl<N: fmt::Debug + fmt::Display> Error for UnifyError<N> {}
+
+impl<N: PartialEq, T> Unify<N, T> for FreeVar<N> {
+    fn unify(&self, other: &FreeVar<N>, _: &mut Unifier<N, T>) -> Result<(), UnifyError<N>> {
+        if self == other {
+            Ok(())
+        } else {
+            Err(UnifyError::Mismatch)
+        }
+    }
+
+    fn zonk(&mut self, _: &Unifier<N, T>) {}
+
+    fn visit_meta_vars(&self, _: &mut impl FnMut(&MetaVar<N>)) {}
+
+    fn visit_mut_subterms(&mut self, _: &mut impl FnMut(&mut T)) {}
+}
+
+impl<N: PartialEq + Clone, T> Unify<N, T> for Var<N> {
+    fn unify(&self, other: &Var<N>, _: &mut Unifier<N, T>) -> Result<(), UnifyError<N>> {
+        if self.term_eq(other) {
+            Ok(())
+        } else {
+            Err(UnifyError::Mismatch)
+        }
+    }
+
+    fn zonk(&mut self, _: &Unifier<N, T>) {}
+
+    fn visit_meta_vars(&self, _: &mut impl FnMut(&MetaVar<N>)) {}
+
+    fn visit_mut_subterms(&mut self, _: &mut impl FnMut(&mut T)) {}
+}
+
+impl<N, T> Unify<N, T> for Binder<N> {
+    fn unify(&self, _: &Binder<N>, _: &mut Unifier<N, T>) -> Result<(), UnifyError<N>> {
+        // Binders are identified positionally by `Scope::unbind2`
+        Ok(())
+    }
+
+    fn zonk(&mut self, _: &Unifier<N, T>) {}
+
+    fn visit_meta_vars(&self, _: &mut impl FnMut(&MetaVar<N>)) {}
+
+    fn visit_mut_subterms(&mut self, _: &mut impl FnMut(&mut T)) {}
+}
+
+macro_rules! impl_unify_partial_eq {
+    ($T:ty) => {
+        impl<N, T> Unify<N, T> for $T {
+            fn unify(&self, other: &$T, _: &mut Unifier<N, T>) -> Result<(), UnifyError<N>> {
+                if self == other {
+                    Ok(())
+                } else {
+                    Err(UnifyError::Mismatch)
+                }
+            }
+
+            fn zonk(&mut self, _: &Unifier<N, T>) {}
+
+            fn visit_meta_vars(&self, _: &mut impl FnMut(&MetaVar<N>)) {}
+
+            fn visit_mut_subterms(&mut self, _: &mut impl FnMut(&mut T)) {}
+        }
+    };
+}
+
+impl_unify_partial_eq!(());
+impl_unify_partial_eq!(String);
+impl_unify_partial_eq!(str);
+impl_unify_partial_eq!(char);
+impl_unify_partial_eq!(bool);
+impl_unify_partial_eq!(u8);
+impl_unify_partial_eq!(u16);
+impl_unify_partial_eq!(u32);
+impl_unify_partial_eq!(u64);
+impl_unify_partial_eq!(usize);
+impl_unify_partial_eq!(i8);
+impl_unify_partial_eq!(i16);
+impl_unify_partial_eq!(i32);
+impl_unify_partial_eq!(i64);
+impl_unify_partial_eq!(isize);
+impl_unify_partial_eq!(f32);
+impl_unify_partial_eq!(f64);
+
+impl<N, T, U> Unify<N, T> for Option<U>
+where
+    U: Unify<N, T>,
+{
+    fn unify(&self, other: &Option<U>, unifier: &mut Unifier<N, T>) -> Result<(), UnifyError<N>> {
+        match (self.as_ref(), other.as_ref()) {
+            (Some(lhs), Some(rhs)) => lhs.unify(rhs, unifier),
+            (None, None) => Ok(()),
+            (_, _) => Err(UnifyError::Mismatch),
+        }
+    }
+
+    fn zonk(&mut self, unifier: &Unifier<N, T>) {
+        if let Some(ref mut inner) = *self {
+            inner.zonk(unifier);
+        }
+    }
+
+    fn visit_meta_vars(&self, on_meta_var: &mut impl FnMut(&MetaVar<N>)) {
+        if let Some(ref inner) = *self {
+            inner.visit_meta_vars(on_meta_var);
+        }
+    }
+
+    fn visit_mut_subterms(&mut self, on_subterm: &mut impl FnMut(&mut T)) {
+        if let Some(ref mut inner) = *self {
+            inner.visit_mut_subterms(on_subterm);
+        }
+    }
+}
+
+impl<N, T, U> Unify<N, T> for Box<U>
+where
+    U: Unify<N, T> + ?Sized,
+{
+    fn unify(&self, other: &Box<U>, unifier: &mut Unifier<N, T>) -> Result<(), UnifyError<N>> {
+        U::unify(self, other, unifier)
+    }
+
+    fn zonk(&mut self, unifier: &Unifier<N, T>) {
+        U::zonk(self, unifier);
+    }
+
+    fn visit_meta_vars(&self, on_meta_var: &mut impl FnMut(&MetaVar<N>)) {
+        U::visit_meta_vars(self, on_meta_var);
+    }
+    fn visit_mut_subterms(&mut self, on_subterm: &mut impl FnMut(&mut T)) {
+        U::visit_mut_subterms(self, on_subterm);
+    }
+}
+
+impl<N, T, U> Unify<N, T> for Rc<U>
+where
+    U: Unify<N, T> + Clone,
+{
+    fn unify(&self, other: &Rc<U>, unifier: &mut Unifier<N, T>) -> Result<(), UnifyError<N>> {
+        U::unify(self, other, unifier)
+    }
+
+    fn zonk(&mut self, unifier: &Unifier<N, T>) {
+        U::zonk(Rc::make_mut(self), unifier);
+    }
+
+    fn visit_meta_vars(&self, on_meta_var: &mut impl FnMut(&MetaVar<N>)) {
+        U::visit_meta_vars(self, on_meta_var);
+    }
+    fn visit_mut_subterms(&mut self, on_subterm: &mut impl FnMut(&mut T)) {
+        U::visit_mut_subterms(Rc::make_mut(self), on_subterm);
+    }
+}
+
+impl<N, T, U> Unify<N, T> for Arc<U>
+where
+    U: Unify<N, T> + Clone,
+{
+    fn unify(&self, other: &Arc<U>, unifier: &mut Unifier<N, T>) -> Result<(), UnifyError<N>> {
+        U::unify(self, other, unifier)
+    }
+
+    fn zonk(&mut self, unifier: &Unifier<N, T>) {
+        U::zonk(Arc::make_mut(self), unifier);
+    }
+
+    fn visit_meta_vars(&self, on_meta_var: &mut impl FnMut(&MetaVar<N>)) {
+        U::visit_meta_vars(self, on_meta_var);
+    }
+    fn visit_mut_subterms(&mut self, on_subterm: &mut impl FnMut(&mut T)) {
+        U::visit_mut_subterms(Arc::make_mut(self), on_subterm);
+    }
+}
+
+macro_rules! impl_unify_tuple {
+    ($($U:ident: $index:tt),+) => {
+        impl<N, T, $($U),+> Unify<N, T> for ($($U,)+)
+        where
+            $($U: Unify<N, T>,)+
+        {
+            fn unify(
+                &self,
+                other: &($($U,)+),
+                unifier: &mut Unifier<N, T>,
+            ) -> Result<(), UnifyError<N>> {
+                $(self.$index.unify(&other.$index, unifier)?;)+
+                Ok(())
+            }
+
+            fn zonk(&mut self, unifier: &Unifier<N, T>) {
+                $(self.$index.zonk(unifier);)+
+            }
+
+            fn visit_meta_vars(&self, on_meta_var: &mut impl FnMut(&MetaVar<N>)) {
+                $(self.$index.visit_meta_vars(on_meta_var);)+
+            }
+
+            fn visit_mut_subterms(&mut self, on_subterm: &mut impl FnMut(&mut T)) {
+                $(self.$index.visit_mut_subterms(on_subterm);)+
+            }
+        }
+    };
+}
+
+impl_unify_tuple!(U1: 0, U2: 1);
+impl_unify_tuple!(U1: 0, U2: 1, U3: 2);
+impl_unify_tuple!(U1: 0, U2: 1, U3: 2, U4: 3);
+impl_unify_tuple!(U1: 0, U2: 1, U3: 2, U4: 3, U5: 4);
+
+impl<N, T, U> Unify<N, T> for [U]
+where
+    U: Unify<N, T>,
+{
+    fn unify(&self, other: &[U], unifier: &mut Unifier<N, T>) -> Result<(), UnifyError<N>> {
+        if self.len() != other.len() {
+            return Err(UnifyError::Mismatch);
+        }
+        for (lhs, rhs) in <_>::zip(self.iter(), other.iter()) {
+            lhs.unify(rhs, unifier)?;
+        }
+        Ok(())
+    }
+
+    fn zonk(&mut self, unifier: &Unifier<N, T>) {
+        for elem in self {
+            elem.zonk(unifier);
+        }
+    }
+
+    fn visit_meta_vars(&self, on_meta_var: &mut impl FnMut(&MetaVar<N>)) {
+        for elem in self {
+            elem.visit_meta_vars(on_meta_var);
+        }
+    }
+
+    fn visit_mut_subterms(&mut self, on_subterm: &mut impl FnMut(&mut T)) {
+        for elem in self {
+            elem.visit_mut_subterms(on_subterm);
+        }
+    }
+}
+
+impl<N, T, U> Unify<N, T> for Vec<U>
+where
+    U: Unify<N, T>,
+{
+    fn unify(&self, other: &Vec<U>, unifier: &mut Unifier<N, T>) -> Result<(), UnifyError<N>> {
+        <[U]>::unify(self, other, unifier)
+    }
+
+    fn zonk(&mut self, unifier: &Unifier<N, T>) {
+        <[U]>::zonk(self, unifier);
+    }
+
+    fn visit_meta_vars(&self, on_meta_var: &mut impl FnMut(&MetaVar<N>)) {
+        <[U]>::visit_meta_vars(self, on_meta_var);
+    }
+
+    fn visit_mut_subterms(&mut self, on_subterm: &mut impl FnMut(&mut T)) {
+        <[U]>::visit_mut_subterms(self, on_subterm);
+    }
+}
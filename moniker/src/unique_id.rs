@@ -4,8 +4,15 @@ use std::fmt;
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct UniqueId(u32);
 
+/// The ids from this point onwards are reserved for `UniqueId::canonical`
+const CANONICAL_START: u32 = 1 << 31;
+
 impl UniqueId {
     /// Generate a new, globally unique id
+    ///
+    /// # Panics
+    ///
+    /// Panics if the ids below the canonical ids have run out.
     pub fn new() -> UniqueId {
         use std::sync::atomic::{AtomicUsize, Ordering};
 
@@ -13,13 +20,37 @@ impl UniqueId {
             static ref NEXT_ID: AtomicUsize = AtomicUsize::new(0);
         }
 
-        // FIXME: check for integer overflow
-        UniqueId(NEXT_ID.fetch_add(1, Ordering::SeqCst) as u32)
+        let id = NEXT_ID.fetch_add(1, Ordering::SeqCst);
+        assert!(id < CANONICAL_START as usize, "ran out of unique ids");
+        UniqueId(id as u32)
+    }
+
+    /// The canonical id with the given index
+    ///
+    /// Canonical ids are never generated by `UniqueId::new`, so they don't
+    /// clash with the ids of fresh variables. They are used by `canonicalize`
+    /// to give variables ids that don't depend on the order in which fresh
+    /// variables happened to be generated.
+    pub fn canonical(index: u32) -> UniqueId {
+        assert!(index < CANONICAL_START, "canonical index out of range");
+        UniqueId(CANONICAL_START + index)
+    }
+
+    /// Returns the index of the id if it is a canonical id
+    pub fn canonical_index(self) -> Option<u32> {
+        if self.0 >= CANONICAL_START {
+            Some(self.0 - CANONICAL_START)
+        } else {
+            None
+        }
     }
 }
 
 impl fmt::Display for UniqueId {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fmt::Display::fmt(&self.0, f)
+        match self.canonical_index() {
+            None => fmt::Display::fmt(&self.0, f),
+            Some(index) => write!(f, "c{}", index),
+        }
     }
 }
@@ -0,0 +1,243 @@
+use std::any::Any;
+use std::fmt;
+
+use binder::Binder;
+use bound::{BoundPattern, ScopeState};
+use diff::TermDiff;
+use meta_var::MetaVar;
+use node::Node;
+use strict::PatternEqStrict;
+use unify::{Unifier, Unify, UnifyError};
+use walk::{PatternTerms, Walker, WalkerMut};
+
+/// A group of binding patterns, where the order of the patterns is not
+/// significant
+///
+/// This is useful for things like the definitions of a `let rec`, or the
+/// fields of a record pattern, where `f = ..; g = ..` and `g = ..; f = ..`
+/// should be considered to be alpha equivalent.
+///
+/// The patterns are kept in the order that they were given. When a scope is
+/// compared for alpha equivalence, it searches for an order of the patterns
+/// in the other scope's group that makes the groups alpha equivalent, and
+/// compares the bodies with their bound variables renumbered to follow that
+/// order. Each permutation of the patterns that binds the same number of
+/// variables in each position is tried in turn, so comparing large groups
+/// that are not alpha equivalent can be slow.
+///
+/// The bound variables that refer to the group are renumbered by the scope
+/// (or the `Rec`) that binds them, so the search is only done when the group
+/// is the pattern of a `Scope`, or of a `Rec` that is the pattern of a
+/// `Scope`. Comparing groups on their own with `pattern_eq` compares the
+/// patterns in order.
+///
+/// Contrast with `Multi`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unordered<P> {
+    pub unsafe_patterns: Vec<P>,
+}
+
+impl<P> Unordered<P> {
+    /// Group the given patterns
+    pub fn new(patterns: Vec<P>) -> Unordered<P> {
+        Unordered {
+            unsafe_patterns: patterns,
+        }
+    }
+
+    /// The patterns, in the order that they were given
+    pub fn patterns(&self) -> &[P] {
+        &self.unsafe_patterns
+    }
+
+    /// Return the patterns, in the order that they were given
+    pub fn into_patterns(self) -> Vec<P> {
+        self.unsafe_patterns
+    }
+}
+
+/// Try each order of the patterns of the other group that lines them up with
+/// patterns that bind the same number of variables, stopping once `on_order`
+/// returns `true`
+///
+/// `order` holds the positions in the other group of the patterns that have
+/// been lined up so far.
+fn try_orders(
+    binders_lens: &[usize],
+    other_binders_lens: &[usize],
+    order: &mut Vec<usize>,
+    on_order: &mut dyn FnMut(&[usize]) -> bool,
+) -> bool {
+    let index = order.len();
+    if index == binders_lens.len() {
+        return on_order(order);
+    }
+
+    for other_index in 0..other_binders_lens.len() {
+        if other_binders_lens[other_index] == binders_lens[index] && !order.contains(&other_index) {
+            order.push(other_index);
+            if try_orders(binders_lens, other_binders_lens, order, on_order) {
+                return true;
+            }
+            order.pop();
+        }
+    }
+
+    false
+}
+
+impl<N, P> BoundPattern<N> for Unordered<P>
+where
+    N: Clone,
+    P: Clone + BoundPattern<N>,
+{
+    fn pattern_eq(&self, other: &Unordered<P>) -> bool {
+        <[P]>::pattern_eq(&self.unsafe_patterns, &other.unsafe_patterns)
+    }
+
+    fn pattern_diff(&self, other: &Unordered<P>) -> Option<TermDiff>
+    where
+        N: fmt::Display,
+    {
+        <[P]>::pattern_diff(&self.unsafe_patterns, &other.unsafe_patterns)
+    }
+
+    fn close_pattern(&mut self, state: ScopeState, binders: &[Binder<N>]) {
+        <[P]>::close_pattern(&mut self.unsafe_patterns, state, binders);
+    }
+
+    fn open_pattern(&mut self, state: ScopeState, binders: &[Binder<N>]) {
+        <[P]>::open_pattern(&mut self.unsafe_patterns, state, binders);
+    }
+
+    fn visit_binders(&self, on_binder: &mut impl FnMut(&Binder<N>)) {
+        <[P]>::visit_binders(&self.unsafe_patterns, on_binder);
+    }
+
+    fn visit_mut_binders(&mut self, on_binder: &mut impl FnMut(&mut Binder<N>)) {
+        <[P]>::visit_mut_binders(&mut self.unsafe_patterns, on_binder);
+    }
+
+    fn walk_pattern(&self, terms: PatternTerms, walker: &mut impl Walker<N>) {
+        <[P]>::walk_pattern(&self.unsafe_patterns, terms, walker);
+    }
+
+    fn walk_mut_pattern(&mut self, terms: PatternTerms, walker: &mut impl WalkerMut<N>) {
+        <[P]>::walk_mut_pattern(&mut self.unsafe_patterns, terms, walker);
+    }
+
+    fn try_reorderings(
+        &self,
+        other: &Unordered<P>,
+        on_reordering: &mut dyn FnMut(&Unordered<P>, &[usize]) -> bool,
+    ) -> bool {
+        if self.unsafe_patterns.len() != other.unsafe_patterns.len() {
+            return false;
+        }
+
+        let binders_len = |pattern: &P| {
+            let mut binders_len = 0;
+            pattern.visit_binders(&mut |_| binders_len += 1);
+            binders_len
+        };
+        let binders_lens = self
+            .unsafe_patterns
+            .iter()
+            .map(binders_len)
+            .collect::<Vec<_>>();
+        let other_binders_lens = other
+            .unsafe_patterns
+            .iter()
+            .map(binders_len)
+            .collect::<Vec<_>>();
+        let other_offsets = other_binders_lens
+            .iter()
+            .scan(0, |offset, &binders_len| {
+                let start = *offset;
+                *offset += binders_len;
+                Some(start)
+            })
+            .collect::<Vec<_>>();
+
+        try_orders(
+            &binders_lens,
+            &other_binders_lens,
+            &mut Vec::new(),
+            &mut |order| {
+                let reordered = Unordered {
+                    unsafe_patterns: order
+                        .iter()
+                        .map(|&index| other.unsafe_patterns[index].clone())
+                        .collect(),
+                };
+                let positions = order
+                    .iter()
+                    .flat_map(|&index| {
+                        other_offsets[index]..other_offsets[index] + other_binders_lens[index]
+                    })
+                    .collect::<Vec<_>>();
+
+                on_reordering(&reordered, &positions)
+            },
+        )
+    }
+}
+
+impl<N, T, P> Unify<N, T> for Unordered<P>
+where
+    P: Unify<N, T>,
+{
+    fn unify(
+        &self,
+        other: &Unordered<P>,
+        unifier: &mut Unifier<N, T>,
+    ) -> Result<(), UnifyError<N>> {
+        <[P]>::unify(&self.unsafe_patterns, &other.unsafe_patterns, unifier)
+    }
+
+    fn zonk(&mut self, unifier: &Unifier<N, T>) {
+        <[P]>::zonk(&mut self.unsafe_patterns, unifier);
+    }
+
+    fn visit_meta_vars(&self, on_meta_var: &mut impl FnMut(&MetaVar<N>)) {
+        <[P]>::visit_meta_vars(&self.unsafe_patterns, on_meta_var);
+    }
+
+    fn visit_mut_subterms(&mut self, on_subterm: &mut impl FnMut(&mut T)) {
+        <[P]>::visit_mut_subterms(&mut self.unsafe_patterns, on_subterm);
+    }
+}
+
+impl<P: Node> Node for Unordered<P> {
+    fn node_name(&self) -> &'static str {
+        "Unordered"
+    }
+
+    fn children(&self) -> Vec<&dyn Node> {
+        self.unsafe_patterns
+            .iter()
+            .map(|pattern| pattern as &dyn Node)
+            .collect()
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut dyn Node> {
+        self.unsafe_patterns
+            .iter_mut()
+            .map(|pattern| pattern as &mut dyn Node)
+            .collect()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl<P: PatternEqStrict> PatternEqStrict for Unordered<P> {
+    fn pattern_eq_strict(&self, other: &Unordered<P>) -> bool {
+        <[P]>::pattern_eq_strict(&self.unsafe_patterns, &other.unsafe_patterns)
+    }
+}
@@ -0,0 +1,93 @@
+use bound::BoundPattern;
+use var::Var;
+
+/// The construct that introduced a frame of binders
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum FrameKind {
+    /// The binders of a `Scope`'s pattern, bound in the body of the scope
+    Scope,
+    /// The binders of one of the patterns in a `Nest`, bound in the patterns
+    /// that follow it
+    Nest,
+    /// The binders of a `Rec` pattern, bound in the pattern itself
+    Rec,
+}
+
+/// The terms embedded in a pattern that should be walked
+///
+/// The terms that are outside of the scope of a pattern's binders are walked
+/// before entering the frame of the pattern's binders, and the terms that are
+/// inside their scope are walked afterwards.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PatternTerms {
+    /// Walk all of the terms embedded in the pattern
+    All,
+    /// Walk the terms that are outside of the scope of the pattern's binders,
+    /// for example `Embed` and `Outer`
+    Outer,
+    /// Walk the terms that are inside the scope of the pattern's binders, for
+    /// example `Inner`
+    Inner,
+}
+
+impl PatternTerms {
+    /// Returns `true` if the terms that are outside of the scope of the
+    /// pattern's binders should be walked
+    pub fn outer(self) -> bool {
+        self != PatternTerms::Inner
+    }
+
+    /// Returns `true` if the terms that are inside the scope of the pattern's
+    /// binders should be walked
+    pub fn inner(self) -> bool {
+        self != PatternTerms::Outer
+    }
+}
+
+/// Callbacks for walking the binding structure of terms and patterns
+///
+/// Frames of binders are entered and exited in a stack-like fashion, where the
+/// innermost frame corresponds to a `ScopeOffset` of zero for the variables
+/// that are visited while it is on the stack.
+pub trait Walker<N> {
+    /// Called when entering a frame containing the binders of `pattern`
+    fn enter_frame(&mut self, _kind: FrameKind, _pattern: &impl BoundPattern<N>) {}
+
+    /// Called when exiting the most recently entered frame of binders
+    fn exit_frame(&mut self, _kind: FrameKind) {}
+
+    /// Called before walking a pattern whose embedded terms skip the innermost
+    /// frame of binders, for example a `Shift`
+    ///
+    /// The innermost frame should be hidden until the corresponding call to
+    /// `exit_shift`.
+    fn enter_shift(&mut self) {}
+
+    /// Called after walking a pattern that was entered with `enter_shift`
+    fn exit_shift(&mut self) {}
+
+    /// Called on each variable that is encountered during the walk
+    fn on_var(&mut self, _var: &Var<N>) {}
+}
+
+/// Callbacks for walking the binding structure of terms and patterns, with
+/// mutable access to the binders and variables that are encountered
+///
+/// Frames of binders are entered and exited in the same way as for `Walker`.
+pub trait WalkerMut<N> {
+    /// Called when entering a frame containing the binders of `pattern`
+    fn enter_frame(&mut self, _kind: FrameKind, _pattern: &mut impl BoundPattern<N>) {}
+
+    /// Called when exiting the most recently entered frame of binders
+    fn exit_frame(&mut self, _kind: FrameKind) {}
+
+    /// Called before walking a pattern whose embedded terms skip the innermost
+    /// frame of binders, for example a `Shift`
+    fn enter_shift(&mut self) {}
+
+    /// Called after walking a pattern that was entered with `enter_shift`
+    fn exit_shift(&mut self) {}
+
+    /// Called on each variable that is encountered during the walk
+    fn on_var(&mut self, _var: &mut Var<N>) {}
+}
@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use binder::Binder;
+use bound::{BoundPattern, BoundTerm};
+use free_var::FreeVar;
+use node::Node;
+use var::Var;
+use walk::{Walker, WalkerMut};
+
+/// Compare two terms for alpha equivalence, returning the correspondence
+/// between their binders if they are alpha equivalent
+///
+/// Each pair contains a binder of `lhs`, along with the binder of `rhs` that
+/// it corresponds to. This is the same correspondence that `Scope::unbind2`
+/// uses when it gives the binders of two scopes the same fresh names. The
+/// binders are paired up by walking the structural views of the terms (see
+/// `Node`) in lock-step, following only the children that are compared for
+/// alpha equivalence, so the pairs are in the order that the binders are
+/// found in pre-order, and the binders in skipped fields are left out.
+pub fn term_eq_witness<N, T>(lhs: &T, rhs: &T) -> Option<Vec<(Binder<N>, Binder<N>)>>
+where
+    N: Clone + 'static,
+    T: BoundTerm<N> + Node,
+{
+    if !lhs.term_eq(rhs) {
+        return None;
+    }
+
+    let mut binders = Vec::new();
+    pair_binders(lhs, rhs, &mut binders)?;
+    Some(binders)
+}
+
+/// Compare two patterns for alpha equivalence, returning the correspondence
+/// between their binders if they are alpha equivalent
+///
+/// This includes the binders of the scopes in the terms embedded in the
+/// patterns. See `term_eq_witness` for more details.
+pub fn pattern_eq_witness<N, P>(lhs: &P, rhs: &P) -> Option<Vec<(Binder<N>, Binder<N>)>>
+where
+    N: Clone + 'static,
+    P: BoundPattern<N> + Node,
+{
+    if !lhs.pattern_eq(rhs) {
+        return None;
+    }
+
+    let mut binders = Vec::new();
+    pair_binders(lhs, rhs, &mut binders)?;
+    Some(binders)
+}
+
+/// Walk two nodes in lock-step, pairing up the binders that are found in the
+/// same positions, and returning `None` if the nodes have different shapes
+fn pair_binders<N>(
+    lhs: &dyn Node,
+    rhs: &dyn Node,
+    binders: &mut Vec<(Binder<N>, Binder<N>)>,
+) -> Option<()>
+where
+    N: Clone + 'static,
+{
+    match (
+        lhs.downcast_ref::<Binder<N>>(),
+        rhs.downcast_ref::<Binder<N>>(),
+    ) {
+        (Some(lhs_binder), Some(rhs_binder)) => {
+            binders.push((lhs_binder.clone(), rhs_binder.clone()));
+            Some(())
+        },
+        (None, None) => {
+            let lhs_children = lhs.eq_children();
+            let rhs_children = rhs.eq_children();
+            if lhs.node_name() != rhs.node_name() || lhs_children.len() != rhs_children.len() {
+                return None;
+            }
+            for (lhs_child, rhs_child) in <_>::zip(lhs_children.into_iter(), rhs_children) {
+                pair_binders(lhs_child, rhs_child, binders)?;
+            }
+            Some(())
+        },
+        (_, _) => None,
+    }
+}
+
+/// Compare two terms for alpha equivalence up to a renaming of their free
+/// variables, returning the renaming if there is one
+///
+/// This is useful for comparing terms that were opened separately, for example
+/// the bodies of two scopes that were unbound with `Scope::unbind` rather than
+/// `Scope::unbind2`. The renaming maps each of the free variables in `lhs` to
+/// the free variable in `rhs` that it corresponds to, and is a bijection.
+pub fn term_eq_renaming<N, T>(lhs: &T, rhs: &T) -> Option<HashMap<FreeVar<N>, FreeVar<N>>>
+where
+    N: Clone + Eq + Hash,
+    T: Clone + BoundTerm<N>,
+{
+    let mut lhs_vars = FreeVarOccurrences(Vec::new());
+    let mut rhs_vars = FreeVarOccurrences(Vec::new());
+    lhs.walk_term(&mut lhs_vars);
+    rhs.walk_term(&mut rhs_vars);
+
+    if lhs_vars.0.len() != rhs_vars.0.len() {
+        return None;
+    }
+
+    // Pair up the occurrences of the free variables, making sure that each
+    // variable is always paired with the same variable in both directions
+    let mut renaming = HashMap::new();
+    let mut inverse = HashMap::new();
+    for (lhs_var, rhs_var) in <_>::zip(lhs_vars.0.into_iter(), rhs_vars.0) {
+        let lhs_target = renaming
+            .entry(lhs_var.clone())
+            .or_insert_with(|| rhs_var.clone());
+        let rhs_target = inverse
+            .entry(rhs_var.clone())
+            .or_insert_with(|| lhs_var.clone());
+        if *lhs_target != rhs_var || *rhs_target != lhs_var {
+            return None;
+        }
+    }
+
+    let mut renamed = lhs.clone();
+    renamed.walk_mut_term(&mut FreeVarRenamer(&renaming));
+
+    if renamed.term_eq(rhs) {
+        Some(renaming)
+    } else {
+        None
+    }
+}
+
+/// Collects each occurrence of a free variable that is found when walking a
+/// term, in order
+struct FreeVarOccurrences<N>(Vec<FreeVar<N>>);
+
+impl<N: Clone> Walker<N> for FreeVarOccurrences<N> {
+    fn on_var(&mut self, var: &Var<N>) {
+        if let Var::Free(ref free_var) = *var {
+            self.0.push(free_var.clone());
+        }
+    }
+}
+
+/// Renames the free variables that are found when walking a term
+pub(crate) struct FreeVarRenamer<'a, N: 'a>(pub(crate) &'a HashMap<FreeVar<N>, FreeVar<N>>);
+
+impl<'a, N> WalkerMut<N> for FreeVarRenamer<'a, N>
+where
+    N: Clone + Eq + Hash,
+{
+    fn on_var(&mut self, var: &mut Var<N>) {
+        if let Var::Free(ref mut free_var) = *var {
+            if let Some(new_free_var) = self.0.get(free_var) {
+                *free_var = new_free_var.clone();
+            }
+        }
+    }
+}